@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+// Per-value data of one or more states, kept in parallel columns
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StateData<V> {
+    pub values: Vec<V>,    // Values in the search's numeric representation
+    pub chains: Vec<usize>, // Operations each value's flow has passed through
+    pub origins: Vec<u64>,  // Inputs each value's flow came from, one bit per input
+    pub sources: Vec<usize>, // Input each value's flow came from predominantly
+}
+
+impl<V: Copy> StateData<V> {
+    pub fn new() -> StateData<V> {
+        StateData { values: Vec::new(), chains: Vec::new(), origins: Vec::new(), sources: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.chains.clear();
+        self.origins.clear();
+        self.sources.clear();
+    }
+
+    pub fn extend(&mut self, other: &StateData<V>) {
+        self.values.extend_from_slice(&other.values);
+        self.chains.extend_from_slice(&other.chains);
+        self.origins.extend_from_slice(&other.origins);
+        self.sources.extend_from_slice(&other.sources);
+    }
+
+    // Copy `len` entries starting at `start` into `state`, replacing its contents
+    pub fn copy_range(&self, start: usize, len: usize, state: &mut StateData<V>) {
+        let range = start..start + len;
+        state.clear();
+        state.values.extend_from_slice(&self.values[range.clone()]);
+        state.chains.extend_from_slice(&self.chains[range.clone()]);
+        state.origins.extend_from_slice(&self.origins[range.clone()]);
+        state.sources.extend_from_slice(&self.sources[range]);
+    }
+
+    pub fn push(&mut self, value: V, chain: usize, origin: u64, source: usize) {
+        self.values.push(value);
+        self.chains.push(chain);
+        self.origins.push(origin);
+        self.sources.push(source);
+    }
+}
+
+// Where one state's values sit in the arena
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Slot {
+    start: usize,
+    len: usize,
+}
+
+// Bump storage for the states of every node a search creates. Nodes only hold a `Slot`, so pushing
+// a node costs no allocation of its own, and everything is freed at once when the search is dropped
+#[derive(Debug)]
+pub(crate) struct Arena<V> {
+    data: StateData<V>,
+}
+
+impl<V: Copy> Arena<V> {
+    pub fn new() -> Arena<V> {
+        Arena { data: StateData::new() }
+    }
+
+    // Values stored, across all states
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn alloc(&mut self, state: &StateData<V>) -> Slot {
+        let slot = Slot { start: self.data.len(), len: state.len() };
+        self.data.extend(state);
+        slot
+    }
+
+    // Copy a stored state out, so it can be read while further states are allocated
+    pub fn load(&self, slot: Slot, state: &mut StateData<V>) {
+        self.data.copy_range(slot.start, slot.len, state);
+    }
+}
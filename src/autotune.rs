@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use crate::{search_solutions, Objective, SearchControl, SearchOptions, SearchStats, SolutionSet, Strategy};
+
+// Deepest the tuner goes when the caller leaves the depth to it
+pub const MAX_DEPTH: usize = 12;
+// Depth of the first round; each round that hits the depth limit goes two deeper
+const START_DEPTH: usize = 4;
+const START_WIDTH: usize = 64;
+// Beam width past which a round is left unbounded
+const MAX_WIDTH: usize = 65_536;
+// Below this much time left, another round is not worth starting
+const MIN_ROUND: Duration = Duration::from_millis(5);
+
+// One search the tuner ran: the settings it chose and how they fared
+#[derive(Clone, Debug)]
+pub struct Round {
+    pub strategy: Strategy,
+    pub max_depth: usize,
+    pub elapsed: Duration,
+    pub expanded: u64,
+    pub found: Option<usize>, // Steps of the shortest plan found
+    pub timed_out: bool,
+}
+
+// Everything the rounds found, ranked as `search_solutions` would, with their statistics combined
+#[derive(Clone, Debug)]
+pub struct TunedRun {
+    pub solutions: SolutionSet,
+    pub stats: SearchStats,
+    pub rounds: Vec<Round>,
+}
+
+// Search within `budget`, picking the settings as it goes rather than up front. It first looks for any
+// plan with a narrow beam at a shallow depth, widening the beam when it pruned and deepening when the
+// depth limit cut it short; a round that runs out of its share of time hands the rest to plain greedy
+// search, the quickest to reach some plan. Once a plan is found, the remaining time goes to shorter ones:
+// weighted searches below its length, whose step cost is halved, making them greedier, whenever a round
+// runs out of time. Each round gets half the time left, so there is always a next one to adapt into.
+// `options.max_depth` is the deepest any round goes, and `options.strategy` is ignored
+pub fn solve_within(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions, budget: Duration, limit: usize) -> TunedRun {
+    let start_time = Instant::now();
+    let deadline = start_time + options.timeout.map_or(budget, |timeout| timeout.min(budget));
+    let cancelled = || options.control.as_ref().is_some_and(SearchControl::is_cancelled);
+    let objectives: Vec<Objective> = options.objectives.iter().copied().chain([Objective::Steps, Objective::Deviation]).collect();
+
+    let mut solutions = SolutionSet::new();
+    let mut rounds = Vec::new();
+    let mut stats = SearchStats::default();
+    let mut expanded = 0;
+    let mut depth = START_DEPTH.min(options.max_depth);
+    let mut width = Some(START_WIDTH);
+    let mut step_cost = (target / 10.0).max(can_be_off_by);
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left < MIN_ROUND || cancelled() {
+            break;
+        }
+        let shortest = solutions.iter().map(|solution| solution.steps.len()).min();
+        let (strategy, max_depth) = match shortest {
+            Some(1) => break,
+            Some(steps) => (Strategy::Weighted { step_cost }, steps - 1),
+            None => (width.map_or(Strategy::Greedy, |width| Strategy::Beam { width }), depth),
+        };
+        // The last setting there is to try gets all the time left
+        let last = shortest.is_none() && width.is_none() && depth == options.max_depth;
+        let mut round_options = options.clone();
+        round_options.strategy = strategy;
+        round_options.max_depth = max_depth;
+        round_options.timeout = Some(if last { left } else { left / 2 });
+        let round_start = Instant::now();
+        let (found, round_stats) = search_solutions(inputs.clone(), target, can_be_off_by, &round_options, limit);
+        expanded += round_stats.expanded;
+        rounds.push(Round {
+            strategy,
+            max_depth,
+            elapsed: round_start.elapsed(),
+            expanded: round_stats.expanded,
+            found: found.iter().map(|solution| solution.steps.len()).min(),
+            timed_out: round_stats.timed_out,
+        });
+        let (found_any, timed_out) = (!found.is_empty(), round_stats.timed_out);
+        for solution in found.into_vec() {
+            solutions.insert(solution);
+        }
+        stats = round_stats;
+
+        match (shortest, found_any, timed_out) {
+            // A shorter plan: look for one shorter still
+            (Some(_), true, _) => {}
+            (Some(_), false, true) => step_cost /= 2.0,
+            // Every plan below the best one's length was tried: there is none
+            (Some(_), false, false) => break,
+            (None, true, _) => {}
+            (None, false, true) if last => break,
+            (None, false, true) => {
+                width = None;
+                depth = options.max_depth;
+            }
+            (None, false, false) => {
+                let deepen = stats.depth_limited > 0 && depth < options.max_depth;
+                let widen = width.is_some() && stats.beam_pruned > 0;
+                if !deepen && !widen {
+                    // Nothing was cut short, so no setting finds a plan
+                    break;
+                }
+                if deepen {
+                    depth = (depth + 2).min(options.max_depth);
+                }
+                if widen {
+                    width = width.map(|width| width * 4).filter(|&width| width <= MAX_WIDTH);
+                }
+            }
+        }
+    }
+
+    solutions.rank(&objectives);
+    let solutions: SolutionSet = solutions.into_iter().take(limit).collect();
+    stats.expanded = expanded;
+    stats.timed_out = solutions.is_empty() && deadline <= Instant::now();
+    TunedRun { solutions, stats, rounds }
+}
@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::presets::PRESETS;
+use crate::SearchOptions;
+
+// A case counts as slower only if its mean grew by this fraction...
+const SLOWDOWN_THRESHOLD: f64 = 0.05;
+// ...and Welch's t statistic clears this, about 95% confidence for five runs each
+const CRITICAL_T: f64 = 2.3;
+
+// One `bench` invocation: every case's timings in seconds, one per run
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchRun {
+    pub timestamp: u64, // Seconds since the Unix epoch
+    pub version: String,
+    pub samples: Vec<(String, Vec<f64>)>,
+}
+
+// A case that got significantly slower than the baseline
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    pub case: String,
+    pub baseline: f64, // Mean seconds
+    pub current: f64,
+    pub t: f64,
+}
+
+impl BenchRun {
+    // Time every preset `runs` times with the given options, under the preset's own constraints. The
+    // presets run from trivial to a few hundred milliseconds, so a slowdown in any part of the search
+    // shows up in at least one of them
+    pub fn measure(options: &SearchOptions, runs: usize) -> BenchRun {
+        let samples = PRESETS
+            .iter()
+            .map(|preset| {
+                let problem = preset.problem();
+                let times = (0..runs)
+                    .map(|_| {
+                        let start_time = Instant::now();
+                        problem.solve(options);
+                        start_time.elapsed().as_secs_f64()
+                    })
+                    .collect();
+                (preset.name.to_string(), times)
+            })
+            .collect();
+        BenchRun {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            samples,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut samples = BTreeMap::new();
+        for (case, times) in &self.samples {
+            samples.insert(case.clone(), Json::Array(times.iter().map(|&time| Json::Number(time)).collect()));
+        }
+        let mut fields = BTreeMap::new();
+        fields.insert("timestamp".to_string(), Json::Number(self.timestamp as f64));
+        fields.insert("version".to_string(), Json::String(self.version.clone()));
+        fields.insert("samples".to_string(), Json::Object(samples));
+        Json::Object(fields)
+    }
+
+    pub fn from_json(document: &Json) -> Result<BenchRun, String> {
+        let mut samples = Vec::new();
+        for (case, times) in document.get("samples").and_then(Json::as_object).ok_or("missing object \"samples\"")? {
+            let times = times
+                .as_array()
+                .ok_or_else(|| format!("samples of {} must be an array", case))?
+                .iter()
+                .map(|time| time.as_f64().ok_or_else(|| format!("non-numeric sample for {}", case)))
+                .collect::<Result<Vec<f64>, String>>()?;
+            samples.push((case.clone(), times));
+        }
+        Ok(BenchRun {
+            timestamp: document.get("timestamp").and_then(Json::as_f64).ok_or("missing number \"timestamp\"")? as u64,
+            version: document.get("version").and_then(Json::as_str).unwrap_or("").to_string(),
+            samples,
+        })
+    }
+
+    // Every case that is both noticeably and significantly slower than in `baseline`
+    pub fn regressions(&self, baseline: &BenchRun) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (case, times) in &self.samples {
+            let Some((_, before)) = baseline.samples.iter().find(|(name, _)| name == case) else { continue };
+            let (current, baseline) = (mean(times), mean(before));
+            let spread = (variance(times) / times.len() as f64 + variance(before) / before.len() as f64).sqrt();
+            let t = if spread > 0.0 { (current - baseline) / spread } else { 0.0 };
+            if current > baseline * (1.0 + SLOWDOWN_THRESHOLD) && t > CRITICAL_T {
+                regressions.push(Regression { case: case.clone(), baseline, current, t });
+            }
+        }
+        regressions
+    }
+}
+
+// Every run recorded in a history file, oldest first; a missing file is an empty history
+pub fn load_history(path: &str) -> Result<Vec<BenchRun>, Error> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(source) if source.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => return Err(Error::Io { path: path.to_string(), source }),
+    };
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| {
+            json::parse(line)
+                .and_then(|document| BenchRun::from_json(&document))
+                .map_err(|message| Error::InvalidFile { path: path.to_string(), message: format!("run {}: {}", index + 1, message) })
+        })
+        .collect()
+}
+
+// Add a run to the end of a history file, one JSON document per line
+pub fn append_history(path: &str, run: &BenchRun) -> Result<(), Error> {
+    let io_error = |source| Error::Io { path: path.to_string(), source };
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(io_error)?;
+    writeln!(file, "{}", run.to_json()).map_err(io_error)
+}
+
+pub fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len().max(1) as f64
+}
+
+// Sample variance, zero for fewer than two samples
+fn variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(samples);
+    samples.iter().map(|sample| (sample - mean) * (sample - mean)).sum::<f64>() / (samples.len() - 1) as f64
+}
@@ -0,0 +1,128 @@
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+
+// Most priorities the bucket array spans at once; anything further from the lowest waits in `overflow`
+const MAX_SPAN: usize = 1 << 16;
+// Room left below a cost that moved the window down, as costs below the cheapest tend to keep coming
+const HEADROOM: i64 = (MAX_SPAN / 8) as i64;
+
+// Priority queue for the search frontier, bucketed by the integer f-cost (thousandths). Buckets sit in
+// an array indexed by `cost - base`, with a bitmap of the non-empty ones, so finding where an item goes
+// takes constant time and finding the next bucket to pop skips 64 empty buckets per step. Costs beyond
+// `MAX_SPAN` of the window wait unsorted in an overflow list until the window drains to them.
+// Within a bucket, items are popped greatest first by their own ordering (for nodes, the preferred tie
+// break), through a heap: style, preference and seeded tie breaks are not bounded integers, so that
+// order costs O(log k) in the bucket's size k
+#[derive(Debug)]
+pub(crate) struct BucketQueue<T> {
+    buckets: Vec<BinaryHeap<T>>,
+    occupied: Vec<u64>, // One bit per bucket, set while it holds anything
+    base: i64,          // Cost of `buckets[0]`
+    cursor: usize,      // No bucket below this index holds anything
+    overflow: Vec<(i64, T)>, // Items costing `base + MAX_SPAN` or more
+    len: usize,
+}
+
+impl<T: Ord> BucketQueue<T> {
+    pub fn new() -> BucketQueue<T> {
+        BucketQueue { buckets: Vec::new(), occupied: Vec::new(), base: 0, cursor: 0, overflow: Vec::new(), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn push(&mut self, cost: i64, item: T) {
+        if self.len == self.overflow.len() {
+            // Nothing in the window: start it at this cost, or a cheaper one still waiting
+            let lowest = self.overflow.iter().map(|&(waiting, _)| waiting).min().map_or(cost, |waiting| waiting.min(cost));
+            self.rebase(lowest);
+        } else if cost < self.base {
+            self.rebase(cost - HEADROOM);
+        }
+        self.len += 1;
+        self.place(cost, item);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        loop {
+            if let Some(index) = self.next_occupied() {
+                self.cursor = index;
+                let bucket = &mut self.buckets[index];
+                let item = bucket.pop();
+                if bucket.is_empty() {
+                    self.occupied[index / 64] &= !(1 << (index % 64));
+                }
+                self.len -= 1;
+                return item;
+            }
+            // Window used up: move it to the cheapest items still waiting
+            let lowest = self.overflow.iter().map(|&(cost, _)| cost).min()?;
+            self.rebase(lowest);
+        }
+    }
+
+    // The lowest non-empty bucket at or above the cursor
+    fn next_occupied(&self) -> Option<usize> {
+        let first = self.cursor / 64;
+        let below_cursor = (1u64 << (self.cursor % 64)) - 1;
+        let mut words = self.occupied.iter().enumerate().skip(first);
+        let (_, &word) = words.next()?;
+        let word = word & !below_cursor;
+        if word != 0 {
+            return Some(first * 64 + word.trailing_zeros() as usize);
+        }
+        words.find(|(_, &word)| word != 0).map(|(index, word)| index * 64 + word.trailing_zeros() as usize)
+    }
+
+    fn place(&mut self, cost: i64, item: T) {
+        let index = (cost - self.base) as usize;
+        if index >= MAX_SPAN {
+            self.overflow.push((cost, item));
+            return;
+        }
+        if index >= self.buckets.len() {
+            self.buckets.resize_with(index + 1, BinaryHeap::new);
+            self.occupied.resize(index / 64 + 1, 0);
+        }
+        self.buckets[index].push(item);
+        self.occupied[index / 64] |= 1 << (index % 64);
+        self.cursor = self.cursor.min(index);
+    }
+
+    // Start the window at `base`, moving buckets that no longer fit, and waiting items that now do
+    fn rebase(&mut self, base: i64) {
+        let old_base = self.base;
+        let mut waiting: Vec<(i64, T)> = core::mem::take(&mut self.overflow);
+        for (index, bucket) in self.buckets.iter_mut().enumerate().filter(|(_, bucket)| !bucket.is_empty()) {
+            waiting.extend(core::mem::take(bucket).into_iter().map(|item| (old_base + index as i64, item)));
+        }
+        self.occupied.iter_mut().for_each(|word| *word = 0);
+        self.base = base;
+        self.cursor = self.buckets.len();
+        for (cost, item) in waiting {
+            self.place(cost, item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pops_in_cost_order_across_the_window() {
+        let costs = [5, 3, 200_000, -70_000, 3, 9, 150_000, 4, -70_001];
+        let mut queue = BucketQueue::new();
+        for (order, &cost) in costs.iter().enumerate() {
+            // Greater items pop first within a bucket, as for nodes
+            queue.push(cost, (cost, order));
+        }
+        queue.push(100_000, (100_000, 9));
+        let popped: Vec<i64> = core::iter::from_fn(|| queue.pop()).map(|(cost, _)| cost).collect();
+        let mut expected: Vec<i64> = costs.iter().copied().chain([100_000]).collect();
+        expected.sort_unstable();
+        assert_eq!(popped, expected);
+        assert_eq!(queue.len(), 0);
+    }
+}
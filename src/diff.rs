@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+
+use crate::{Solution, Step};
+
+// Steps compare by operation and values rounded to the fixed-point precision, so reordered plans match
+fn step_key(step: &Step) -> String {
+    let round = |values: &[f64]| values.iter().map(|v| format!("{:.3}", v)).collect::<Vec<_>>().join(", ");
+    format!("{} [{}] -> [{}]", step.operation.name(), round(&step.consumed), round(&step.produced))
+}
+
+fn step_counts(solution: &Solution) -> BTreeMap<String, i64> {
+    let mut counts = BTreeMap::new();
+    for step in &solution.steps {
+        *counts.entry(step_key(step)).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Human-readable structural differences: outputs, remainder, steps added/removed and device count
+pub fn describe(old: &Solution, new: &Solution) -> Vec<String> {
+    let mut lines = Vec::new();
+    if old.outputs != new.outputs {
+        lines.push(format!("Final Outputs: {:?} -> {:?}", old.outputs, new.outputs));
+    }
+    if old.remainder != new.remainder {
+        lines.push(format!("Remainder: {:?} -> {:?}", old.remainder, new.remainder));
+    }
+
+    let old_steps = step_counts(old);
+    let new_steps = step_counts(new);
+    for (key, &count) in &old_steps {
+        for _ in 0..(count - new_steps.get(key).copied().unwrap_or(0)) {
+            lines.push(format!("- {}", key));
+        }
+    }
+    for (key, &count) in &new_steps {
+        for _ in 0..(count - old_steps.get(key).copied().unwrap_or(0)) {
+            lines.push(format!("+ {}", key));
+        }
+    }
+
+    // Every step is one splitter/merger to build
+    if old.steps.len() != new.steps.len() {
+        let delta = new.steps.len() as i64 - old.steps.len() as i64;
+        lines.push(format!("Devices: {} -> {} ({:+})", old.steps.len(), new.steps.len(), delta));
+    }
+    lines
+}
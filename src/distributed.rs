@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::problem::Problem;
+use crate::render;
+use crate::{search_solutions, PrunePredicate, SearchOptions, SearchStats, Solution, State};
+
+// How often a worker tells the coordinator how far it has got
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+// Solving one problem on several machines. The coordinator hands every worker the problem document
+// and a part number; each worker searches only the plans whose first operation lands in its part, so
+// between them they cover the whole search once. Messages are JSON objects, one per line:
+//   coordinator -> worker  {"problem": {...}, "part": 0, "parts": 4}, later {"stop": true}
+//   worker -> coordinator  {"expanded": 1200} every second, then {"done": true, "expanded": 5000, "plan": {...} or null}
+// The first plan any worker finds wins and the others are told to stop. Search flags are each
+// worker's own, so every machine may tune its own time and memory limits
+
+// Whether a state belongs to `part` of `parts`: states one operation from the inputs are dealt out by a
+// hash of their values, which every machine computes alike, and everything below follows its ancestor
+pub fn in_part(state: &State, part: usize, parts: usize) -> bool {
+    if state.depth != 1 || parts <= 1 {
+        return true;
+    }
+    let mut values: Vec<i64> = state.values.iter().map(|&value| (value * 1000.0).round() as i64).collect();
+    values.sort_unstable();
+    // FNV-1a, so the partition does not depend on the standard library's randomly keyed hasher
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in values.iter().flat_map(|value| value.to_le_bytes()) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash % parts as u64 == part as u64
+}
+
+// What the coordinator hears from its workers while they search
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkerEvent {
+    Joined { worker: usize, peer: String },
+    Progress { worker: usize, expanded: u64 },
+    Finished { worker: usize, expanded: u64, found: bool },
+    Lost { worker: usize }, // Hung up without reporting
+}
+
+// How one worker's share of the search went
+#[derive(Clone, Debug, Default)]
+pub struct WorkerResult {
+    pub peer: String,
+    pub expanded: u64,
+    pub plan: Option<Solution>,
+    pub lost: bool,
+}
+
+// Every worker's outcome, by part number, and the part whose plan came in first
+#[derive(Clone, Debug)]
+pub struct DistributedRun {
+    pub workers: Vec<WorkerResult>,
+    pub winner: Option<usize>,
+}
+
+impl DistributedRun {
+    pub fn plan(&self) -> Option<&Solution> {
+        self.winner.and_then(|winner| self.workers[winner].plan.as_ref())
+    }
+
+    pub fn expanded(&self) -> u64 {
+        self.workers.iter().map(|worker| worker.expanded).sum()
+    }
+}
+
+fn send(stream: &mut TcpStream, fields: BTreeMap<String, Json>) -> std::io::Result<()> {
+    writeln!(stream, "{}", Json::Object(fields))
+}
+
+fn message(line: &str) -> Option<Json> {
+    json::parse(line.trim()).ok()
+}
+
+// Wait on `address` for `workers` workers, split the problem between them and collect their answers,
+// reporting each event to `on_event` as it arrives
+pub fn coordinate(address: &str, document: &Json, workers: usize, mut on_event: impl FnMut(WorkerEvent)) -> Result<DistributedRun, Error> {
+    let io_error = |source| Error::Io { path: address.to_string(), source };
+    // Refuse a bad problem here rather than once on every worker
+    Problem::from_json(document)?;
+    let listener = TcpListener::bind(address).map_err(io_error)?;
+    let mut streams = Vec::new();
+    let mut results = Vec::new();
+    while streams.len() < workers {
+        let (mut stream, peer) = listener.accept().map_err(io_error)?;
+        let mut job = BTreeMap::new();
+        job.insert("problem".to_string(), document.clone());
+        job.insert("part".to_string(), Json::Number(streams.len() as f64));
+        job.insert("parts".to_string(), Json::Number(workers as f64));
+        // A worker that hangs up before taking its job leaves the place for the next one
+        if send(&mut stream, job).is_err() {
+            continue;
+        }
+        on_event(WorkerEvent::Joined { worker: streams.len(), peer: peer.to_string() });
+        results.push(WorkerResult { peer: peer.to_string(), ..WorkerResult::default() });
+        streams.push(stream);
+    }
+
+    let mut winner = None;
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for (worker, stream) in streams.iter().enumerate() {
+            let (reader, sender) = (stream.try_clone(), sender.clone());
+            scope.spawn(move || {
+                let Ok(reader) = reader else {
+                    let _ = sender.send((worker, None));
+                    return;
+                };
+                for line in BufReader::new(reader).lines() {
+                    let Some(message) = line.ok().as_deref().and_then(message) else { break };
+                    let done = message.get("done").is_some();
+                    let _ = sender.send((worker, Some(message)));
+                    if done {
+                        return;
+                    }
+                }
+                let _ = sender.send((worker, None));
+            });
+        }
+        drop(sender);
+
+        for (worker, message) in receiver {
+            let Some(message) = message else {
+                let _ = streams[worker].shutdown(Shutdown::Both);
+                results[worker].lost = true;
+                on_event(WorkerEvent::Lost { worker });
+                continue;
+            };
+            let expanded = message.get("expanded").and_then(Json::as_f64).unwrap_or(0.0) as u64;
+            results[worker].expanded = expanded;
+            if message.get("done").is_none() {
+                on_event(WorkerEvent::Progress { worker, expanded });
+                continue;
+            }
+            let plan = message.get("plan").and_then(|plan| render::from_json(plan).ok());
+            on_event(WorkerEvent::Finished { worker, expanded, found: plan.is_some() });
+            if plan.is_some() && winner.is_none() {
+                winner = Some(worker);
+                for (other, stream) in streams.iter_mut().enumerate() {
+                    if other != worker {
+                        let _ = send(stream, BTreeMap::from([("stop".to_string(), Json::Bool(true))]));
+                    }
+                }
+            }
+            results[worker].plan = plan;
+            // Hanging up lets the worker's listener finish
+            let _ = streams[worker].shutdown(Shutdown::Both);
+        }
+    });
+    Ok(DistributedRun { workers: results, winner })
+}
+
+// Join the coordinator at `address`, search the part it assigns and send back what was found.
+// `base` supplies the search settings the problem does not
+pub fn work(address: &str, base: &SearchOptions) -> Result<(Option<Solution>, SearchStats), Error> {
+    let io_error = |source| Error::Io { path: address.to_string(), source };
+    let mut stream = TcpStream::connect(address).map_err(io_error)?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(io_error)?);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(io_error)?;
+    let invalid = |message: &str| Error::InvalidFile { path: address.to_string(), message: message.to_string() };
+    let job = message(&line).ok_or_else(|| invalid("the coordinator sent no job"))?;
+    let problem = Problem::from_json(job.get("problem").ok_or_else(|| invalid("the job has no problem"))?)?;
+    let part = job.get("part").and_then(Json::as_f64).ok_or_else(|| invalid("the job has no part"))? as usize;
+    let parts = job.get("parts").and_then(Json::as_f64).ok_or_else(|| invalid("the job has no parts"))? as usize;
+
+    let mut options = problem.search_options(base);
+    let control = options.control.clone().unwrap_or_default();
+    options.control = Some(control.clone());
+    // Everything outside this worker's part is pruned, along with whatever the caller prunes already
+    let prune = options.prune.take();
+    options.prune = Some(PrunePredicate::new(move |state: &State| {
+        !in_part(state, part, parts) || prune.as_ref().is_some_and(|prune| (prune.0)(state))
+    }));
+
+    thread::scope(|scope| {
+        // A stop message, or the coordinator going away, ends the search early
+        let listener = control.clone();
+        scope.spawn(move || {
+            for line in reader.lines() {
+                match line.ok().as_deref().and_then(message) {
+                    Some(message) if message.get("stop").is_none() => {}
+                    _ => break,
+                }
+            }
+            listener.cancel();
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let options = &options;
+        let problem = &problem;
+        scope.spawn(move || {
+            let _ = sender.send(search_solutions(problem.inputs.clone(), problem.target, problem.tolerance, options, 1));
+        });
+        let (solutions, stats) = loop {
+            match receiver.recv_timeout(PROGRESS_INTERVAL) {
+                Ok(result) => break result,
+                Err(_) => {
+                    let _ = send(&mut stream, BTreeMap::from([("expanded".to_string(), Json::Number(control.expanded() as f64))]));
+                }
+            }
+        };
+        let plan = solutions.into_iter().next();
+        let mut done = BTreeMap::new();
+        done.insert("done".to_string(), Json::Bool(true));
+        done.insert("expanded".to_string(), Json::Number(stats.expanded as f64));
+        done.insert("plan".to_string(), plan.as_ref().map_or(Json::Null, render::to_json));
+        let sent = send(&mut stream, done);
+        // The coordinator hangs up once it has the answer, which ends the listener
+        let _ = stream.shutdown(Shutdown::Write);
+        sent.map_err(io_error)?;
+        Ok((plan, stats))
+    })
+}
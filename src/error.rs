@@ -0,0 +1,67 @@
+use std::fmt;
+use std::io;
+
+use crate::SCALE;
+
+// Everything that can go wrong between reading arguments or files and starting a search,
+// each carrying enough context to say what to fix
+#[derive(Debug)]
+pub enum Error {
+    MissingValue { flag: String },
+    InvalidValue { what: String, text: String, expected: &'static str },
+    InvalidInput { index: usize, text: String }, // `index` counts from zero
+    UnknownName { kind: &'static str, name: String, expected: &'static str },
+    Overflow { value: f64, depth: usize }, // A rate too large for fixed-point, first reachable at `depth`
+    Io { path: String, source: io::Error },
+    InvalidFile { path: String, message: String },
+    InvalidProblem(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::MissingValue { flag } => write!(f, "{} needs a value", flag),
+            Error::InvalidValue { what, text, expected } => write!(f, "invalid {} \"{}\": expected {}", what, text, expected),
+            Error::InvalidInput { index, text } => {
+                write!(f, "input {} (\"{}\") is not a number; inputs are comma-separated rates like 60,45, 4x120 or 780/2", index + 1, text)
+            }
+            Error::UnknownName { kind, name, expected } => write!(f, "unknown {} \"{}\" (expected {})", kind, name, expected),
+            Error::Overflow { value, depth } => write!(
+                f,
+                "rate {} at depth {} is too large to represent (limit {}); use smaller units",
+                value,
+                depth,
+                i64::MAX as f64 / SCALE
+            ),
+            Error::Io { path, source } => write!(f, "cannot read {}: {}", path, source),
+            Error::InvalidFile { path, message } => write!(f, "{} is not valid: {}", path, message),
+            Error::InvalidProblem(message) => write!(f, "invalid problem: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Fixed-point values saturate silently, so refuse rates whose merges could leave the representable range.
+// Merging the inputs pairwise, the total is reachable after inputs - 1 combines at most
+pub fn check_range(inputs: &[f64]) -> Result<(), Error> {
+    let limit = i64::MAX as f64 / SCALE;
+    if let Some(&value) = inputs.iter().find(|v| v.abs() > limit) {
+        return Err(Error::Overflow { value, depth: 0 });
+    }
+    let mut total = 0.0;
+    for (depth, &value) in inputs.iter().enumerate() {
+        total += value;
+        if total.abs() > limit {
+            return Err(Error::Overflow { value: total, depth });
+        }
+    }
+    Ok(())
+}
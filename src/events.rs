@@ -0,0 +1,95 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{
+    search_solutions, EventSink, Objective, Search, SearchControl, SearchEvent, SearchOptions, SearchStats, Solution, SolutionSet,
+};
+
+// Run a search on its own thread, streaming its events through a channel as they happen.
+// Iterate the receiver to animate the search; join the handle for the final result.
+pub fn stream_events(
+    inputs: Vec<f64>,
+    target: f64,
+    can_be_off_by: f64,
+    options: &SearchOptions,
+    limit: usize,
+) -> (Receiver<SearchEvent>, JoinHandle<(SolutionSet, SearchStats)>) {
+    let (sender, receiver) = mpsc::channel();
+    let mut options = options.clone();
+    options.events = Some(EventSink(Arc::new(move |event: &SearchEvent| {
+        // A dropped receiver just means nobody is watching any more
+        let _ = sender.send(event.clone());
+    })));
+
+    let handle = thread::spawn(move || search_solutions(inputs, target, can_be_off_by, &options, limit));
+    (receiver, handle)
+}
+
+// What a GUI hears about a search running in the background
+#[derive(Clone, Debug)]
+pub enum ProgressEvent {
+    // Sent every interval while the search runs
+    Progress { elapsed: Duration, expanded: u64, frontier: u64, best_deviation: Option<f64> },
+    // A new distinct plan, as soon as it is found
+    Found(Solution),
+    // The search is over; the handle holds the ranked plans
+    Finished { elapsed: Duration, stats: Box<SearchStats> },
+}
+
+// Run a search on its own thread, reporting progress at most once per `interval` instead of on every
+// expansion, so a GUI can poll the receiver with `try_recv` from its event loop without falling behind.
+// Put a `SearchControl` in the options to cancel the search from the GUI
+pub fn stream_progress(
+    inputs: Vec<f64>,
+    target: f64,
+    can_be_off_by: f64,
+    options: &SearchOptions,
+    limit: usize,
+    interval: Duration,
+) -> (Receiver<ProgressEvent>, JoinHandle<(SolutionSet, SearchStats)>) {
+    let (sender, receiver) = mpsc::channel();
+    let mut options = options.clone();
+    let control = options.control.get_or_insert_with(SearchControl::new).clone();
+
+    let handle = thread::spawn(move || {
+        let started = Instant::now();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let ticker = {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                // Waiting on the stop channel rather than sleeping lets the ticker end as soon as the search does
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let event = ProgressEvent::Progress {
+                        elapsed: started.elapsed(),
+                        expanded: control.expanded(),
+                        frontier: control.frontier(),
+                        best_deviation: control.best_deviation(),
+                    };
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+
+        let mut search: Search = Search::new(inputs, target, can_be_off_by, &options);
+        while search.found().len() < limit {
+            match search.next_solution() {
+                Some(solution) => {
+                    let _ = sender.send(ProgressEvent::Found(solution));
+                }
+                None => break,
+            }
+        }
+        let _ = stop.send(());
+        let _ = ticker.join();
+
+        let (mut solutions, stats) = search.finish();
+        solutions.rank(&[Objective::Steps, Objective::Deviation]);
+        let _ = sender.send(ProgressEvent::Finished { elapsed: started.elapsed(), stats: Box::new(stats.clone()) });
+        (solutions, stats)
+    });
+    (receiver, handle)
+}
@@ -0,0 +1,263 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Operation, SearchOptions, SCALE};
+
+// Most decimal places an input or target may have for the exact analysis
+const MAX_DECIMALS: u32 = 9;
+
+// A short reason no plan can exist, however deep the search is allowed to go
+#[derive(Clone, Debug, PartialEq)]
+pub enum Infeasibility {
+    // Splitting and merging never create flow, so no value exceeds the inputs' total
+    AboveTotal { lowest: f64, total: f64 },
+    // Every output takes its share of the inputs' total
+    TooManyOutputs { outputs: usize, lowest: f64, total: f64 },
+    // Every reachable value is a multiple of `unit` divided only by the split factors, but the
+    // target as a multiple of `unit` has `factor` in its denominator
+    Denominator { target: f64, unit: f64, numerator: u128, denominator: u128, factor: u128, splits: &'static str },
+    // Every accepted value needs more splits along one flow than the depth limit allows
+    TooDeep { needed: usize, max_depth: usize, unit: f64 },
+}
+
+impl fmt::Display for Infeasibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Infeasibility::AboveTotal { lowest, total } => {
+                write!(f, "even the lowest accepted value {} exceeds the inputs' total of {}; splits and merges never add flow", lowest, total)
+            }
+            Infeasibility::TooManyOutputs { outputs, lowest, total } => write!(
+                f,
+                "{} outputs of at least {} need {}, more than the inputs' total of {}",
+                outputs,
+                lowest,
+                *outputs as f64 * lowest,
+                total
+            ),
+            Infeasibility::Denominator { target, unit, numerator, denominator, factor, splits } => write!(
+                f,
+                "the target {} is {}/{} × {}, which needs a factor of {} in the denominator, but every reachable value is a multiple of {} divided only by {}",
+                target, numerator, denominator, unit, factor, unit, splits
+            ),
+            Infeasibility::TooDeep { needed, max_depth, unit } => write!(
+                f,
+                "every accepted value is a multiple of {} divided by at least {} successive splits, more than the depth limit of {}",
+                unit, needed, max_depth
+            ),
+        }
+    }
+}
+
+// Look for a proof that no plan reaches the target, for problems the search came back empty on.
+// Finding none says nothing: the checks are sufficient conditions only
+pub fn certificate(inputs: &[f64], target: f64, can_be_off_by: f64, options: &SearchOptions) -> Option<Infeasibility> {
+    if options.goal.is_some() {
+        return None;
+    }
+    let total: f64 = inputs.iter().sum();
+    let lowest = target - can_be_off_by;
+    if lowest > total {
+        return Some(Infeasibility::AboveTotal { lowest, total });
+    }
+    let outputs = options.output_count.unwrap_or(options.min_outputs);
+    if outputs > 1 && outputs as f64 * lowest > total {
+        return Some(Infeasibility::TooManyOutputs { outputs, lowest, total });
+    }
+    // The search stores thousandths and rounds at every level, so a value that close to the target
+    // matches it: with one within reach, the exact proofs below do not hold
+    let too_deep = depth_certificate(inputs, target, can_be_off_by, options);
+    too_deep.as_ref()?;
+    if can_be_off_by == 0.0 {
+        if let Some(proof) = denominator_certificate(inputs, target, options) {
+            return Some(proof);
+        }
+    }
+    too_deep
+}
+
+// Check a problem before searching it, so hopeless queries are answered at once. Unlike a failure
+// report, a best-effort search still wants to run, for the closest value it can find
+pub fn precheck(inputs: &[f64], target: f64, can_be_off_by: f64, options: &SearchOptions) -> Option<Infeasibility> {
+    if options.best_effort {
+        return None;
+    }
+    certificate(inputs, target, can_be_off_by, options)
+}
+
+// How serious a finding of `lint` is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,   // No plan can exist, or the problem is malformed
+    Warning, // A plan may exist, but probably not the one meant
+}
+
+// One finding of `lint`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+// Everything suspicious about a problem that can be told without searching it: malformed numbers,
+// rates finer than the search's thousandths, inputs no belt carries, and every proof `certificate`
+// finds that no plan exists. An empty list means nothing was found, not that a plan exists
+pub fn lint(inputs: &[f64], target: f64, can_be_off_by: f64, options: &SearchOptions) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    let mut report = |severity, message: String| found.push(Diagnostic { severity, message });
+    let granularity = 1.0 / SCALE;
+
+    if inputs.is_empty() {
+        report(Severity::Error, "there are no inputs".into());
+    }
+    for (index, &input) in inputs.iter().enumerate() {
+        if input <= 0.0 {
+            report(Severity::Error, format!("input {} is {}; inputs must be positive rates", index + 1, input));
+        } else if input < granularity {
+            report(Severity::Error, format!("input {} ({}) is below the search's granularity of {}, so it counts as nothing", index + 1, input, granularity));
+        } else if decimals(input).is_none_or(|places| places > 3) {
+            report(Severity::Warning, format!("input {} ({}) is finer than the search's granularity of {} and will be rounded", index + 1, input, granularity));
+        }
+        if let Some(capacity) = options.max_capacity().filter(|&capacity| input > capacity) {
+            report(Severity::Warning, format!("input {} ({}) already exceeds the largest capacity of {}", index + 1, input, capacity));
+        }
+    }
+    if target <= 0.0 && options.goal.is_none() {
+        report(Severity::Error, format!("the target is {}; it must be a positive rate", target));
+    } else if decimals(target).is_none_or(|places| places > 3) {
+        report(Severity::Warning, format!("the target {} is finer than the search's granularity of {} and will be rounded", target, granularity));
+    }
+    if can_be_off_by < 0.0 {
+        report(Severity::Error, format!("canBeOffBy is {}; it must not be negative", can_be_off_by));
+    } else if can_be_off_by > 0.0 && can_be_off_by < granularity {
+        report(Severity::Warning, format!("canBeOffBy {} is below the search's granularity of {}, so only exact results match", can_be_off_by, granularity));
+    }
+    if options.operations.is_empty() {
+        report(Severity::Error, "no operation is allowed, so nothing can change the inputs".into());
+    }
+    if options.max_depth == 0 {
+        report(Severity::Warning, "the depth limit is 0, so only the inputs as given can match".into());
+    }
+    if let Some(capacity) = options.max_capacity().filter(|&capacity| target - can_be_off_by > capacity) {
+        report(Severity::Error, format!("the target {} is above the largest capacity of {}, so no stream can carry it", target, capacity));
+    }
+    if inputs.iter().all(|&input| input > 0.0) && target > 0.0 && can_be_off_by >= 0.0 {
+        if let Some(proof) = certificate(inputs, target, can_be_off_by, options) {
+            report(Severity::Error, format!("{}", proof));
+        }
+    }
+    found
+}
+
+// A value that is unit × m / (2^a 3^b) in lowest terms needs a flow split in halves `a` times and one
+// split in thirds `b` times, so at least max(a, b) operations. If no value within tolerance gets by
+// with `max_depth`, no plan does. Fixed-point splits round, so the window is widened by a step per level
+fn depth_certificate(inputs: &[f64], target: f64, can_be_off_by: f64, options: &SearchOptions) -> Option<Infeasibility> {
+    let places = inputs.iter().map(|&input| decimals(input)).max()??;
+    let scale = 10u128.pow(places);
+    let numerators: Option<Vec<u128>> = inputs.iter().map(|&input| whole(input, scale)).collect();
+    let common = numerators?.into_iter().fold(0, gcd);
+    if common == 0 {
+        return None;
+    }
+    let unit = common as f64 / scale as f64;
+    let slack = options.max_depth as f64 / SCALE;
+    let (low, high) = (target - can_be_off_by - slack, target + can_be_off_by + slack);
+    let (halves, thirds, _) = split_factors(options);
+    let reachable = |a: u32, b: u32| {
+        let step = unit / (power(2.0, a) * power(3.0, b));
+        // Some multiple of the step falls inside the window
+        let first = whole(low.max(0.0) / step, 1).map_or(0, |nearest| if nearest as f64 * step < low { nearest + 1 } else { nearest });
+        first as f64 * step <= high
+    };
+    let depth = options.max_depth as u32;
+    let within = (0..=depth).any(|a| (0..=depth).any(|b| (halves || a == 0) && (thirds || b == 0) && reachable(a, b)));
+    if within {
+        return None;
+    }
+    let needed = (depth + 1..=depth + MAX_EXTRA_SPLITS)
+        .find(|&k| (0..=k).any(|a| (0..=k).any(|b| (halves || a == 0) && (thirds || b == 0) && reachable(a, b))))
+        .map_or(depth as usize + 1, |k| k as usize);
+    Some(Infeasibility::TooDeep { needed, max_depth: options.max_depth, unit })
+}
+
+// How far past the depth limit to look when reporting how many splits a target needs
+const MAX_EXTRA_SPLITS: u32 = 32;
+
+// Whole powers without `f64::powi`, which needs std
+fn power(base: f64, exponent: u32) -> f64 {
+    (0..exponent).fold(1.0, |power, _| power * base)
+}
+
+// Split factors the allowed operations provide, as a description for certificates
+fn split_factors(options: &SearchOptions) -> (bool, bool, &'static str) {
+    let allowed = |operation| options.operations.contains(&operation) && !options.operation_limits.contains(&(operation, 0));
+    let (halves, thirds) = (allowed(Operation::SplitTwo), allowed(Operation::SplitThree));
+    let splits = match (halves, thirds) {
+        (true, true) => "powers of 2 and 3",
+        (true, false) => "powers of 2",
+        (false, true) => "powers of 3",
+        (false, false) => "1, as no splits are allowed",
+    };
+    (halves, thirds, splits)
+}
+
+// With no tolerance, the target must be exactly unit × m / (2^a 3^b) for the inputs' greatest common
+// unit; any other prime in its denominator rules every depth out
+fn denominator_certificate(inputs: &[f64], target: f64, options: &SearchOptions) -> Option<Infeasibility> {
+    let scale = 10u128.pow(decimals(target)?.max(inputs.iter().map(|&input| decimals(input)).max()??));
+    let numerators: Option<Vec<u128>> = inputs.iter().map(|&input| whole(input, scale)).collect();
+    let common = numerators?.into_iter().fold(0, gcd);
+    let scaled_target = whole(target, scale)?;
+    if common == 0 || scaled_target == 0 {
+        return None;
+    }
+    let shared = gcd(scaled_target, common);
+    let (numerator, denominator) = (scaled_target / shared, common / shared);
+    let (halves, thirds, splits) = split_factors(options);
+    let mut factor = denominator;
+    for (allowed, prime) in [(halves, 2), (thirds, 3)] {
+        while allowed && factor % prime == 0 {
+            factor /= prime;
+        }
+    }
+    (factor > 1).then(|| Infeasibility::Denominator { target, unit: common as f64 / scale as f64, numerator, denominator, factor, splits })
+}
+
+// Decimal places needed to write `value` exactly, if it has few enough
+fn decimals(value: f64) -> Option<u32> {
+    (0..=MAX_DECIMALS).find(|&places| {
+        let scaled = value * 10u64.pow(places) as f64;
+        whole(scaled, 1).is_some_and(|nearest| (scaled - nearest as f64).abs() <= 1e-6 * scaled.max(1.0))
+    })
+}
+
+// `value × scale` rounded to a whole number, for non-negative values in range; `f64::round` needs std
+fn whole(value: f64, scale: u128) -> Option<u128> {
+    let scaled = value * scale as f64 + 0.5;
+    (value >= 0.0 && scaled < u128::MAX as f64).then_some(scaled as u128)
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_thirds_are_not_proved_impossible() {
+        // 100 / 3 is stored as 33.333, which the search matches exactly at level 1
+        let options = SearchOptions::default();
+        assert_eq!(certificate(&[100.0], 33.333, 0.0, &options), None);
+        assert_eq!(certificate(&[10.0], 3.333, 0.0, &options), None);
+    }
+
+    #[test]
+    fn unreachable_denominators_are_still_proved() {
+        let options = SearchOptions { max_depth: 2, ..SearchOptions::default() };
+        assert!(matches!(certificate(&[100.0], 20.0, 0.0, &options), Some(Infeasibility::Denominator { factor: 5, .. })));
+    }
+}
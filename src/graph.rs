@@ -0,0 +1,84 @@
+use crate::{Operation, Solution, SCALE};
+
+// Where a stream starts or ends in the flow graph
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    Input,
+    Operation(Operation),
+    Output,
+    Remainder,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphNode {
+    pub id: usize,
+    pub kind: NodeKind,
+    pub label: String,
+}
+
+// A stream carrying `rate` from one node to another
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub rate: f64,
+}
+
+// The plan as a graph: inputs feed operations, whose streams end as outputs or remainder
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlowGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+// Steps record values rather than stream identities, so consumed values are matched
+// against the open streams within the rounding introduced by fixed-point scaling
+fn same_rate(a: f64, b: f64) -> bool {
+    (a - b).abs() < 2.0 / SCALE
+}
+
+pub fn build(solution: &Solution) -> FlowGraph {
+    let mut graph = FlowGraph::default();
+    let mut open: Vec<(usize, f64)> = Vec::new(); // (source node, rate) of streams not yet consumed
+
+    for &input in &solution.inputs {
+        let id = graph.nodes.len();
+        graph.nodes.push(GraphNode { id, kind: NodeKind::Input, label: format!("{}", input) });
+        open.push((id, input));
+    }
+
+    for step in &solution.steps {
+        let id = graph.nodes.len();
+        graph.nodes.push(GraphNode { id, kind: NodeKind::Operation(step.operation), label: step.operation.name().to_string() });
+        for &value in &step.consumed {
+            // Prefer the most recently produced stream, mirroring how the search appends new values
+            if let Some(position) = open.iter().rposition(|&(_, rate)| same_rate(rate, value)) {
+                let (from, rate) = open.remove(position);
+                graph.edges.push(GraphEdge { from, to: id, rate });
+            }
+        }
+        for &value in &step.produced {
+            open.push((id, value));
+        }
+    }
+
+    let mut outputs = solution.outputs.clone();
+    for (from, rate) in open {
+        let kind = match outputs.iter().position(|&output| same_rate(output, rate)) {
+            Some(position) => {
+                outputs.remove(position);
+                NodeKind::Output
+            }
+            None => NodeKind::Remainder,
+        };
+        let id = graph.nodes.len();
+        let label = match kind {
+            NodeKind::Output => "output",
+            _ => "remainder",
+        };
+        graph.nodes.push(GraphNode { id, kind, label: label.to_string() });
+        graph.edges.push(GraphEdge { from, to: id, rate });
+    }
+
+    graph
+}
@@ -0,0 +1,64 @@
+use std::fs;
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::{Operation, Solution, Step, SCALE};
+
+// Steps of a layout already built, which every plan must start with: {"steps": [{"operation": "split3",
+// "consumed": [60]}, ...]}. A saved JSON solution works too; only its operations and consumed rates are
+// read, and what each step produces is worked out again
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub steps: Vec<Step>,
+}
+
+impl Hint {
+    pub fn from_json(document: &Json) -> Result<Hint, String> {
+        let mut steps = Vec::new();
+        for (index, step) in document.get("steps").and_then(Json::as_array).ok_or("missing array \"steps\"")?.iter().enumerate() {
+            let name = step.get("operation").and_then(Json::as_str).ok_or_else(|| format!("step {} has no operation", index + 1))?;
+            let operation = Operation::from_name(name).ok_or_else(|| format!("step {} has unknown operation {}", index + 1, name))?;
+            let consumed = step
+                .get("consumed")
+                .and_then(Json::as_array)
+                .ok_or_else(|| format!("step {} has no array \"consumed\"", index + 1))?
+                .iter()
+                .map(|rate| rate.as_f64().ok_or_else(|| format!("step {} consumes a non-numeric rate", index + 1)))
+                .collect::<Result<Vec<f64>, String>>()?;
+            if consumed.len() != operation.arity() {
+                return Err(format!("step {} is a {} and must consume {} rate(s), not {}", index + 1, name, operation.arity(), consumed.len()));
+            }
+            steps.push(Step::new(operation, consumed));
+        }
+        Ok(Hint { steps })
+    }
+
+    pub fn load(path: &str) -> Result<Hint, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        json::parse(&text)
+            .and_then(|document| Hint::from_json(&document))
+            .map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+
+    // The rates left once the hint's steps have run on `inputs`, which the search goes on from. Fails
+    // naming the first step that consumes a rate not available at that point
+    pub fn apply(&self, inputs: &[f64]) -> Result<Vec<f64>, String> {
+        let mut rates = inputs.to_vec();
+        for (index, step) in self.steps.iter().enumerate() {
+            for &consumed in &step.consumed {
+                // Rates are compared as the search stores them, in thousandths
+                let position = rates.iter().position(|&rate| (rate - consumed).abs() < 1.0 / SCALE);
+                let position = position.ok_or_else(|| format!("step {} ({}) needs {} but only {:?} are available", index + 1, step, consumed, rates))?;
+                rates.remove(position);
+            }
+            rates.extend_from_slice(&step.produced);
+        }
+        Ok(rates)
+    }
+
+    // A plan found from the hinted state, made whole: the hint's steps first, from the original inputs
+    pub fn complete(&self, inputs: Vec<f64>, completion: Solution) -> Solution {
+        let steps = self.steps.iter().cloned().chain(completion.steps).collect();
+        Solution { inputs, steps, ..completion }
+    }
+}
@@ -0,0 +1,69 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{search_solutions, GoalInfo, GoalPredicate, SearchOptions, Solution, State};
+
+// Most feeds an inverse search combines before giving up
+pub const MAX_FEEDS: usize = 6;
+
+impl GoalPredicate {
+    // Every desired output must be matched by its own stream, within `can_be_off_by`; anything
+    // left over is remainder
+    pub fn outputs(desired: Vec<f64>, can_be_off_by: f64) -> GoalPredicate {
+        GoalPredicate::new(move |state: &State| {
+            let mut outputs: Vec<usize> = Vec::new();
+            for &want in &desired {
+                // Closest unused stream first, so a near miss does not take another output's exact match
+                let index = (0..state.values.len())
+                    .filter(|index| !outputs.contains(index) && (state.values[*index] - want).abs() <= can_be_off_by)
+                    .min_by(|&a, &b| (state.values[a] - want).abs().total_cmp(&(state.values[b] - want).abs()))?;
+                outputs.push(index);
+            }
+            Some(GoalInfo { outputs })
+        })
+    }
+}
+
+// The mirror image of a normal search: given the outputs needed and the feed rates available
+// (each usable any number of times), find the smallest set of feeds that can be split and merged
+// into those outputs, and the plan doing it. Candidate feed sets are tried by ascending total,
+// then fewest feeds, so the first one that works wastes the least
+pub fn solve_inverse(desired: &[f64], feeds: &[f64], can_be_off_by: f64, options: &SearchOptions) -> Option<Solution> {
+    if desired.is_empty() || feeds.is_empty() {
+        return None;
+    }
+    let needed: f64 = desired.iter().sum::<f64>() - can_be_off_by * desired.len() as f64;
+    let mut candidates = feed_sets(feeds, MAX_FEEDS);
+    candidates.retain(|set| set.iter().sum::<f64>() >= needed);
+    candidates.sort_by(|a, b| a.iter().sum::<f64>().total_cmp(&b.iter().sum::<f64>()).then(a.len().cmp(&b.len())));
+
+    // The mean output steers the search; the predicate checks every output
+    let target = desired.iter().sum::<f64>() / desired.len() as f64;
+    let mut options = options.clone();
+    options.goal = Some(GoalPredicate::outputs(desired.to_vec(), can_be_off_by));
+    candidates.into_iter().find_map(|inputs| search_solutions(inputs, target, can_be_off_by, &options, 1).0.into_iter().next())
+}
+
+// Every multiset of up to `most` feeds, each listed largest first
+fn feed_sets(feeds: &[f64], most: usize) -> Vec<Vec<f64>> {
+    let mut kinds = feeds.to_vec();
+    kinds.sort_by(|a, b| b.total_cmp(a));
+    kinds.dedup();
+    let mut sets = Vec::new();
+    let mut pending = vec![(Vec::new(), 0)]; // A set so far and the first kind it may still add
+    while let Some((set, first)) = pending.pop() {
+        if set.len() == most {
+            sets.push(set);
+            continue;
+        }
+        for (kind, &rate) in kinds.iter().enumerate().skip(first) {
+            let mut longer: Vec<f64> = set.clone();
+            longer.push(rate);
+            pending.push((longer, kind));
+        }
+        if !set.is_empty() {
+            sets.push(set);
+        }
+    }
+    sets
+}
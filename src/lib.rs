@@ -0,0 +1,79 @@
+// Search for split/merge plans that turn input rates into outputs near a target.
+// The core search only needs `alloc`; everything touching files, clocks or stdout sits behind the
+// default `std` feature.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod arena;
+mod bucket_queue;
+pub mod feasibility;
+pub mod inverse;
+pub mod merge;
+pub mod rng;
+pub mod value;
+mod search;
+mod solution_set;
+mod state_key;
+mod tolerance;
+
+#[cfg(feature = "std")]
+pub mod autotune;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "parallel")]
+pub mod distributed;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "parallel")]
+pub mod events;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "std")]
+pub mod hint;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod memo;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod packs;
+#[cfg(feature = "std")]
+pub mod parse;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(feature = "parallel")]
+pub mod portfolio;
+#[cfg(feature = "std")]
+pub mod presets;
+#[cfg(feature = "std")]
+pub mod problem;
+#[cfg(feature = "std")]
+pub mod recipes;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod search_tree;
+#[cfg(feature = "parallel")]
+pub mod solve_async;
+#[cfg(feature = "std")]
+pub mod server;
+#[cfg(feature = "std")]
+pub mod solver;
+#[cfg(feature = "std")]
+pub mod trace;
+#[cfg(feature = "std")]
+pub mod warm_start;
+#[cfg(feature = "std")]
+pub mod websocket;
+
+pub use search::*;
+pub use solution_set::*;
+pub use tolerance::*;
@@ -1,264 +1,2419 @@
-use std::collections::{BinaryHeap, HashMap};
-use std::time::Instant;
-use std::cmp::Ordering;
+use std::time::{Duration, Instant};
 use std::env;
+use std::io::Read;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct Node {
-    values: Vec<i64>, // Scaled values as integers
-    path: Vec<String>, // Track operations
-    depth: usize,      // Track depth
-    estimated_cost: i64, // Used for priority in A* (difference from target)
+use calculate_outputs::autotune;
+use calculate_outputs::bench::{self, BenchRun};
+use calculate_outputs::error::Error;
+use calculate_outputs::parse;
+use calculate_outputs::feasibility::{self, Severity};
+use calculate_outputs::inverse;
+use calculate_outputs::merge;
+use calculate_outputs::json::{self, Json};
+use calculate_outputs::packs::{self, Pack, PackRegistry};
+use calculate_outputs::pipeline::Pipeline;
+#[cfg(feature = "parallel")]
+use calculate_outputs::distributed::{self, WorkerEvent};
+#[cfg(feature = "parallel")]
+use calculate_outputs::portfolio::{self, PortfolioMode};
+use calculate_outputs::repair;
+use calculate_outputs::presets::{Preset, PRESETS};
+use calculate_outputs::problem::{self, Problem};
+use calculate_outputs::recipes::{self, RecipeDatabase};
+use calculate_outputs::render::{self, NumberFormat, OutputFormat};
+use calculate_outputs::script::Script;
+use calculate_outputs::search_tree::SearchTree;
+use calculate_outputs::server::{self, Server};
+use calculate_outputs::solver::Solver;
+use calculate_outputs::trace::{self, Trace};
+use calculate_outputs::hint::Hint;
+use calculate_outputs::memo::Memo;
+use calculate_outputs::warm_start::{self, SearchCache};
+use calculate_outputs::{
+    diff, enumerate_optimal, search_solutions, GoalPredicate, shortest_path_to_target, BeltTier, Objective, Operation, Profile, SearchControl, SearchOptions,
+    Preference, SearchStats, Solution, SolutionSet, Strategy, Style, Timings, Tolerance, ToleranceMode, SCALE,
+};
+
+// Options and positional arguments gathered from the command line
+struct CliArgs {
+    program: String,
+    positional: Vec<String>,
+    profile: Profile,
+    pack: Option<Pack>, // Installed pack chosen with --profile, replacing the built-in profile
+    max_flow: Option<Vec<f64>>,
+    base_rate: Option<f64>,
+    machines: Option<usize>,
+    demand: Option<f64>,          // Total to deliver across however many streams it takes
+    stream_range: Option<(f64, f64)>, // Bounds on each of those streams
+    clock: Option<f64>, // Percent
+    recipes: Option<String>,
+    shared: Vec<Operation>, // Operation types whose devices may carry several flows at once
+    top_k: usize,
+    max_depth: Option<usize>,
+    style: Style,
+    prefer: Preference,
+    strategy: Option<Strategy>,
+    portfolio: Vec<Strategy>, // Strategies raced against each other, from --portfolio
+    #[cfg(feature = "parallel")]
+    portfolio_mode: PortfolioMode,
+    tolerance_mode: ToleranceMode, // Which side of the target outputs may fall on
+    tolerance: Option<ToleranceArg>, // From --tolerance, replacing canBeOffBy
+    seed: Option<u64>,
+    timeout: Option<Duration>,
+    time_budget: Option<Duration>, // Let the search tune its own settings to finish within this
+    server_limits: server::Limits, // What `serve` lets one request or client take
+    output: OutputFormat,
+    stdin: bool, // Read one JSON problem from stdin
+    watch: Option<String>, // Problem file to re-solve whenever it changes
+    problem: Option<String>, // JSON or TOML problem file to solve instead of positional arguments
+    preset: Option<&'static Preset>, // Built-in problem to solve instead of positional arguments
+    rank: Vec<Objective>,    // Order for top-k solutions, most important first
+    objectives: Vec<Objective>, // What the search optimizes, most important first
+    best_effort_depth: Option<usize>, // Settle for the closest state within this many operations
+    enumerate_optimal: Option<usize>, // List up to this many of the shortest plans
+    progress: bool,          // Emit JSON-lines progress events on stderr
+    dump_tree: Option<String>, // DOT file to write the explored search tree to
+    cache: Option<String>,     // File carrying found plans from one run to the next
+    memo: Option<String>,      // File accumulating plans for every sub-state across runs
+    script: Option<Script>,    // Goal and pruning rules loaded from --script
+    hint: Option<Hint>,        // Steps every plan must start with, from --hint
+    numbers: NumberFormat,
+    limits: Vec<(Operation, usize)>, // From --limit op=N
+    footprints: Vec<(Operation, f64)>, // From --footprint op=AREA, overriding the profile's
+    max_footprint: Option<f64>,
+    max_outputs: Option<usize>,
+    separate_inputs: bool, // Never merge flows from different inputs
+    use_all_inputs: bool,
+    max_chain: Option<usize>,
+    costs: Vec<(Operation, f64)>, // From --cost op=C
+    max_cost: Option<f64>,
+    max_value: Option<f64>,
+    forbidden: Vec<(f64, f64)>, // From --forbid, as inclusive ranges
+    input_capacities: Vec<(usize, f64)>, // From --input-capacity N=C, indexed from 0
+    optional_inputs: Vec<(usize, f64)>,  // From --optional-input N=PENALTY, indexed from 0
+    dedup_epsilon: Option<f64>,
+    profile_internal: bool, // Print where the search spent its time
+    memory_limit: Option<usize>, // Bytes, from --memory-limit in megabytes
+    history: Option<String>, // Where `bench` records its runs
+    compare: bool,           // Have `bench` flag slowdowns against the previous run
+    record: Option<String>,  // Trace file capturing every search event, for `replay`
+    arguments: Vec<String>,  // The options as given, without the program name or --record, kept in traces
+    variables: Vec<(String, String)>, // From --set NAME=VALUE, filling problem templates
+}
+
+fn main() {
+    // let inputs = vec![60.0];
+    // let target = 12.0;
+    // let can_be_off_by = 1.0;
+    let mut cli = parse_args(with_env_defaults(env::args().collect())).unwrap_or_else(|e| fail(e));
+    if cli.positional.first().map(String::as_str) == Some("solve") {
+        // `solve` is the default subcommand and may be named explicitly
+        cli.positional.remove(0);
+    }
+
+    match cli.positional.first().map(String::as_str) {
+        Some("balance") => run_balance(&cli),
+        Some("inverse") => run_inverse(&cli),
+        Some("merge") => run_merge(&cli),
+        Some("check") => run_check(&cli),
+        Some("pipeline") => run_pipeline(&cli),
+        Some("multi") => run_multi(&cli),
+        Some("sweep") => run_sweep(&cli),
+        Some("compare") => run_compare(&cli),
+        Some("sensitivity") => run_sensitivity(&cli),
+        Some("tui") => run_tui(&cli),
+        Some("convert") => run_convert(&cli),
+        Some("diff") => run_diff(&cli),
+        Some("repair") => run_repair(&cli),
+        Some("packs") => run_packs(),
+        Some("presets") => run_presets(),
+        Some("serve") => run_serve(&cli),
+        #[cfg(feature = "parallel")]
+        Some("coordinate") => run_coordinate(&cli),
+        #[cfg(feature = "parallel")]
+        Some("worker") => run_worker(&cli),
+        Some("bench") => run_bench(&cli),
+        Some("replay") => run_replay(&cli),
+        _ => run_solve(&cli),
+    }
+}
+
+const STRATEGIES: &str = "greedy, shortest, weighted:COST or beam:WIDTH";
+
+#[cfg(feature = "parallel")]
+fn portfolio_default() -> Vec<Strategy> {
+    portfolio::DEFAULT_PORTFOLIO.to_vec()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn portfolio_default() -> Vec<Strategy> {
+    vec![Strategy::Greedy]
+}
+
+const OUTPUT_FORMATS: &str = "human, json, dot, mermaid, csv, html, tikz or cytoscape";
+
+// Options that may also be set through CALC_OUTPUTS_* environment variables
+const ENV_OPTIONS: [(&str, &str); 8] = [
+    ("CALC_OUTPUTS_PROFILE", "--profile"),
+    ("CALC_OUTPUTS_MAX_FLOW", "--max-flow"),
+    ("CALC_OUTPUTS_MAX_DEPTH", "--max-depth"),
+    ("CALC_OUTPUTS_TIMEOUT", "--timeout"),
+    ("CALC_OUTPUTS_STYLE", "--style"),
+    ("CALC_OUTPUTS_TOP_K", "--top-k"),
+    ("CALC_OUTPUTS_SEED", "--seed"),
+    ("CALC_OUTPUTS_OUTPUT", "--output"),
+];
+
+// Insert environment-provided options ahead of the real arguments, so flags given on the command line win
+fn with_env_defaults(args: Vec<String>) -> Vec<String> {
+    let mut merged = vec![args[0].clone()];
+    for (variable, flag) in ENV_OPTIONS {
+        if let Ok(value) = env::var(variable) {
+            merged.push(flag.to_string());
+            merged.push(value);
+        }
+    }
+    merged.extend(args.into_iter().skip(1));
+    merged
+}
+
+// Parse command-line arguments
+fn parse_args(args: Vec<String>) -> Result<CliArgs, Error> {
+    let mut cli = CliArgs {
+        program: args[0].clone(),
+        positional: Vec::new(),
+        profile: Profile::Default,
+        pack: None,
+        max_flow: None,
+        base_rate: None,
+        machines: None,
+        demand: None,
+        stream_range: None,
+        clock: None,
+        recipes: None,
+        shared: Vec::new(),
+        top_k: 1,
+        max_depth: None,
+        style: Style::Any,
+        prefer: Preference::None,
+        strategy: None,
+        portfolio: Vec::new(),
+        #[cfg(feature = "parallel")]
+        portfolio_mode: PortfolioMode::First,
+        tolerance_mode: ToleranceMode::Both,
+        tolerance: None,
+        seed: None,
+        timeout: None,
+        time_budget: None,
+        server_limits: server::Limits::default(),
+        output: OutputFormat::Human,
+        stdin: false,
+        watch: None,
+        problem: None,
+        preset: None,
+        rank: Vec::new(),
+        objectives: Vec::new(),
+        best_effort_depth: None,
+        enumerate_optimal: None,
+        progress: false,
+        dump_tree: None,
+        cache: None,
+        memo: None,
+        script: None,
+        hint: None,
+        numbers: NumberFormat::default(),
+        limits: Vec::new(),
+        footprints: Vec::new(),
+        max_footprint: None,
+        max_outputs: None,
+        separate_inputs: false,
+        use_all_inputs: false,
+        max_chain: None,
+        costs: Vec::new(),
+        max_cost: None,
+        max_value: None,
+        forbidden: Vec::new(),
+        input_capacities: Vec::new(),
+        optional_inputs: Vec::new(),
+        dedup_epsilon: None,
+        profile_internal: false,
+        memory_limit: None,
+        history: None,
+        compare: false,
+        record: None,
+        arguments: Vec::new(),
+        variables: Vec::new(),
+    };
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let name = next_value(&mut rest, "--profile")?;
+                cli.pack = None;
+                match Profile::from_name(name) {
+                    Some(profile) => cli.profile = profile,
+                    None => {
+                        let (registry, errors) = PackRegistry::discover();
+                        for error in errors {
+                            log("warn", "packs", &format!("skipping pack: {}", error), &[]);
+                        }
+                        let pack = registry.find(name).ok_or_else(|| unknown("profile", name, "default, factorio, pipes or an installed pack (see `packs`)"))?;
+                        cli.pack = Some(pack.clone());
+                    }
+                }
+            }
+            "--max-flow" => {
+                let value = next_value(&mut rest, "--max-flow")?;
+                let mut capacities = value
+                    .split(',')
+                    .map(|s| parse_rate(s.trim(), "--max-flow capacity", "comma-separated rates like 15,30"))
+                    .collect::<Result<Vec<f64>, Error>>()?;
+                capacities.sort_by(|a, b| a.total_cmp(b));
+                cli.max_flow = Some(capacities);
+            }
+            "--base-rate" => {
+                let value = next_value(&mut rest, "--base-rate")?;
+                cli.base_rate = Some(parse_rate(value, "--base-rate", "a rate")?);
+            }
+            "--machines" => {
+                let value = next_value(&mut rest, "--machines")?;
+                cli.machines = Some(parse_value(value, "--machines", "a whole number")?);
+            }
+            "--demand" => {
+                let value = next_value(&mut rest, "--demand")?;
+                cli.demand = Some(parse_rate(value, "--demand", "a total rate")?);
+            }
+            "--stream-range" => {
+                let value = next_value(&mut rest, "--stream-range")?;
+                let invalid = || Error::InvalidValue { what: "--stream-range".to_string(), text: value.clone(), expected: "bounds like 100..150" };
+                if !value.contains("..") {
+                    return Err(invalid());
+                }
+                cli.stream_range = Some(parse::parse_band(value).ok_or_else(invalid)?);
+            }
+            "--clock" => {
+                let value = next_value(&mut rest, "--clock")?;
+                cli.clock = Some(parse_value(value.trim_end_matches('%'), "--clock", "a percentage like 75 or 75%")?);
+            }
+            "--recipes" => {
+                cli.recipes = Some(next_value(&mut rest, "--recipes")?.clone());
+            }
+            "--share" => {
+                let value = next_value(&mut rest, "--share")?;
+                for name in value.split(',') {
+                    let operation = Operation::from_name(name.trim()).ok_or_else(|| unknown("operation", name, "split2, split3, combine2 or combine3"))?;
+                    cli.shared.push(operation);
+                }
+            }
+            "--limit" => {
+                let value = next_value(&mut rest, "--limit")?;
+                let (operation, count) = operation_setting(value, "--limit", "operation=count like split3=4")?;
+                let count = parse_value(count, "--limit count", "a whole number")?;
+                cli.limits.retain(|&(limited, _)| limited != operation);
+                cli.limits.push((operation, count));
+            }
+            "--footprint" => {
+                let value = next_value(&mut rest, "--footprint")?;
+                let (operation, area) = operation_setting(value, "--footprint", "operation=area like split2=2")?;
+                let area = parse_value(area, "--footprint area", "a number")?;
+                cli.footprints.retain(|&(declared, _)| declared != operation);
+                cli.footprints.push((operation, area));
+            }
+            "--cost" => {
+                let value = next_value(&mut rest, "--cost")?;
+                let (operation, cost) = operation_setting(value, "--cost", "operation=cost like split3=2.5")?;
+                let cost = parse_value(cost, "--cost", "a number")?;
+                cli.costs.retain(|&(declared, _)| declared != operation);
+                cli.costs.push((operation, cost));
+            }
+            "--max-cost" => {
+                let value = next_value(&mut rest, "--max-cost")?;
+                cli.max_cost = Some(parse_value(value, "--max-cost", "a number")?);
+            }
+            "--forbid" => {
+                let value = next_value(&mut rest, "--forbid")?;
+                for part in value.split(',') {
+                    let band = parse::parse_band(part.trim()).ok_or_else(|| Error::InvalidValue {
+                        what: "--forbid".to_string(),
+                        text: part.to_string(),
+                        expected: "rates or ranges like 86.667 or 80..90",
+                    })?;
+                    cli.forbidden.push(band);
+                }
+            }
+            "--max-value" => {
+                let value = next_value(&mut rest, "--max-value")?;
+                cli.max_value = Some(parse_rate(value, "--max-value", "a rate")?);
+            }
+            "--input-capacity" => {
+                let value = next_value(&mut rest, "--input-capacity")?;
+                let (input, capacity) = input_setting(value, "--input-capacity", "input=capacity like 2=300, counting inputs from 1")?;
+                let capacity = parse_rate(capacity, "--input-capacity", "a rate")?;
+                cli.input_capacities.retain(|&(declared, _)| declared != input);
+                cli.input_capacities.push((input, capacity));
+            }
+            "--optional-input" => {
+                let value = next_value(&mut rest, "--optional-input")?;
+                let (input, penalty) = input_setting(value, "--optional-input", "input=penalty like 2=1.5, counting inputs from 1")?;
+                let penalty = parse_value(penalty, "--optional-input penalty", "a number of steps")?;
+                cli.optional_inputs.retain(|&(declared, _)| declared != input);
+                cli.optional_inputs.push((input, penalty));
+            }
+            "--dedup-epsilon" => {
+                let value = next_value(&mut rest, "--dedup-epsilon")?;
+                let epsilon: f64 = parse_value(value, "--dedup-epsilon", "a positive rate")?;
+                if epsilon <= 0.0 {
+                    return Err(Error::InvalidValue { what: "--dedup-epsilon".to_string(), text: value.clone(), expected: "a positive rate" });
+                }
+                cli.dedup_epsilon = Some(epsilon);
+            }
+            "--max-footprint" => {
+                let value = next_value(&mut rest, "--max-footprint")?;
+                cli.max_footprint = Some(parse_value(value, "--max-footprint", "a number")?);
+            }
+            "--max-chain" => {
+                let value = next_value(&mut rest, "--max-chain")?;
+                cli.max_chain = Some(parse_value(value, "--max-chain", "a whole number")?);
+            }
+            "--max-outputs" => {
+                let value = next_value(&mut rest, "--max-outputs")?;
+                cli.max_outputs = Some(parse_value(value, "--max-outputs", "a whole number")?);
+            }
+            "--top-k" => {
+                let value = next_value(&mut rest, "--top-k")?;
+                cli.top_k = parse_value(value, "--top-k", "a whole number")?;
+            }
+            "--rank" => {
+                let value = next_value(&mut rest, "--rank")?;
+                cli.rank.extend(objective_list(value)?);
+            }
+            "--objective" => {
+                let value = next_value(&mut rest, "--objective")?;
+                cli.objectives.extend(objective_list(value)?);
+            }
+            "--seed" => {
+                let value = next_value(&mut rest, "--seed")?;
+                cli.seed = Some(parse_value(value, "--seed", "a whole number")?);
+            }
+            "--quota" => {
+                let value = next_value(&mut rest, "--quota")?;
+                let seconds: f64 = parse_value(value.trim_end_matches('s'), "--quota", "seconds like 2.5 or 2.5s")?;
+                cli.server_limits.quota = Some(Duration::from_secs_f64(seconds));
+            }
+            "--rate-limit" => cli.server_limits.rate = Some(parse_value(next_value(&mut rest, "--rate-limit")?, "--rate-limit", "requests per minute")?),
+            "--concurrent" => cli.server_limits.concurrent = parse_value(next_value(&mut rest, "--concurrent")?, "--concurrent", "a request count")?,
+            "--timeout" => {
+                let value = next_value(&mut rest, "--timeout")?;
+                let seconds: f64 = parse_value(value.trim_end_matches('s'), "--timeout", "seconds like 2.5 or 2.5s")?;
+                cli.timeout = Some(Duration::from_secs_f64(seconds));
+            }
+            "--time-budget" => {
+                let value = next_value(&mut rest, "--time-budget")?;
+                let seconds: f64 = parse_value(value.trim_end_matches('s'), "--time-budget", "seconds like 10 or 10s")?;
+                cli.time_budget = Some(Duration::from_secs_f64(seconds));
+            }
+            "--output" => {
+                let name = next_value(&mut rest, "--output")?;
+                cli.output = OutputFormat::from_name(name).ok_or_else(|| unknown("output format", name, OUTPUT_FORMATS))?;
+            }
+            "--stdin" => cli.stdin = true,
+            "--separate-inputs" => cli.separate_inputs = true,
+            "--profile-internal" => cli.profile_internal = true,
+            "--memory-limit" => {
+                let value = next_value(&mut rest, "--memory-limit")?;
+                let megabytes: f64 = parse_value(value, "--memory-limit", "a size in megabytes")?;
+                cli.memory_limit = Some((megabytes * 1024.0 * 1024.0) as usize);
+            }
+            "--compare" => cli.compare = true,
+            "--log-format" => {
+                let format = next_value(&mut rest, "--log-format")?;
+                let json = match format.as_str() {
+                    "text" => false,
+                    "json" => true,
+                    _ => return Err(unknown("log format", format, "text or json")),
+                };
+                LOG_JSON.store(json, Ordering::Relaxed);
+            }
+            "--record" => {
+                cli.record = Some(next_value(&mut rest, "--record")?.clone());
+            }
+            "--history" => {
+                cli.history = Some(next_value(&mut rest, "--history")?.clone());
+            }
+            "--use-all-inputs" => cli.use_all_inputs = true,
+            "--decimals" => {
+                let value = next_value(&mut rest, "--decimals")?;
+                cli.numbers.decimals = Some(parse_value(value, "--decimals", "a whole number")?);
+            }
+            "--thousands" => cli.numbers.thousands = true,
+            "--progress" => {
+                let format = next_value(&mut rest, "--progress")?;
+                if format != "jsonl" {
+                    return Err(unknown("progress format", format, "jsonl"));
+                }
+                cli.progress = true;
+            }
+            "--watch" => {
+                cli.watch = Some(next_value(&mut rest, "--watch")?.clone());
+            }
+            "--dump-search-tree" => {
+                cli.dump_tree = Some(next_value(&mut rest, "--dump-search-tree")?.clone());
+            }
+            "--memo" => {
+                cli.memo = Some(next_value(&mut rest, "--memo")?.clone());
+            }
+            "--cache" => {
+                cli.cache = Some(next_value(&mut rest, "--cache")?.clone());
+            }
+            "--hint" => {
+                cli.hint = Some(Hint::load(next_value(&mut rest, "--hint")?)?);
+            }
+            "--script" => {
+                cli.script = Some(Script::load(next_value(&mut rest, "--script")?)?);
+            }
+            "--set" => {
+                let value = next_value(&mut rest, "--set")?;
+                let (name, setting) = value.split_once('=').ok_or_else(|| Error::InvalidValue {
+                    what: "--set".to_string(),
+                    text: value.to_string(),
+                    expected: "NAME=VALUE",
+                })?;
+                cli.variables.push((name.trim().to_string(), setting.trim().to_string()));
+            }
+            "--preset" => {
+                let name = next_value(&mut rest, "--preset")?;
+                cli.preset = Some(Preset::find(name).ok_or_else(|| unknown("preset", name, "a name listed by `presets`"))?);
+            }
+            "--problem" => {
+                cli.problem = Some(next_value(&mut rest, "--problem")?.clone());
+            }
+            "--enumerate-optimal" => {
+                let value = next_value(&mut rest, "--enumerate-optimal")?;
+                cli.enumerate_optimal = Some(parse_value(value, "--enumerate-optimal", "a whole number")?);
+            }
+            "--best-effort-depth" => {
+                let value = next_value(&mut rest, "--best-effort-depth")?;
+                cli.best_effort_depth = Some(parse_value(value, "--best-effort-depth", "a whole number")?);
+            }
+            "--max-depth" => {
+                let value = next_value(&mut rest, "--max-depth")?;
+                cli.max_depth = Some(parse_value(value, "--max-depth", "a whole number")?);
+            }
+            "--strategy" => {
+                let name = next_value(&mut rest, "--strategy")?;
+                cli.strategy = Some(Strategy::from_name(name).ok_or_else(|| unknown("strategy", name, STRATEGIES))?);
+            }
+            "--portfolio" => {
+                let value = next_value(&mut rest, "--portfolio")?;
+                cli.portfolio = if value == "default" {
+                    portfolio_default()
+                } else {
+                    value.split(',').map(|name| Strategy::from_name(name.trim()).ok_or_else(|| unknown("strategy", name, STRATEGIES))).collect::<Result<_, _>>()?
+                };
+            }
+            #[cfg(feature = "parallel")]
+            "--portfolio-mode" => {
+                let name = next_value(&mut rest, "--portfolio-mode")?;
+                cli.portfolio_mode = PortfolioMode::from_name(name).ok_or_else(|| unknown("portfolio mode", name, "first or best"))?;
+            }
+            "--tolerance-mode" => {
+                let name = next_value(&mut rest, "--tolerance-mode")?;
+                cli.tolerance_mode = ToleranceMode::from_name(name).ok_or_else(|| unknown("tolerance mode", name, "both, at-least or at-most"))?;
+            }
+            "--tolerance" => {
+                cli.tolerance = Some(parse_tolerance(next_value(&mut rest, "--tolerance")?)?);
+            }
+            "--style" => {
+                let name = next_value(&mut rest, "--style")?;
+                cli.style = Style::from_name(name).ok_or_else(|| unknown("style", name, "any, manifold or balanced"))?;
+            }
+            "--prefer" => {
+                let name = next_value(&mut rest, "--prefer")?;
+                cli.prefer = Preference::from_name(name).ok_or_else(|| unknown("preference", name, "splits, combines or none"))?;
+            }
+            _ => cli.positional.push(arg.clone()),
+        }
+    }
+    // A replayed search must not overwrite the trace it is checked against
+    let mut arguments = args[1..].iter();
+    while let Some(arg) = arguments.next() {
+        if arg == "--record" {
+            arguments.next();
+        } else {
+            cli.arguments.push(arg.clone());
+        }
+    }
+    Ok(cli)
+}
+
+// The value following a flag
+fn next_value<'a>(rest: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a String, Error> {
+    rest.next().ok_or_else(|| Error::MissingValue { flag: flag.to_string() })
 }
 
-// Custom Ord for BinaryHeap priority based on estimated cost (A* heuristic)
-impl Ord for Node {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.estimated_cost.cmp(&self.estimated_cost) // Min-heap behavior
+fn parse_value<T: FromStr>(text: &str, what: &str, expected: &'static str) -> Result<T, Error> {
+    text.parse().map_err(|_| Error::InvalidValue { what: what.to_string(), text: text.to_string(), expected })
+}
+
+// A rate, which may be written as arithmetic like 780/2
+fn parse_rate(text: &str, what: &str, expected: &'static str) -> Result<f64, Error> {
+    parse::evaluate(text).ok_or_else(|| Error::InvalidValue { what: what.to_string(), text: text.to_string(), expected })
+}
+
+// What --tolerance gave: one distance, bounds on each side, or a share of whatever the target turns out to be
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ToleranceArg {
+    Plain(f64), // Applied on the sides --tolerance-mode allows
+    Band(Tolerance),
+    Percent { percent: f64, floor: f64 }, // Never narrower than `floor`, however small the target
+}
+
+impl ToleranceArg {
+    fn band(self, target: f64, mode: ToleranceMode) -> Tolerance {
+        match self {
+            ToleranceArg::Plain(off_by) => Tolerance::new(off_by, mode),
+            ToleranceArg::Band(band) => band,
+            ToleranceArg::Percent { percent, floor } => Tolerance::new((target.abs() * percent / 100.0).max(floor), mode),
+        }
     }
 }
 
-impl PartialOrd for Node {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+// A band like +0.5/-2.0, naming how far above and below the target outputs may fall in either order; a
+// single number for the same distance both ways; or a percentage of the target with an optional absolute
+// floor, like `1% min 0.1`
+fn parse_tolerance(text: &str) -> Result<ToleranceArg, Error> {
+    const EXPECTED: &str = "a rate, bounds above and below like +0.5/-2.0, or a percentage like 1% min 0.1";
+    let invalid = || Error::InvalidValue { what: "--tolerance".to_string(), text: text.to_string(), expected: EXPECTED };
+    let (share, floor) = text.split_once(" min ").unwrap_or((text, "0"));
+    if let Some(percent) = share.trim().strip_suffix('%') {
+        let percent = parse::evaluate(percent).filter(|&percent| percent >= 0.0).ok_or_else(invalid)?;
+        let floor = parse::evaluate(floor).filter(|&floor| floor >= 0.0).ok_or_else(invalid)?;
+        return Ok(ToleranceArg::Percent { percent, floor });
+    }
+    if !text.contains('/') {
+        let off_by = parse_rate(text, "--tolerance", EXPECTED)?;
+        return Ok(ToleranceArg::Plain(off_by));
+    }
+    let mut band = Tolerance { below: 0.0, above: 0.0 };
+    for bound in text.split('/').map(str::trim) {
+        let (side, rate) = match bound.split_at_checked(1) {
+            Some(("+", rate)) => (&mut band.above, rate),
+            Some(("-", rate)) => (&mut band.below, rate),
+            _ => return Err(invalid()),
+        };
+        *side = parse::evaluate(rate).filter(|&rate| rate >= 0.0).ok_or_else(invalid)?;
     }
+    Ok(ToleranceArg::Band(band))
 }
 
-const SCALE: f64 = 1000.0;
-const MAX_DEPTH: usize = 6; // Reduced max depth to limit path expansion
+fn unknown(kind: &'static str, name: &str, expected: &'static str) -> Error {
+    Error::UnknownName { kind, name: name.trim().to_string(), expected }
+}
 
-fn main() {
-    // let inputs = vec![60.0];
-    // let target = 12.0;
-    // let can_be_off_by = 1.0;
-    // Parse command-line arguments
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 4 {
-        eprintln!("Usage: {} <inputs> <target> <canBeOffBy>", args[0]);
-        eprintln!("Example: {} \"10.0,10.0,10.0\" 12.0 1.0", args[0]);
+// Comma-separated objectives, most important first
+fn objective_list(value: &str) -> Result<Vec<Objective>, Error> {
+    value
+        .split(',')
+        .map(|name| Objective::from_name(name.trim()).ok_or_else(|| unknown("objective", name, "steps (machines), deviation, remainder (waste) or outputs")))
+        .collect()
+}
+
+// Split a per-input setting written as input=value, turning the 1-based input number into an index
+fn input_setting<'a>(value: &'a str, flag: &str, expected: &'static str) -> Result<(usize, &'a str), Error> {
+    let invalid = || Error::InvalidValue { what: flag.to_string(), text: value.to_string(), expected };
+    let (input, setting) = value.split_once('=').ok_or_else(invalid)?;
+    let input: usize = input.trim().parse().ok().filter(|&input| input >= 1).ok_or_else(invalid)?;
+    Ok((input - 1, setting.trim()))
+}
+
+// Split a per-operation setting written as operation=value
+fn operation_setting<'a>(value: &'a str, flag: &str, expected: &'static str) -> Result<(Operation, &'a str), Error> {
+    let (name, setting) = value.split_once('=').ok_or_else(|| Error::InvalidValue {
+        what: flag.to_string(),
+        text: value.to_string(),
+        expected,
+    })?;
+    let operation = Operation::from_name(name.trim()).ok_or_else(|| unknown("operation", name, "split2, split3, combine2 or combine3"))?;
+    Ok((operation, setting.trim()))
+}
+
+// Whether diagnostics go to stderr as JSON objects (--log-format json) rather than plain text
+static LOG_JSON: AtomicBool = AtomicBool::new(false);
+
+// Write one diagnostic to stderr: as plain text, or with --log-format json as one object carrying its
+// level, the phase of the run it came from and any counters, for log aggregation
+fn log(level: &str, phase: &str, message: &str, counters: &[(&str, f64)]) {
+    if !LOG_JSON.load(Ordering::Relaxed) {
+        match level {
+            "warn" => eprintln!("warning: {}", message),
+            _ => eprintln!("{}", message),
+        }
+        return;
+    }
+    let mut fields = BTreeMap::new();
+    fields.insert("level".to_string(), Json::String(level.to_string()));
+    fields.insert("phase".to_string(), Json::String(phase.to_string()));
+    fields.insert("message".to_string(), Json::String(message.to_string()));
+    for &(name, value) in counters {
+        fields.insert(name.to_string(), Json::Number(value));
+    }
+    eprintln!("{}", Json::Object(fields));
+}
+
+// A line about how the problem was read: part of the human report on stdout, but a diagnostic on
+// stderr under machine-readable formats, which keep stdout to the document alone
+fn note(cli: &CliArgs, phase: &str, message: &str) {
+    match cli.output {
+        OutputFormat::Human => println!("{}", message),
+        _ => log("info", phase, message, &[]),
+    }
+}
+
+// Cancelled by the first Ctrl-C, so the running search stops and reports what it found so far
+static INTERRUPT: OnceLock<SearchControl> = OnceLock::new();
+// Conventional exit status of a process stopped by SIGINT
+const INTERRUPTED_STATUS: i32 = 130;
+
+// A control that Ctrl-C cancels, trapping SIGINT the first time it is asked for. The handler only sets
+// the control's flag, which the search polls, and hands SIGINT back to its default action, so a second
+// Ctrl-C ends a search that does not get to checking its control. Elsewhere Ctrl-C is left as it is
+fn interrupt_control() -> SearchControl {
+    INTERRUPT
+        .get_or_init(|| {
+            #[cfg(unix)]
+            {
+                // libc is not a dependency, so `signal` is declared by hand. This relies on POSIX and the
+                // platform C ABI: `void (*signal(int, void (*)(int)))(int)`, with handlers passed as
+                // pointer-sized values, SIG_DFL as 0 and SIGINT as 2, which holds on every Unix we build for
+                const SIGINT: i32 = 2;
+                const SIG_DFL: usize = 0;
+                extern "C" {
+                    fn signal(signum: i32, handler: usize) -> usize;
+                }
+                extern "C" fn on_interrupt(_: i32) {
+                    // Only an atomic store and `signal` here: both are safe inside a signal handler
+                    if let Some(control) = INTERRUPT.get() {
+                        control.cancel();
+                    }
+                    unsafe {
+                        signal(SIGINT, SIG_DFL);
+                    }
+                }
+                unsafe {
+                    signal(SIGINT, on_interrupt as extern "C" fn(i32) as usize);
+                }
+            }
+            SearchControl::new()
+        })
+        .clone()
+}
+
+// Report an error at the CLI boundary and exit
+fn fail(error: Error) -> ! {
+    if LOG_JSON.load(Ordering::Relaxed) {
+        log("error", "setup", &error.to_string(), &[]);
+    } else {
+        eprintln!("error: {}", error);
+    }
+    std::process::exit(1);
+}
+
+// Build the search settings from the chosen profile and any overriding flags
+fn search_options(cli: &CliArgs) -> SearchOptions {
+    let mut options = cli.pack.as_ref().map_or_else(|| cli.profile.search_options(), |pack| pack.options.clone());
+    if let Some(capacities) = &cli.max_flow {
+        // Explicit capacities replace any tiers the profile brought along
+        options.belt_tiers = capacities
+            .iter()
+            .enumerate()
+            .map(|(index, &capacity)| BeltTier { name: format!("tier {}", index + 1), capacity })
+            .collect();
+    }
+    if let Some(depth) = cli.max_depth {
+        options.max_depth = depth;
+    }
+    if let Some(depth) = cli.best_effort_depth {
+        options.max_depth = depth;
+        options.best_effort = true;
+    }
+    options.style = cli.style;
+    options.prefer = cli.prefer;
+    if let Some(strategy) = cli.strategy {
+        options.strategy = strategy;
+    }
+    options.operation_limits = cli.limits.clone();
+    for &(operation, area) in &cli.footprints {
+        options.footprints.retain(|&(declared, _)| declared != operation);
+        options.footprints.push((operation, area));
+    }
+    if cli.max_footprint.is_some() {
+        options.max_footprint = cli.max_footprint;
+    }
+    for &(operation, cost) in &cli.costs {
+        options.costs.retain(|&(declared, _)| declared != operation);
+        options.costs.push((operation, cost));
+    }
+    if cli.max_cost.is_some() {
+        options.max_cost = cli.max_cost;
+    }
+    if cli.max_value.is_some() {
+        options.max_value = cli.max_value;
+    }
+    options.forbidden.extend_from_slice(&cli.forbidden);
+    for &(input, capacity) in &cli.input_capacities {
+        options.input_capacities.retain(|&(declared, _)| declared != input);
+        options.input_capacities.push((input, capacity));
+    }
+    if cli.dedup_epsilon.is_some() {
+        options.dedup_epsilon = cli.dedup_epsilon;
+    }
+    for &(input, penalty) in &cli.optional_inputs {
+        options.optional_inputs.retain(|&(declared, _)| declared != input);
+        options.optional_inputs.push((input, penalty));
+    }
+    if cli.max_outputs.is_some() {
+        options.max_outputs = cli.max_outputs;
+    }
+    options.separate_inputs |= cli.separate_inputs;
+    options.use_all_inputs |= cli.use_all_inputs;
+    options.profile_internal |= cli.profile_internal;
+    if !cli.objectives.is_empty() {
+        options.objectives = cli.objectives.clone();
+    }
+    if cli.max_chain.is_some() {
+        options.max_chain = cli.max_chain;
+    }
+    if cli.memory_limit.is_some() {
+        options.memory_limit = cli.memory_limit;
+    }
+    if let Some(script) = &cli.script {
+        if let Some(goal) = script.goal() {
+            options.goal = Some(goal);
+        }
+        options.prune = script.pruning();
+    }
+    options.seed = cli.seed;
+    options.timeout = cli.timeout;
+    options
+}
+
+// Solve for outputs close to a target from a list of inputs
+fn run_solve(cli: &CliArgs) {
+    if cli.stdin {
+        run_solve_stdin(cli);
+        return;
+    }
+    if let Some(path) = &cli.watch {
+        run_watch(cli, path);
+        return;
+    }
+    if let Some(path) = &cli.problem {
+        let problem = Problem::load_template(path, &cli.variables).unwrap_or_else(|e| fail(e));
+        let options = problem.search_options(&search_options(cli));
+        solve_and_print(cli, problem.inputs, problem.target, problem.tolerance, &options);
+        return;
+    }
+    if let Some(preset) = cli.preset {
+        let problem = preset.problem();
+        let options = problem.search_options(&search_options(cli));
+        solve_and_print(cli, problem.inputs, problem.target, problem.tolerance, &options);
+        return;
+    }
+
+    if let Some(total) = cli.demand {
+        run_demand(cli, total);
+        return;
+    }
+
+    // With machine settings and no explicit target, the target is derived from the machines
+    // --tolerance stands in for canBeOffBy, which may then be left out
+    let tolerance_given = cli.tolerance.is_some() as usize;
+    let derive_target = (cli.machines.is_some() || cli.clock.is_some()) && cli.positional.len() == 2 - tolerance_given;
+    let required = if derive_target { 2 } else { 3 } - tolerance_given;
+    if cli.positional.len() < required {
+        print_usage(&cli.program);
         std::process::exit(1);
     }
 
     // Convert inputs to a vector of floats
-    let inputs: Vec<f64> = args[1]
-        .split(',')
-        .map(|s| s.parse().expect("Failed to parse input values"))
-        .collect();
+    let inputs = parse::parse_inputs(&cli.positional[0]).unwrap_or_else(|e| fail(e));
+
+    let mut options = search_options(cli);
 
     // Parse target and canBeOffBy as floats
-    let target: f64 = args[2].parse().expect("Failed to parse target");
-    let can_be_off_by: f64 = args[3].parse().expect("Failed to parse canBeOffBy");
+    let target: f64 = if derive_target {
+        let base_rate = cli.base_rate.unwrap_or_else(|| {
+            log("error", "setup", "--machines and --clock require --base-rate", &[]);
+            std::process::exit(1);
+        });
+        let target = machine_rate(base_rate, cli.clock.unwrap_or(100.0));
+        if let Some(machines) = cli.machines {
+            options.min_outputs = machines;
+            note(cli, "setup", &format!("Derived target: {} per machine for {} machine(s)", target, machines));
+        } else {
+            note(cli, "setup", &format!("Derived target: {} per machine", target));
+        }
+        target
+    } else if let Some(recipe_target) = recipes::parse_recipe_target(&cli.positional[1]) {
+        let recipe_target = recipe_target.unwrap_or_else(|e| {
+            log("error", "setup", &e.to_string(), &[]);
+            std::process::exit(1);
+        });
+        let database = load_recipes(cli);
+        let recipe = database.get(&recipe_target.name).unwrap_or_else(|| {
+            log("error", "setup", &format!("Unknown recipe: {}", recipe_target.name), &[]);
+            std::process::exit(1);
+        });
+        let target = machine_rate(recipe.rate, cli.clock.unwrap_or(100.0));
+        options.min_outputs = recipe_target.machines;
+        note(
+            cli,
+            "setup",
+            &format!("Recipe {}: {} machine(s) consuming {} {} each", recipe_target.name, recipe_target.machines, target, recipe.input),
+        );
+        target
+    } else {
+        parse_rate(&cli.positional[1], "target", "a rate or recipe:<name> x <machines>").unwrap_or_else(|e| fail(e))
+    };
+    let can_be_off_by: f64 = match (cli.tolerance, cli.positional.get(required - 1 + tolerance_given)) {
+        (Some(_), None) => 0.0, // Unused: the band comes from --tolerance
+        (_, off_by) => parse_rate(off_by.map_or("", String::as_str), "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e)),
+    };
+
+    solve_and_print(cli, inputs, target, can_be_off_by, &options);
+}
 
+// Meet a total demand in streams within --stream-range, letting the search choose how many
+fn run_demand(cli: &CliArgs, total: f64) {
+    if cli.positional.len() < 2 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+    let (low, high) = cli.stream_range.unwrap_or_else(|| {
+        log("error", "setup", "--demand requires --stream-range", &[]);
+        std::process::exit(1);
+    });
+    let inputs = parse::parse_inputs(&cli.positional[0]).unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = parse_rate(&cli.positional[1], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e));
+
+    // The middle of the range steers the search; the predicate decides what counts as done
+    let mut options = search_options(cli);
+    options.goal = Some(GoalPredicate::demand(total, can_be_off_by, low, high));
+    note(cli, "setup", &format!("Demand: {} total in streams of {} to {}", total, low, high));
+    solve_and_print(cli, inputs, (low + high) / 2.0, (high - low) / 2.0, &options);
+}
+
+// Read one JSON problem from stdin and write the solution as JSON (or `null`) to stdout
+fn run_solve_stdin(cli: &CliArgs) {
+    let mut text = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+        log("error", "setup", &format!("Failed to read stdin: {}", e), &[]);
+        std::process::exit(1);
+    }
+    let text = problem::substitute(&text, &cli.variables).unwrap_or_else(|message| fail(Error::InvalidProblem(message)));
+    let problem = Problem::from_json_str(&text).unwrap_or_else(|e| fail(e));
+
+    let format = if cli.output == OutputFormat::Human { OutputFormat::Json } else { cli.output };
+    match problem.solve(&search_options(cli)) {
+        Some(solution) => print!("{}", render::render_with(&solution, format, &cli.numbers)),
+        None => {
+            println!("null");
+            log("info", "search", "No solution found.", &[]);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Re-solve a JSON or TOML problem file whenever it changes, printing what changed in the result
+fn run_watch(cli: &CliArgs, path: &str) {
+    let options = search_options(cli);
+    let mut last_modified = None;
+    let mut previous: Option<Solution> = None;
+
+    loop {
+        let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        if modified.is_some() && modified != last_modified {
+            last_modified = modified;
+            println!("== {} changed ==", path);
+            match Problem::load_template(path, &cli.variables) {
+                Ok(problem) => {
+                    let start_time = Instant::now();
+                    match problem.solve(&options) {
+                        Some(solution) => {
+                            print_solution(cli, &solution, &problem.search_options(&options));
+                            if let Some(previous) = &previous {
+                                let changes = diff::describe(previous, &solution);
+                                if changes.is_empty() {
+                                    println!("No change from previous solution.");
+                                }
+                                for change in changes {
+                                    println!("Changed: {}", change);
+                                }
+                            }
+                            previous = Some(solution);
+                        }
+                        None => println!("No solution found."),
+                    }
+                    println!("Total time taken: {:?}", start_time.elapsed());
+                }
+                Err(e) => log("error", "watch", &format!("error: {}", e), &[]),
+            }
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+}
+
+// Split one input into N equal streams with nothing left over
+fn run_balance(cli: &CliArgs) {
+    if cli.positional.len() < 3 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let input: f64 = parse_rate(&cli.positional[1], "input rate", "a rate").unwrap_or_else(|e| fail(e));
+    let count: usize = parse_value(&cli.positional[2], "output count", "a whole number").unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = match cli.positional.get(3) {
+        Some(value) => parse_rate(value, "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e)),
+        None => 0.0,
+    };
+    if count == 0 {
+        log("error", "setup", "Output count must be at least 1", &[]);
+        std::process::exit(1);
+    }
+
+    let mut options = search_options(cli);
+    options.output_count = Some(count);
+    if cli.max_depth.is_none() {
+        // A split adds at most two streams, so `count` streams take at least (count - 1) / 2 operations,
+        // and counts with prime factors other than 2 and 3 need merges on top. No tight upper bound is
+        // known, so allow two operations per stream as headroom
+        options.max_depth = options.max_depth.max(2 * count);
+    }
+    solve_and_print(cli, vec![input], input / count as f64, can_be_off_by, &options);
+}
+
+// Lint a problem without solving it: every diagnostic found, and a failing exit status on any error
+fn run_check(cli: &CliArgs) {
+    let problem = match (&cli.problem, cli.preset) {
+        (Some(path), _) => Problem::load_template(path, &cli.variables).unwrap_or_else(|e| fail(e)),
+        (None, Some(preset)) => preset.problem(),
+        (None, None) => {
+            if cli.positional.len() < 4 {
+                print_usage(&cli.program);
+                std::process::exit(1);
+            }
+            Problem {
+                inputs: parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e)),
+                target: parse_rate(&cli.positional[2], "target", "a rate").unwrap_or_else(|e| fail(e)),
+                tolerance: parse_rate(&cli.positional[3], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e)),
+                ..Problem::default()
+            }
+        }
+    };
+    let options = problem.search_options(&search_options(cli));
+    let diagnostics = feasibility::lint(&problem.inputs, problem.target, problem.tolerance, &options);
+    for diagnostic in &diagnostics {
+        match diagnostic.severity {
+            Severity::Error => println!("error: {}", diagnostic.message),
+            Severity::Warning => println!("warning: {}", diagnostic.message),
+        }
+    }
+    if diagnostics.is_empty() {
+        println!("No problems found.");
+    }
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        std::process::exit(1);
+    }
+}
+
+// Consolidate inputs into as few streams as --max-flow allows, with as few combines as possible
+fn run_merge(cli: &CliArgs) {
+    if cli.positional.len() < 2 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let inputs = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+    let options = search_options(cli);
+    let Some(solution) = merge::merge_streams(&inputs, &options) else {
+        match options.max_capacity() {
+            Some(capacity) if inputs.iter().any(|&rate| rate > capacity) => {
+                println!("No plan: an input already exceeds the belt capacity of {}.", capacity)
+            }
+            _ => println!("No plan: the profile allows no combine operation."),
+        }
+        return;
+    };
+    if cli.output == OutputFormat::Human {
+        println!("Merged {} input(s) into {} stream(s) with {} operation(s)", inputs.len(), solution.outputs.len(), solution.steps.len());
+    }
+    print_solution(cli, &solution, &options);
+}
+
+// Solve a pipeline file's stages in turn, each fed by the one before, and report them together
+fn run_pipeline(cli: &CliArgs) {
+    if cli.positional.len() < 2 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let pipeline = Pipeline::load_template(&cli.positional[1], &cli.variables).unwrap_or_else(|e| fail(e));
+    let options = search_options(cli);
+    let start_time = Instant::now();
+    let run = pipeline.run(&options);
+    for (index, ((name, solution), stage)) in run.plans.iter().zip(&pipeline.stages).enumerate() {
+        if cli.output == OutputFormat::Human {
+            println!("== Stage {} of {}: {} ==", index + 1, pipeline.stages.len(), name);
+        }
+        print_solution(cli, solution, &stage.problem.search_options(&options));
+    }
+    if let Some((name, inputs)) = &run.failed {
+        log("error", "search", &format!("No solution found for stage {} from inputs {:?}", name, inputs), &[]);
+        std::process::exit(1);
+    }
+    if cli.output == OutputFormat::Human {
+        let devices: usize = run.plans.iter().map(|(_, solution)| solution.steps.len()).sum();
+        let last = &pipeline.stages[pipeline.stages.len() - 1];
+        let (_, final_plan) = &run.plans[run.plans.len() - 1];
+        println!("== Pipeline ==");
+        println!("Stages: {}, devices: {}", run.plans.len(), devices);
+        println!("Final streams: {:?}", last.feed.rates(final_plan));
+        println!("Total time taken: {:?}", start_time.elapsed());
+    }
+}
+
+// Work backwards from the outputs needed to the fewest, smallest feeds that can produce them
+fn run_inverse(cli: &CliArgs) {
+    if cli.positional.len() < 3 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let desired = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+    let feeds = parse::parse_inputs(&cli.positional[2]).unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = match cli.positional.get(3) {
+        Some(value) => parse_rate(value, "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e)),
+        None => 0.0,
+    };
+
+    let options = search_options(cli);
+    let Some(solution) = inverse::solve_inverse(&desired, &feeds, can_be_off_by, &options) else {
+        note(cli, "search", &format!("No set of up to {} feeds produces those outputs.", inverse::MAX_FEEDS));
+        return;
+    };
+    // The rendered document carries the feeds as its inputs
+    note(cli, "search", &format!("Inputs needed: {:?}", solution.inputs));
+    let mut options = options;
+    options.goal = Some(GoalPredicate::outputs(desired.clone(), can_be_off_by));
+    let target = desired.iter().sum::<f64>() / desired.len() as f64;
+    solve_and_print(cli, solution.inputs, target, can_be_off_by, &options);
+}
+
+// Solve several independent flows, then count devices with shared operation types counted once per step
+fn run_multi(cli: &CliArgs) {
+    if cli.positional.len() < 2 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let options = search_options(cli);
+    let start_time = Instant::now();
+    let mut problems: Vec<(&str, Problem)> = Vec::new();
+
+    for spec in &cli.positional[1..] {
+        // Each flow is written as name=inputs:target:canBeOffBy
+        let (name, problem) = spec.split_once('=').unwrap_or_else(|| {
+            log("error", "setup", &format!("Invalid flow: {} (expected name=inputs:target:canBeOffBy)", spec), &[]);
+            std::process::exit(1);
+        });
+        let parts: Vec<&str> = problem.split(':').collect();
+        if parts.len() != 3 {
+            log("error", "setup", &format!("Invalid flow: {} (expected name=inputs:target:canBeOffBy)", spec), &[]);
+            std::process::exit(1);
+        }
+        let inputs = parse::parse_inputs(parts[0]).unwrap_or_else(|e| fail(e));
+        let target: f64 = parse_rate(parts[1], "target", "a rate").unwrap_or_else(|e| fail(e));
+        let can_be_off_by: f64 = parse_rate(parts[2], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e));
+        problems.push((name, Problem { inputs, target, tolerance: can_be_off_by, ..Problem::default() }));
+    }
+
+    // The flows are independent, so they are solved side by side and reported in order
+    let solver = Solver::new(options.clone());
+    #[cfg(feature = "parallel")]
+    let answers: Vec<Option<Solution>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = problems.iter().map(|(_, problem)| scope.spawn(|| solver.solve(problem))).collect();
+        handles.into_iter().map(|handle| handle.join().expect("Solver thread panicked")).collect()
+    });
+    #[cfg(not(feature = "parallel"))]
+    let answers: Vec<Option<Solution>> = problems.iter().map(|(_, problem)| solver.solve(problem)).collect();
+
+    let mut solved: Vec<(String, Solution)> = Vec::new();
+    for ((name, _), answer) in problems.iter().zip(answers) {
+        println!("== {} ==", name);
+        match answer {
+            Some(solution) => {
+                print_solution(cli, &solution, &options);
+                solved.push((name.to_string(), solution));
+            }
+            None => println!("No solution found."),
+        }
+    }
+
+    println!("== Combined plan ==");
+    let mut total = 0;
+    for operation in Operation::ALL {
+        let counts: Vec<(&str, usize)> = solved
+            .iter()
+            .map(|(name, solution)| (name.as_str(), solution.steps.iter().filter(|step| step.operation == operation).count()))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        if counts.is_empty() {
+            continue;
+        }
+        if cli.shared.contains(&operation) && counts.len() > 1 {
+            let devices = counts.iter().map(|&(_, count)| count).max().unwrap_or(0);
+            let names: Vec<&str> = counts.iter().map(|&(name, _)| name).collect();
+            println!("{}: {} device(s) (shared by {})", operation.name(), devices, names.join(", "));
+            total += devices;
+        } else {
+            let devices: usize = counts.iter().map(|&(_, count)| count).sum();
+            println!("{}: {} device(s)", operation.name(), devices);
+            total += devices;
+        }
+    }
+    println!("Total devices: {}", total);
+
+    let duration = start_time.elapsed();
+    println!("Total time taken: {:?}", duration);
+}
+
+// Parse `start..end:step` into the values it covers (end inclusive). Each part may be arithmetic as for
+// `parse::evaluate`
+fn parse_range(text: &str) -> Option<Vec<f64>> {
+    let (bounds, step) = text.split_once(':')?;
+    let (start, end) = bounds.split_once("..")?;
+    let (start, end, step) = (parse::evaluate(start)?, parse::evaluate(end)?, parse::evaluate(step)?);
+    if step <= 0.0 || end < start {
+        return None;
+    }
+    let count = ((end - start) / step + 1e-9).floor() as usize;
+    Some((0..=count).map(|index| start + step * index as f64).collect())
+}
+
+// Solve once per value of the swept target or tolerance and print a table of the results
+fn run_sweep(cli: &CliArgs) {
+    if cli.positional.len() < 4 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let inputs = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+
+    // Exactly one of target and canBeOffBy is a range
+    let (targets, tolerances) = match (parse_range(&cli.positional[2]), parse_range(&cli.positional[3])) {
+        (Some(targets), None) => (targets, vec![parse_rate(&cli.positional[3], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e))]),
+        (None, Some(tolerances)) => (vec![parse_rate(&cli.positional[2], "target", "a rate").unwrap_or_else(|e| fail(e))], tolerances),
+        _ => {
+            log("error", "setup", "Exactly one of target and canBeOffBy must be a range like 100..150:5", &[]);
+            std::process::exit(1);
+        }
+    };
+
+    let options = search_options(cli);
+    println!("{:>12} {:>12} {:>6} {:>8} {:>10} {:>12}", "target", "tolerance", "depth", "outputs", "remainder", "time");
+    for &target in &targets {
+        for &can_be_off_by in &tolerances {
+            let start_time = Instant::now();
+            let solution = shortest_path_to_target(inputs.clone(), target, can_be_off_by, &options);
+            let duration = format!("{:?}", start_time.elapsed());
+            match solution {
+                Some(solution) => println!(
+                    "{:>12} {:>12} {:>6} {:>8} {:>10} {:>12}",
+                    target,
+                    can_be_off_by,
+                    solution.steps.len(),
+                    solution.outputs.len(),
+                    solution.remainder.len(),
+                    duration
+                ),
+                None => println!("{:>12} {:>12} {:>6} {:>8} {:>10} {:>12}", target, can_be_off_by, "-", "-", "-", duration),
+            }
+        }
+    }
+}
+
+// Strategies `compare` tries when no --portfolio names them
+const COMPARED_STRATEGIES: [Strategy; 5] =
+    [Strategy::Greedy, Strategy::Shortest, Strategy::Weighted { step_cost: 1.0 }, Strategy::Weighted { step_cost: 10.0 }, Strategy::Beam { width: 256 }];
+
+// Solve one problem under every strategy and tie-breaking preference, one after another, and print a
+// table of what each cost and found, so the right mode can be picked from measurements
+fn run_compare(cli: &CliArgs) {
+    if cli.positional.len() < 4 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let inputs = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+    let target: f64 = parse_rate(&cli.positional[2], "target", "a rate").unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = parse_rate(&cli.positional[3], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e));
+    let strategies = if cli.portfolio.is_empty() { COMPARED_STRATEGIES.to_vec() } else { cli.portfolio.clone() };
+    // An explicit --prefer is held fixed; otherwise every preference is tried
+    let preferences = match cli.prefer {
+        Preference::None => vec![Preference::None, Preference::Splits, Preference::Combines],
+        prefer => vec![prefer],
+    };
+
+    // A run that found a plan
+    struct Run {
+        configuration: String,
+        seconds: f64,
+        nodes: u64,
+        steps: usize,
+        waste: f64,
+    }
+    let base = search_options(cli);
+    let mut found = Vec::new();
+    println!("{:<24} {:>12} {:>10} {:>6} {:>10} {:>10}", "configuration", "time", "nodes", "depth", "waste", "deviation");
+    for &strategy in &strategies {
+        for &prefer in &preferences {
+            let mut options = base.clone();
+            options.strategy = strategy;
+            options.prefer = prefer;
+            let configuration = match prefer {
+                Preference::None => strategy.name(),
+                prefer => format!("{} +{}", strategy.name(), prefer.name()),
+            };
+            let start_time = Instant::now();
+            let (solutions, stats) = search_solutions(inputs.clone(), target, can_be_off_by, &options, 1);
+            let elapsed = start_time.elapsed();
+            let duration = format!("{:?}", elapsed);
+            let Some(solution) = solutions.best() else {
+                let outcome = if stats.timed_out { "timed out" } else { "no plan" };
+                println!("{:<24} {:>12} {:>10} {:>6} {:>10} {:>10}", configuration, duration, stats.expanded, "-", "-", outcome);
+                continue;
+            };
+            let waste = solution.remainder.iter().fold(0.0, |total, part| total + part);
+            let deviation = solution.outputs.iter().map(|&output| (output - target).abs()).fold(0.0, f64::max);
+            println!(
+                "{:<24} {:>12} {:>10} {:>6} {:>10} {:>10}",
+                configuration,
+                duration,
+                stats.expanded,
+                solution.steps.len(),
+                cli.numbers.label(waste),
+                cli.numbers.label(deviation)
+            );
+            found.push(Run { configuration, seconds: elapsed.as_secs_f64(), nodes: stats.expanded, steps: solution.steps.len(), waste });
+        }
+    }
+
+    if found.is_empty() {
+        println!("No configuration found a plan.");
+        std::process::exit(1);
+    }
+    let best = |key: fn(&Run) -> f64| found.iter().min_by(|a, b| key(a).total_cmp(&key(b))).map_or("-", |run| run.configuration.as_str());
+    println!("Fastest: {}", best(|run| run.seconds));
+    println!("Fewest nodes: {}", best(|run| run.nodes as f64));
+    println!("Fewest steps: {}", best(|run| run.steps as f64));
+    println!("Least waste: {}", best(|run| run.waste));
+}
+
+// Samples of the frontier size kept for the explorer's history line
+const TUI_HISTORY: usize = 60;
+
+// Watch a search live: a status screen redrawn in place, driven by commands typed on stdin. Uses
+// plain ANSI escapes so it works in any terminal without extra dependencies
+fn run_tui(cli: &CliArgs) {
+    if cli.positional.len() < 4 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+    let inputs = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+    let target: f64 = parse_rate(&cli.positional[2], "target", "a rate").unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = parse_rate(&cli.positional[3], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e));
+
+    let control = SearchControl::new();
+    let mut options = search_options(cli);
+    options.control = Some(control.clone());
+    // Accepting early cancels the search; best effort then hands back the closest plan seen
+    options.best_effort = true;
+    let accepted = Arc::new(AtomicBool::new(false));
+    let quit = Arc::new(AtomicBool::new(false));
+
+    {
+        let (control, accepted, quit) = (control.clone(), Arc::clone(&accepted), Arc::clone(&quit));
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines() {
+                match line.as_deref().map(str::trim) {
+                    Ok("p") if control.is_paused() => control.resume(),
+                    Ok("p") => control.pause(),
+                    Ok("a") => {
+                        accepted.store(true, Ordering::Relaxed);
+                        control.cancel();
+                    }
+                    Ok("q") | Err(_) => {
+                        quit.store(true, Ordering::Relaxed);
+                        control.cancel();
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    let started = Instant::now();
+    let limit = cli.top_k.max(1);
+    let (solutions, stats) = std::thread::scope(|scope| {
+        let search = scope.spawn(|| search_solutions(inputs.clone(), target, can_be_off_by, &options, limit));
+        let mut history: Vec<u64> = Vec::new();
+        while !search.is_finished() {
+            history.push(control.frontier());
+            if history.len() > TUI_HISTORY {
+                history.remove(0);
+            }
+            let elapsed = started.elapsed().as_secs_f64();
+            let state = if control.is_paused() { "paused" } else { "searching" };
+            print!("\x1b[2J\x1b[H");
+            println!("Inputs {:?} -> target {} (±{})   [{}]", inputs, target, can_be_off_by, state);
+            println!();
+            println!("  Elapsed        {:.1}s", elapsed);
+            println!("  Expanded       {} ({:.0}/s)", control.expanded(), control.expanded() as f64 / elapsed.max(1e-9));
+            println!("  Frontier       {}", control.frontier());
+            println!("  Frontier trend {}", sparkline(&history));
+            match control.best_deviation() {
+                Some(deviation) => println!("  Best so far    {} from the target{}", deviation, if deviation <= can_be_off_by { " (in tolerance)" } else { "" }),
+                None => println!("  Best so far    -"),
+            }
+            println!();
+            println!("Type p + Enter to pause/resume, a to accept the best so far, q to quit");
+            std::thread::sleep(Duration::from_millis(250));
+        }
+        search.join().expect("search thread panicked")
+    });
+
+    print!("\x1b[2J\x1b[H");
+    if quit.load(Ordering::Relaxed) {
+        println!("Search abandoned after {} state(s).", stats.expanded);
+        return;
+    }
+    if accepted.load(Ordering::Relaxed) {
+        println!("Accepted the best plan after {} state(s).", stats.expanded);
+    }
+    if solutions.is_empty() {
+        println!("No solution found.");
+    }
+    for solution in &solutions {
+        print_solution(cli, solution, &options);
+    }
+    println!("Total time taken: {:?}", started.elapsed());
+}
+
+// Heights of a series as block characters, scaled to its own maximum
+fn sparkline(values: &[u64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values.iter().map(|&value| BLOCKS[(value * 7 / max) as usize]).collect()
+}
+
+// Nudges tried on each side of the target when no step is given
+const SENSITIVITY_STEPS: f64 = 5.0;
+
+// Solve the target nudged by up to ±delta and show how the plan's depth changes, to spot a nearby
+// goal that is much simpler to build (or one the current plan sits right on the edge of)
+fn run_sensitivity(cli: &CliArgs) {
+    if cli.positional.len() < 5 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let inputs = parse::parse_inputs(&cli.positional[1]).unwrap_or_else(|e| fail(e));
+    let target: f64 = parse_rate(&cli.positional[2], "target", "a rate").unwrap_or_else(|e| fail(e));
+    let can_be_off_by: f64 = parse_rate(&cli.positional[3], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e));
+    let (delta, step) = match cli.positional[4].split_once(':') {
+        Some((delta, step)) => (delta, Some(step)),
+        None => (cli.positional[4].as_str(), None),
+    };
+    let delta: f64 = parse_rate(delta, "delta", "a rate like 5 or 5:0.5").unwrap_or_else(|e| fail(e));
+    let step: f64 = match step {
+        Some(step) => parse_rate(step, "delta step", "a rate like 5 or 5:0.5").unwrap_or_else(|e| fail(e)),
+        None => delta / SENSITIVITY_STEPS,
+    };
+    if delta <= 0.0 || step <= 0.0 {
+        log("error", "setup", "Delta and step must be positive", &[]);
+        std::process::exit(1);
+    }
+
+    let options = search_options(cli);
+    let depth_at = |target: f64| shortest_path_to_target(inputs.clone(), target, can_be_off_by, &options).map(|solution| solution.steps.len());
+    let base = depth_at(target);
+    let count = (delta / step + 1e-9).floor() as i64;
+    println!("{:>10} {:>12} {:>6} {:>10}", "offset", "target", "depth", "change");
+    let mut simplest: Option<(f64, f64, usize)> = base.map(|depth| (0.0, target, depth)); // (offset, target, depth)
+    for index in -count..=count {
+        // Rounded so repeated steps like 0.8 print as 2.4 rather than 2.4000000000000004
+        let offset = (step * index as f64 * 1e9).round() / 1e9;
+        let nudged = ((target + offset) * 1e9).round() / 1e9;
+        let depth = if index == 0 { base } else { depth_at(nudged) };
+        let change = match (base, depth) {
+            _ if index == 0 => "base".to_string(),
+            (Some(base), Some(depth)) => format!("{:+}", depth as i64 - base as i64),
+            (None, Some(_)) => "feasible".to_string(),
+            (Some(_), None) => "infeasible".to_string(),
+            (None, None) => "-".to_string(),
+        };
+        let shown = depth.map_or("-".to_string(), |depth| depth.to_string());
+        println!("{:>+10} {:>12} {:>6} {:>10}", offset, nudged, shown, change);
+        if let Some(depth) = depth {
+            // Fewest steps wins; on a tie, the nudge closest to the real target
+            if simplest.is_none_or(|(best, _, fewest)| depth < fewest || (depth == fewest && offset.abs() < best.abs())) {
+                simplest = Some((offset, nudged, depth));
+            }
+        }
+    }
+    match (simplest, base) {
+        (Some((_, nudged, depth)), Some(base)) if depth < base => {
+            println!("Simplest nearby: target {} in {} step(s), {} fewer than {}", nudged, depth, base - depth, target)
+        }
+        (Some((_, nudged, depth)), None) => println!("Nearest feasible: target {} in {} step(s)", nudged, depth),
+        (Some(_), Some(_)) => println!("No nudge within ±{} makes the plan simpler", delta),
+        (None, _) => println!("No target within ±{} is reachable", delta),
+    }
+}
+
+// Beyond this many states a dumped search tree is too large for Graphviz to lay out usefully
+const SEARCH_TREE_LIMIT: usize = 2000;
+
+fn solve_and_print(cli: &CliArgs, inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) {
     let start_time = Instant::now(); // Start the timer
 
-    if let Some((final_output, remainder, path)) = shortest_path_to_target(inputs, target, can_be_off_by) {
-        println!("Final Outputs: {:?}", final_output);
-        println!("Remainder: {:?}", remainder);
-        for step in path {
-            println!("{}", step);
+    // A one-sided band is searched for as a symmetric one around its middle, then reported against the
+    // target asked for
+    let band = match cli.tolerance {
+        Some(tolerance) => tolerance.band(target, cli.tolerance_mode),
+        None => Tolerance::new(can_be_off_by, cli.tolerance_mode),
+    };
+    let requested_target = target;
+    let (target, can_be_off_by) = band.centered(target);
+
+    let mut options = options.clone();
+    if options.control.is_none() {
+        options.control = Some(interrupt_control());
+    }
+    // With a hint, only the completion is searched for, from the state its steps leave
+    let original_inputs = inputs.clone();
+    let inputs = match &cli.hint {
+        Some(hint) => {
+            let hinted = hint.apply(&inputs).unwrap_or_else(|message| fail(Error::InvalidProblem(format!("the hint does not fit the inputs: {}", message))));
+            options.max_depth = options.max_depth.saturating_sub(hint.steps.len());
+            log(
+                "info",
+                "hint",
+                &format!("Starting from {} hinted step(s), which leave {:?}", hint.steps.len(), hinted),
+                &[("steps", hint.steps.len() as f64)],
+            );
+            hinted
         }
-    } else {
+        None => inputs,
+    };
+    let tree = cli.dump_tree.as_ref().map(|_| {
+        let (sink, tree) = SearchTree::recorder(SEARCH_TREE_LIMIT);
+        options.events = Some(sink);
+        tree
+    });
+    let recording = cli.record.as_ref().map(|_| {
+        let (sink, events) = trace::recorder(options.events.take());
+        options.events = Some(sink);
+        events
+    });
+    let options = &options;
+
+    // A missing cache is just a first run; an unreadable one is ignored rather than fatal
+    let cache = cli.cache.as_ref().and_then(|path| match SearchCache::load(path) {
+        Ok(cache) => Some(cache),
+        Err(Error::Io { .. }) => None,
+        Err(e) => {
+            log("warn", "cache", &format!("ignoring cache: {}", e), &[]);
+            None
+        }
+    });
+    let mut memo = cli.memo.as_ref().and_then(|path| match Memo::load(path) {
+        Ok(memo) => Some(memo),
+        Err(Error::Io { .. }) => Some(Memo::new()),
+        Err(e) => {
+            log("warn", "memo", &format!("ignoring memo: {}", e), &[]);
+            None
+        }
+    });
+    let cached_inputs = inputs.clone(); // Kept for the cache
+    let search = |options: &SearchOptions| match (&cli.cache, &memo) {
+        (None, None) => match cli.enumerate_optimal {
+            Some(limit) => enumerate_optimal(inputs, target, can_be_off_by, options, limit.max(1)),
+            None if !cli.portfolio.is_empty() => race_strategies(cli, inputs, target, can_be_off_by, options),
+            None if cli.time_budget.is_some() => tune_to_budget(cli, inputs, target, can_be_off_by, options),
+            None => search_solutions(inputs, target, can_be_off_by, options, cli.top_k.max(1)),
+        },
+        _ => {
+            let mut reused = cache.as_ref().map_or_else(Vec::new, |cache| cache.reusable(&inputs, target, can_be_off_by));
+            if reused.is_empty() {
+                reused = memo.as_ref().map_or_else(Vec::new, |memo| memo.recall(&inputs, target, can_be_off_by, options));
+                if !reused.is_empty() {
+                    log("info", "memo", &format!("Recalled {} plan(s) from the memo", reused.len()), &[("recalled", reused.len() as f64)]);
+                }
+            }
+            let (solutions, stats, reused) = warm_start::resolve_with(reused, inputs, target, can_be_off_by, options, cli.top_k.max(1));
+            if reused > 0 && cli.cache.is_some() {
+                log("info", "cache", &format!("Reused {} cached plan(s)", reused), &[("reused", reused as f64)]);
+            }
+            (solutions, stats)
+        }
+    };
+    let (mut solutions, stats) = if cli.progress { with_progress(options, search) } else { search(options) };
+    if let Some(path) = &cli.cache {
+        if let Err(e) = SearchCache::new(cached_inputs.clone(), target, can_be_off_by, &solutions).save(path) {
+            log("error", "cache", &format!("Failed to write cache: {}", e), &[]);
+        }
+    }
+    if let (Some(path), Some(memo)) = (&cli.memo, &mut memo) {
+        let learned: usize = solutions.iter().map(|solution| memo.learn(solution)).sum();
+        if learned > 0 {
+            log("info", "memo", &format!("Learned {} sub-state plan(s); the memo holds {} sub-state(s)", learned, memo.len()), &[("learned", learned as f64)]);
+            if let Err(e) = memo.save(path) {
+                log("error", "memo", &format!("Failed to write memo: {}", e), &[]);
+            }
+        }
+    }
+    if LOG_JSON.load(Ordering::Relaxed) {
+        let depth = solutions.iter().next().map_or(-1.0, |solution| solution.steps.len() as f64);
+        let counters = [
+            ("expanded", stats.expanded as f64),
+            ("solutions", solutions.len() as f64),
+            ("depth", depth),
+            ("depth_limited", stats.depth_limited as f64),
+            ("capacity_rejected", stats.capacity_rejected as f64),
+            ("timed_out", if stats.timed_out { 1.0 } else { 0.0 }),
+            ("interrupted", if stats.cancelled { 1.0 } else { 0.0 }),
+            ("elapsed", start_time.elapsed().as_secs_f64()),
+        ];
+        log("info", "search", "search finished", &counters);
+    }
+    if stats.cancelled {
+        log("warn", "search", "interrupted; reporting the best found so far", &[("expanded", stats.expanded as f64)]);
+    }
+    if stats.timed_out {
+        let message = match (cli.time_budget, options.timeout) {
+            (Some(budget), _) => format!("Search ran out of its time budget of {:?}", budget),
+            (None, Some(timeout)) => format!("Search timed out after {:?}", timeout),
+            (None, None) => "Search timed out".to_string(),
+        };
+        log("warn", "search", &message, &[("expanded", stats.expanded as f64)]);
+    }
+    if let Some(expanded) = stats.fallback_at {
+        log(
+            "warn",
+            "search",
+            &format!("memory limit reached after {} expansions; went on with a memory-light depth-first search", expanded),
+            &[("fallback_at", expanded as f64)],
+        );
+    }
+    if options.profile_internal {
+        print_timings(&stats.timings);
+    }
+    if let (Some(path), Some(events)) = (&cli.record, recording) {
+        let events = std::mem::take(&mut *events.lock().unwrap());
+        let count = events.len();
+        let trace = Trace { arguments: cli.arguments.clone(), inputs: cached_inputs.clone(), target, tolerance: can_be_off_by, events };
+        match trace.save(path) {
+            Ok(()) => log("info", "trace", &format!("Recorded {} search event(s) in {}", count, path), &[("events", count as f64)]),
+            Err(e) => log("error", "trace", &format!("Failed to write trace: {}", e), &[]),
+        }
+    }
+    if let (Some(path), Some(tree)) = (&cli.dump_tree, tree) {
+        let tree = tree.lock().unwrap();
+        if let Err(e) = std::fs::write(path, tree.to_dot()) {
+            log("error", "output", &format!("Failed to write {}: {}", path, e), &[]);
+        } else if tree.truncated() {
+            log("warn", "output", &format!("search tree truncated to the first {} states in {}", SEARCH_TREE_LIMIT, path), &[]);
+        }
+    }
+    if let Some(hint) = &cli.hint {
+        solutions = solutions.into_iter().map(|solution| hint.complete(original_inputs.clone(), solution)).collect();
+    }
+    if !band.is_symmetric() {
+        solutions = solutions.into_iter().map(|solution| Solution { target: requested_target, ..solution }).collect();
+    }
+    if let Some(total) = cli.demand {
+        // The range's middle only steered the search: report each plan against its own split of the demand
+        let share = |solution: &Solution| total / solution.outputs.len().max(1) as f64;
+        solutions = solutions.into_iter().map(|solution| Solution { target: share(&solution), ..solution }).collect();
+    }
+    if !cli.rank.is_empty() {
+        solutions.rank(&cli.rank);
+    }
+    if cli.output != OutputFormat::Human {
+        // Machine-readable formats keep stdout free of anything but the documents
+        if solutions.is_empty() {
+            log("info", "search", "No solution found.", &[]);
+        }
+        for solution in &solutions {
+            if cli.output == OutputFormat::Html {
+                // Only the report has room for how the search went
+                print!("{}", render::html(solution, Some(&stats), &cli.numbers));
+            } else {
+                print_solution(cli, solution, options);
+            }
+        }
+        if stats.cancelled {
+            std::process::exit(INTERRUPTED_STATUS);
+        }
+        return;
+    }
+
+    if stats.cancelled {
+        // Whatever else was found, say how far the search got and how close it came
+        println!("Interrupted after {:?}.", start_time.elapsed());
+        for line in explain_failure(&stats, can_be_off_by, options) {
+            println!("{}", line);
+        }
+    } else if solutions.is_empty() {
         println!("No solution found.");
+        match &stats.infeasible {
+            // Proved before searching, so there are no search statistics to explain
+            Some(proof) => println!("Impossible: {}", proof),
+            None => {
+                for line in explain_failure(&stats, can_be_off_by, options) {
+                    println!("{}", line);
+                }
+            }
+        }
+    }
+    let numbered = cli.top_k > 1 || cli.enumerate_optimal.is_some();
+    for (index, solution) in solutions.iter().enumerate() {
+        if options.best_effort && solution.outputs.iter().any(|&output| (output - target).abs() > can_be_off_by) {
+            println!("No plan within tolerance; closest in {} operation(s) or fewer:", options.max_depth);
+        }
+        if numbered {
+            println!("== Solution {} of {} ==", index + 1, solutions.len());
+        }
+        print_solution(cli, solution, options);
     }
 
     let duration = start_time.elapsed(); // Calculate time taken
     println!("Total time taken: {:?}", duration);
+    if stats.cancelled {
+        std::process::exit(INTERRUPTED_STATUS);
+    }
 }
 
-// Helper function to scale values for integer representation
-fn scale_value(value: f64) -> i64 {
-    (value * SCALE) as i64
+// Search within --time-budget, letting the tuner choose the settings, and report each round it ran
+fn tune_to_budget(cli: &CliArgs, inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) -> (SolutionSet, SearchStats) {
+    let mut options = options.clone();
+    // The tuner deepens as far as it needs unless told otherwise
+    if cli.max_depth.is_none() && cli.best_effort_depth.is_none() {
+        options.max_depth = options.max_depth.max(autotune::MAX_DEPTH);
+    }
+    let budget = cli.time_budget.expect("only called with --time-budget");
+    let run = autotune::solve_within(inputs, target, can_be_off_by, &options, budget, cli.top_k.max(1));
+    for (index, round) in run.rounds.iter().enumerate() {
+        let outcome = match (round.found, round.timed_out) {
+            (Some(steps), _) => format!("found a {}-step plan", steps),
+            (None, true) => "ran out of time".to_string(),
+            (None, false) => "found nothing".to_string(),
+        };
+        log(
+            "info",
+            "tune",
+            &format!("Round {}: {} to depth {} {} in {:?}", index + 1, round.strategy.name(), round.max_depth, outcome, round.elapsed),
+            &[("expanded", round.expanded as f64)],
+        );
+    }
+    (run.solutions, run.stats)
 }
 
-// New function for calculating heuristic based on proximity to target
-fn calculate_heuristic(values: &[i64], target: i64) -> i64 {
-    values.iter().map(|&v| (v - target).abs()).sum()
+// Run the --portfolio strategies side by side, reporting how each did, and keep the winner's plans
+#[cfg(feature = "parallel")]
+fn race_strategies(cli: &CliArgs, inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) -> (SolutionSet, SearchStats) {
+    let run = portfolio::solve_portfolio(inputs, target, can_be_off_by, options, &cli.portfolio, cli.portfolio_mode, cli.top_k.max(1));
+    for (index, entry) in run.entries.iter().enumerate() {
+        let verdict = match (run.winner == Some(index), entry.solutions.best()) {
+            (true, _) => "won",
+            (false, Some(_)) => "found a plan",
+            (false, None) if entry.stats.cancelled => "cancelled",
+            (false, None) => "found nothing",
+        };
+        let depth = entry.solutions.best().map_or(-1.0, |solution| solution.steps.len() as f64);
+        log(
+            "info",
+            "portfolio",
+            &format!("{:<14} {:<13} {:>9} expanded in {:?}", entry.strategy.name(), verdict, entry.stats.expanded, entry.elapsed),
+            &[("expanded", entry.stats.expanded as f64), ("depth", depth), ("elapsed", entry.elapsed.as_secs_f64())],
+        );
+    }
+    // Without a winner, the first strategy's statistics explain the failure
+    let index = run.winner.unwrap_or(0);
+    let entry = run.entries.into_iter().nth(index).expect("a portfolio runs at least one strategy");
+    (entry.solutions, entry.stats)
 }
 
-// Operation to split a value into two equal parts
-fn split_into_two(input: f64) -> (f64, f64) {
-    (input / 2.0, input / 2.0)
+#[cfg(not(feature = "parallel"))]
+fn race_strategies(_: &CliArgs, _: Vec<f64>, _: f64, _: f64, _: &SearchOptions) -> (SolutionSet, SearchStats) {
+    fail(Error::InvalidValue { what: "--portfolio".to_string(), text: String::new(), expected: "a build with the parallel feature" })
 }
 
-// Operation to split a value into three equal parts
-fn split_into_three(input: f64) -> (f64, f64, f64) {
-    let part = input / 3.0;
-    (part, part, part)
+// Re-render a saved JSON solution in another format without solving again
+fn run_convert(cli: &CliArgs) {
+    if cli.positional.len() < 3 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let path = &cli.positional[1];
+    let format = OutputFormat::from_name(&cli.positional[2]).unwrap_or_else(|| {
+        log("error", "setup", &format!("Unknown output format: {} (expected {})", cli.positional[2], OUTPUT_FORMATS), &[]);
+        std::process::exit(1);
+    });
+    let solution = load_solution(path);
+    print!("{}", render::render_with(&solution, format, &cli.numbers));
 }
 
-// Operation to combine two values by summing them
-fn combine_two(a: f64, b: f64) -> f64 {
-    a + b
+// List the built-in profiles and every pack installed in the pack directories
+fn run_packs() {
+    let (registry, errors) = PackRegistry::discover();
+    for pack in registry.iter() {
+        println!("{:<16} {}", pack.name, pack.description);
+    }
+    for error in errors {
+        log("warn", "packs", &format!("skipping pack: {}", error), &[]);
+    }
 }
 
-// Operation to combine three values by summing them
-fn combine_three(a: f64, b: f64, c: f64) -> f64 {
-    a + b + c
+// Run a recorded search again with the options and problem it was recorded with, and report the first
+// event where the two runs differ: the point a change to pruning or the heuristics took effect
+fn run_replay(cli: &CliArgs) {
+    let Some(path) = cli.positional.get(1) else {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    };
+    let trace = Trace::load(path).unwrap_or_else(|e| fail(e));
+    let recorded = parse_args([cli.program.clone()].into_iter().chain(trace.arguments.iter().cloned()).collect()).unwrap_or_else(|e| fail(e));
+    if recorded.cache.is_some() {
+        log("warn", "trace", "the trace was recorded with --cache; the replay searches without the cached plans", &[]);
+    }
+    let mut options = search_options(&recorded);
+    let (sink, events) = trace::recorder(None);
+    options.events = Some(sink);
+    let (inputs, target, can_be_off_by) = (trace.inputs.clone(), trace.target, trace.tolerance);
+    match recorded.enumerate_optimal {
+        Some(limit) => enumerate_optimal(inputs, target, can_be_off_by, &options, limit.max(1)),
+        None => search_solutions(inputs, target, can_be_off_by, &options, recorded.top_k.max(1)),
+    };
+    let replayed = events.lock().unwrap();
+    match trace::first_divergence(&trace.events, &replayed) {
+        None => println!("Replayed {} event(s): identical to the recording", replayed.len()),
+        Some(index) => {
+            println!("Diverged at event {} of {} recorded ({} replayed)", index, trace.events.len(), replayed.len());
+            println!("  recorded: {}", trace.events.get(index).map_or("(end of trace)".to_string(), |event| format!("{:?}", event)));
+            println!("  replayed: {}", replayed.get(index).map_or("(end of search)".to_string(), |event| format!("{:?}", event)));
+            std::process::exit(1);
+        }
+    }
 }
 
-// Main A* function to find the shortest path to target
-fn shortest_path_to_target(inputs: Vec<f64>, target: f64, can_be_off_by: f64) -> Option<(Vec<f64>, Vec<f64>, Vec<String>)> {
-    let mut priority_queue = BinaryHeap::new();
-    let mut visited = HashMap::new();
+// List the built-in problems --preset can solve, with their numbers
+fn run_presets() {
+    for preset in &PRESETS {
+        let problem = preset.problem();
+        println!("{:<28} {}", preset.name, preset.description);
+        println!("{:<28} inputs {:?}, target {}, canBeOffBy {}", "", problem.inputs, problem.target, problem.tolerance);
+    }
+}
 
-    // Scaling parameters
-    let scaled_target = scale_value(target);
-    let scaled_margin = scale_value(can_be_off_by);
+// Runs of each case per `bench`, unless given
+const BENCH_RUNS: usize = 5;
+const BENCH_HISTORY: &str = "bench-history.jsonl";
 
-    // Initialize with the starting node
-    let start = Node {
-        values: inputs.iter().map(|&v| scale_value(v)).collect(),
-        path: vec![],
-        depth: 0,
-        estimated_cost: calculate_heuristic(&inputs.iter().map(|&v| scale_value(v)).collect::<Vec<i64>>(), scaled_target),
+// Time a fixed set of problems, record the run and, with --compare, fail on a significant slowdown
+// against the run recorded before it
+fn run_bench(cli: &CliArgs) {
+    let runs = match cli.positional.get(1) {
+        Some(text) => parse_value(text, "runs", "a whole number").unwrap_or_else(|e| fail(e)),
+        None => BENCH_RUNS,
     };
-    priority_queue.push(start.clone());
-    visited.insert(start.values.clone(), start.depth);
+    let path = cli.history.as_deref().unwrap_or(BENCH_HISTORY);
+    let history = bench::load_history(path).unwrap_or_else(|e| fail(e));
+    let run = BenchRun::measure(&search_options(cli), runs.max(1));
+
+    println!("{:<28} {:>12} {:>12}", "case", "mean", "fastest");
+    for (case, times) in &run.samples {
+        let fastest = times.iter().copied().fold(f64::INFINITY, f64::min);
+        println!("{:<28} {:>12?} {:>12?}", case, Duration::from_secs_f64(bench::mean(times)), Duration::from_secs_f64(fastest));
+    }
+    bench::append_history(path, &run).unwrap_or_else(|e| fail(e));
+    println!("Recorded in {}", path);
 
-    while let Some(current) = priority_queue.pop() {
-        // Evaluate for target proximity
-        if let Some(final_values) = find_final_and_remainder(&current.values, scaled_target, scaled_margin) {
-            let (final_output_scaled, remainder_scaled) = final_values;
-            let final_output = final_output_scaled.iter().map(|&v| v as f64 / SCALE).collect();
-            let remainder = remainder_scaled.iter().map(|&v| v as f64 / SCALE).collect();
-            println!("Solution found at level {}", current.depth);
-            return Some((final_output, remainder, current.path.clone()));
+    if cli.compare {
+        let Some(baseline) = history.last() else {
+            println!("No earlier run in {} to compare against.", path);
+            return;
+        };
+        let regressions = run.regressions(baseline);
+        if regressions.is_empty() {
+            println!("No significant slowdown against the previous run.");
+            return;
+        }
+        for regression in &regressions {
+            println!(
+                "Slower: {} took {:?} against {:?} before ({:+.1}%, t = {:.1})",
+                regression.case,
+                Duration::from_secs_f64(regression.current),
+                Duration::from_secs_f64(regression.baseline),
+                (regression.current / regression.baseline - 1.0) * 100.0,
+                regression.t
+            );
         }
+        std::process::exit(1);
+    }
+}
 
-        // Avoid unnecessary depth
-        if current.depth >= MAX_DEPTH {
-            continue;
+// Where `serve` listens when no address is given
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+// Answer JSON problems over HTTP until killed, with Prometheus metrics at /metrics
+fn run_serve(cli: &CliArgs) {
+    let address = cli.positional.get(1).map_or(DEFAULT_ADDRESS, String::as_str);
+    let server = Server::with_limits(search_options(cli), cli.server_limits);
+    log("info", "server", &format!("Listening on http://{} (POST /solve, GET /live, GET /metrics)", address), &[]);
+    if let Err(e) = server.run(address) {
+        fail(e);
+    }
+}
+
+// Split one problem across `worker` processes on other machines and print the first plan any of them finds
+#[cfg(feature = "parallel")]
+fn run_coordinate(cli: &CliArgs) {
+    let workers = cli.positional.get(2).and_then(|count| count.parse().ok()).filter(|&count: &usize| count > 0);
+    let document = match (&cli.problem, cli.preset) {
+        (Some(path), _) => Problem::read_document(path, &cli.variables).unwrap_or_else(|e| fail(e)),
+        (None, Some(preset)) => json::parse(preset.problem).expect("built-in presets are valid JSON"),
+        (None, None) if cli.positional.len() >= 6 => {
+            let inputs = parse::parse_inputs(&cli.positional[3]).unwrap_or_else(|e| fail(e));
+            let mut fields = BTreeMap::new();
+            fields.insert("inputs".to_string(), Json::Array(inputs.into_iter().map(Json::Number).collect()));
+            fields.insert("target".to_string(), Json::Number(parse_rate(&cli.positional[4], "target", "a rate").unwrap_or_else(|e| fail(e))));
+            fields.insert("tolerance".to_string(), Json::Number(parse_rate(&cli.positional[5], "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e))));
+            Json::Object(fields)
         }
+        _ => {
+            print_usage(&cli.program);
+            std::process::exit(1);
+        }
+    };
+    let (address, Some(workers)) = (&cli.positional[1], workers) else {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    };
 
-        // Explore operations: split/combine
-        for i in 0..current.values.len() {
-            let value = current.values[i];
-            let mut new_values;
-            let mut new_path;
-
-            // Apply each operation (split/combine) and check result immediately
-
-            // Split into two
-            let (part1, part2) = split_into_two(value as f64 / SCALE);
-            new_values = current.values.clone();
-            new_values.remove(i);
-            new_values.push(scale_value(part1));
-            new_values.push(scale_value(part2));
-            new_path = current.path.clone();
-            new_path.push(format!("{} -> [{}, {}]", value as f64 / SCALE, part1, part2));
-
-            let estimated_cost = calculate_heuristic(&new_values, scaled_target);
-            let new_node = Node {
-                values: new_values.clone(),
-                path: new_path.clone(),
-                depth: current.depth + 1,
-                estimated_cost,
-            };
+    let start_time = Instant::now();
+    log("info", "coordinator", &format!("Waiting on {} for {} worker(s)", address, workers), &[("workers", workers as f64)]);
+    let run = distributed::coordinate(address, &document, workers, |event| match event {
+        WorkerEvent::Joined { worker, peer } => log("info", "coordinator", &format!("Worker {} joined from {}", worker + 1, peer), &[]),
+        WorkerEvent::Progress { worker, expanded } => {
+            log("info", "coordinator", &format!("Worker {} has expanded {} node(s)", worker + 1, expanded), &[("expanded", expanded as f64)])
+        }
+        WorkerEvent::Finished { worker, expanded, found } => log(
+            "info",
+            "coordinator",
+            &format!("Worker {} {} after {} node(s)", worker + 1, if found { "found a plan" } else { "finished" }, expanded),
+            &[("expanded", expanded as f64)],
+        ),
+        WorkerEvent::Lost { worker } => log("warn", "coordinator", &format!("Worker {} hung up without an answer", worker + 1), &[]),
+    })
+    .unwrap_or_else(|e| fail(e));
 
-            // Prune based on heuristic and if state has been reached at lower cost
-            if !visited.contains_key(&new_node.values) || visited[&new_node.values] > new_node.depth {
-                priority_queue.push(new_node.clone());
-                visited.insert(new_node.values.clone(), new_node.depth);
-            }
-
-            // Split into three
-            let (part1, part2, part3) = split_into_three(value as f64 / SCALE);
-            new_values = current.values.clone();
-            new_values.remove(i);
-            new_values.push(scale_value(part1));
-            new_values.push(scale_value(part2));
-            new_values.push(scale_value(part3));
-            new_path = current.path.clone();
-            new_path.push(format!("{} -> [{}, {}, {}]", value as f64 / SCALE, part1, part2, part3));
-
-            let estimated_cost = calculate_heuristic(&new_values, scaled_target);
-            let new_node = Node {
-                values: new_values.clone(),
-                path: new_path.clone(),
-                depth: current.depth + 1,
-                estimated_cost,
-            };
+    let problem = Problem::from_json(&document).unwrap_or_else(|e| fail(e));
+    let Some(solution) = run.plan() else {
+        log("error", "search", "No solution found", &[("expanded", run.expanded() as f64)]);
+        if run.workers.iter().any(|worker| worker.lost) {
+            println!("Bound: some workers hung up, so their part of the search is unexplored");
+        }
+        std::process::exit(1);
+    };
+    print_solution(cli, solution, &problem.search_options(&search_options(cli)));
+    if cli.output == OutputFormat::Human {
+        println!("Workers: {}, nodes expanded across them: {}", run.workers.len(), run.expanded());
+        println!("Total time taken: {:?}", start_time.elapsed());
+    }
+}
 
-            if !visited.contains_key(&new_node.values) || visited[&new_node.values] > new_node.depth {
-                priority_queue.push(new_node.clone());
-                visited.insert(new_node.values.clone(), new_node.depth);
-            }
-
-            // Combine two values
-            for j in (i+1)..current.values.len() {
-                let other_value = current.values[j];
-                let combined = combine_two(value as f64 / SCALE, other_value as f64 / SCALE);
-                new_values = current.values.clone();
-                new_values.remove(i);
-                new_values.remove(j - 1); // Adjust index after removal
-                new_values.push(scale_value(combined));
-                new_path = current.path.clone();
-                new_path.push(format!("{} + {} -> {}", value as f64 / SCALE, other_value as f64 / SCALE, combined));
-
-                let estimated_cost = calculate_heuristic(&new_values, scaled_target);
-                let new_node = Node {
-                    values: new_values.clone(),
-                    path: new_path.clone(),
-                    depth: current.depth + 1,
-                    estimated_cost,
-                };
+// Search the part of a problem a coordinator hands out, then exit
+#[cfg(feature = "parallel")]
+fn run_worker(cli: &CliArgs) {
+    let Some(address) = cli.positional.get(1) else {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    };
+    log("info", "worker", &format!("Joining the coordinator at {}", address), &[]);
+    let (plan, stats) = distributed::work(address, &search_options(cli)).unwrap_or_else(|e| fail(e));
+    let outcome = if plan.is_some() { "found a plan" } else if stats.cancelled { "was stopped" } else { "found nothing" };
+    log("info", "worker", &format!("Part searched: {} after {} node(s)", outcome, stats.expanded), &[("expanded", stats.expanded as f64)]);
+}
+
+// Bring a saved plan back in line with inputs that drifted, changing as few devices as possible
+fn run_repair(cli: &CliArgs) {
+    if cli.positional.len() < 3 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
+
+    let old = load_solution(&cli.positional[1]);
+    let inputs = parse::parse_inputs(&cli.positional[2]).unwrap_or_else(|e| fail(e));
+    if inputs.len() != old.inputs.len() {
+        fail(Error::InvalidValue {
+            what: "inputs".to_string(),
+            text: cli.positional[2].clone(),
+            expected: "as many rates as the saved plan's inputs, in the same order",
+        });
+    }
+    // Without a tolerance, the plan must stay as close to the target as it already was
+    let can_be_off_by = match cli.positional.get(3) {
+        Some(text) => parse_rate(text, "canBeOffBy", "a rate").unwrap_or_else(|e| fail(e)),
+        None => old.outputs.iter().map(|&output| (output - old.target).abs()).fold(0.0, f64::max),
+    };
+    let options = search_options(cli);
+    let start_time = Instant::now();
+    let Some(repair) = repair::repair(&old, inputs, can_be_off_by, &options) else {
+        log("error", "search", "No repair found: the saved plan does not replay on its own inputs, or nothing completes it", &[]);
+        std::process::exit(1);
+    };
+    log(
+        "info",
+        "repair",
+        &format!(
+            "Kept {} step(s) as they were and {} with new rates; dropped {} and added {}",
+            repair.kept, repair.adjusted, repair.dropped, repair.added
+        ),
+        &[("modifications", repair.modifications() as f64)],
+    );
+    print_solution(cli, &repair.plan, &options);
+    if cli.output == OutputFormat::Human {
+        match repair.modifications() {
+            0 => println!("Repair: no device changes; {} step(s) carry new rates", repair.adjusted),
+            count => println!("Repair: {} device change(s), {} removed and {} added", count, repair.dropped, repair.added),
+        }
+        for change in diff::describe(&old, &repair.plan) {
+            println!("  {}", change);
+        }
+        println!("Total time taken: {:?}", start_time.elapsed());
+    }
+}
+
+// Compare two saved JSON solutions structurally
+fn run_diff(cli: &CliArgs) {
+    if cli.positional.len() < 3 {
+        print_usage(&cli.program);
+        std::process::exit(1);
+    }
 
-                if !visited.contains_key(&new_node.values) || visited[&new_node.values] > new_node.depth {
-                    priority_queue.push(new_node.clone());
-                    visited.insert(new_node.values.clone(), new_node.depth);
+    let old = load_solution(&cli.positional[1]);
+    let new = load_solution(&cli.positional[2]);
+    let changes = diff::describe(&old, &new);
+    if changes.is_empty() {
+        println!("Solutions are equivalent.");
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+}
+
+// Run `search` while a second thread writes a JSON progress line to stderr every quarter second
+fn with_progress<T: Send>(options: &SearchOptions, search: impl FnOnce(&SearchOptions) -> T + Send) -> T {
+    let control = options.control.clone().unwrap_or_default();
+    let mut options = options.clone();
+    options.control = Some(control.clone());
+    let started = Instant::now();
+    let done = AtomicBool::new(false);
+
+    let event = |kind: &str| {
+        let elapsed = started.elapsed().as_secs_f64();
+        let mut fields = BTreeMap::new();
+        fields.insert("event".to_string(), Json::String(kind.to_string()));
+        fields.insert("elapsed".to_string(), Json::Number(elapsed));
+        fields.insert("expanded".to_string(), Json::Number(control.expanded() as f64));
+        fields.insert("nodes_per_sec".to_string(), Json::Number((control.expanded() as f64 / elapsed).round()));
+        fields.insert("frontier".to_string(), Json::Number(control.frontier() as f64));
+        fields.insert("best_deviation".to_string(), control.best_deviation().map_or(Json::Null, Json::Number));
+        eprintln!("{}", Json::Object(fields));
+    };
+
+    let result = std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                std::thread::sleep(Duration::from_millis(250));
+                if !done.load(Ordering::Relaxed) {
+                    event("progress");
                 }
             }
+        });
+        let result = search(&options);
+        done.store(true, Ordering::Relaxed);
+        result
+    });
+    event("done");
+    result
+}
 
-            // Combine three values
-            for j in (i+1)..current.values.len() {
-                for k in (j+1)..current.values.len() {
-                    let value_b = current.values[j];
-                    let value_c = current.values[k];
-                    let combined = combine_three(value as f64 / SCALE, value_b as f64 / SCALE, value_c as f64 / SCALE);
-                    new_values = current.values.clone();
-                    new_values.remove(i);
-                    new_values.remove(j - 1);
-                    new_values.remove(k - 2); // Adjust indices after each removal
-                    new_values.push(scale_value(combined));
-                    new_path = current.path.clone();
-                    new_path.push(format!("{} + {} + {} -> {}", value as f64 / SCALE, value_b as f64 / SCALE, value_c as f64 / SCALE, combined));
-
-                    let estimated_cost = calculate_heuristic(&new_values, scaled_target);
-                    let new_node = Node {
-                        values: new_values.clone(),
-                        path: new_path.clone(),
-                        depth: current.depth + 1,
-                        estimated_cost,
-                    };
-
-                    if !visited.contains_key(&new_node.values) || visited[&new_node.values] > new_node.depth {
-                        priority_queue.push(new_node.clone());
-                        visited.insert(new_node.values.clone(), new_node.depth);
-                    }
-                }
+fn load_solution(path: &str) -> Solution {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|source| fail(Error::Io { path: path.to_string(), source }));
+    json::parse(&text)
+        .and_then(|document| render::from_json(&document))
+        .unwrap_or_else(|message| fail(Error::InvalidFile { path: path.to_string(), message }))
+}
+
+fn print_solution(cli: &CliArgs, solution: &Solution, options: &SearchOptions) {
+    if cli.output != OutputFormat::Human {
+        print!("{}", render::render_with(solution, cli.output, &cli.numbers));
+        return;
+    }
+
+    print!("{}", render::human_summary(solution, &cli.numbers));
+    if !options.belt_tiers.is_empty() {
+        for &output in &solution.outputs {
+            if let Some(tier) = options.belt_for(output) {
+                let capacity = options.format_rate(tier.capacity);
+                println!("{} fits on: {} ({})", cli.numbers.value(output), tier.name, cli.numbers.text(&capacity));
             }
         }
     }
+    print!("{}", render::human_steps(solution, &cli.numbers));
+    print!("{}", render::efficiency_summary(solution, &cli.numbers));
+    if let Some(rate) = cli.base_rate {
+        for &value in &solution.remainder {
+            let (machines, clock) = clock_to_consume(value, rate);
+            println!("Consume {}: {} machine(s) at {:.2}% clock", cli.numbers.value(value), machines, clock);
+        }
+    }
+}
 
-    None // No solution found
+fn print_usage(program: &str) {
+    eprintln!("Usage: {} [options] <inputs> <target> <canBeOffBy>", program);
+    eprintln!("       {} [options] --base-rate R [--machines N] [--clock P%] <inputs> <canBeOffBy>", program);
+    eprintln!("       {} [options] <inputs> \"recipe:<name> x <machines>\" <canBeOffBy>", program);
+    eprintln!("       {} [options] --demand TOTAL --stream-range LOW..HIGH <inputs> <canBeOffBy>", program);
+    eprintln!("       {} [options] balance <input> <outputCount> [canBeOffBy]", program);
+    eprintln!("       {} [options] inverse <outputs> <feedRates> [canBeOffBy]", program);
+    eprintln!("       {} [options] check <inputs> <target> <canBeOffBy>   (or --problem/--preset; lint without solving)", program);
+    eprintln!("       {} [options] merge <inputs>   (into as few streams as --max-flow allows)", program);
+    eprintln!("       {} [options] pipeline <pipeline.json>   (stages each fed by the one before)", program);
+    eprintln!("       {} [options] sweep <inputs> <target|start..end:step> <canBeOffBy|start..end:step>", program);
+    eprintln!("       {} [options] tui <inputs> <target> <canBeOffBy>", program);
+    eprintln!("       {} [options] sensitivity <inputs> <target> <canBeOffBy> <delta[:step]>", program);
+    eprintln!("       {} [options] solve --stdin < problem.json", program);
+    eprintln!("       {} [options] solve --watch problem.json", program);
+    eprintln!("       {} [options] solve --problem problem.toml", program);
+    eprintln!("       {} convert <solution.json> <format>", program);
+    eprintln!("       {} diff <old.json> <new.json>", program);
+    eprintln!("       {} [options] compare <inputs> <target> <canBeOffBy>   (time every strategy on one problem)", program);
+    eprintln!("       {} [options] repair <solution.json> <newInputs> [canBeOffBy]   (fix a plan for drifted inputs)", program);
+    eprintln!("       {} replay <trace.bin>   (re-run a search saved with --record and compare)", program);
+    eprintln!("       {} [options] bench [runs] [--compare] [--history FILE]", program);
+    eprintln!("       {} [options] serve [address]   (POST /solve with a JSON problem; GET /live WebSocket; GET /metrics)", program);
+    eprintln!("       {} [options] coordinate <address> <workers> <inputs> <target> <canBeOffBy>   (or --problem/--preset)", program);
+    eprintln!("       {} [options] worker <address>   (search the part of a problem a coordinator hands out)", program);
+    eprintln!("       {} packs   (pack files are read from ${} and ~/.config/calculate_outputs/packs)", program, packs::PACKS_VARIABLE);
+    eprintln!("       {} presets   (built-in example problems, solved with --preset NAME)", program);
+    eprintln!("       {} [options] [--share op,...] multi <name=inputs:target:canBeOffBy>...", program);
+    eprintln!("Example: {} \"10.0,10.0,10.0\" 12.0 1.0   (inputs may also be written \"3x10.0\"; any rate may be arithmetic like 780/2)", program);
+    eprintln!();
+    eprintln!("Options:");
+    let options = [
+        ("--profile NAME", "Restrict operations and capacities to a game preset: default, factorio, pipes or a pack"),
+        ("--max-flow C[,C...]", "Capacity (or tier list) no value may exceed"),
+        ("--input-capacity N=C", "Input N (from 1) arrives on a stream carrying at most C"),
+        ("--forbid R[,LOW..HIGH...]", "No operation may produce these rates or rates in these ranges (repeatable)"),
+        ("--max-value V", "Reject any intermediate value above V, on top of the profile's tiers"),
+        ("--base-rate R", "Machine consumption rate; suggests clocks to consume the remainder"),
+        ("--machines N", "Derive the target from machines: need N outputs at the machine rate"),
+        ("--clock P%", "Clock speed of those machines (default 100%)"),
+        ("--demand TOTAL", "Deliver TOTAL across as many streams as needed (with --stream-range)"),
+        ("--stream-range LOW..HIGH", "Bounds on each stream meeting --demand"),
+        ("--recipes FILE", "Recipe JSON extending the bundled database for recipe: targets"),
+        ("--share op[,op...]", "Operation types (split2, split3, combine2, combine3) whose devices multi flows share"),
+        ("--limit OP=N", "Use operation OP at most N times (repeatable)"),
+        ("--cost OP=C", "Cost of one OP device towards --max-cost (default 1)"),
+        ("--max-cost C", "Limit the total cost of all devices in the plan"),
+        ("--footprint OP=AREA", "Floor area of one OP device (default 1; factorio splitters 2)"),
+        ("--use-all-inputs", "Reject plans that leave an input untouched in the remainder"),
+        ("--optional-input N=P", "Input N (from 1) may go unused, at a cost of P extra steps"),
+        ("--separate-inputs", "Never merge flows that came from different inputs"),
+        ("--max-chain N", "Pass any one flow through at most N operations"),
+        ("--max-outputs N", "Allow at most N final streams, matched outputs plus remainder"),
+        ("--max-footprint AREA", "Limit the total floor area of all devices in the plan"),
+        ("--dedup-epsilon E", "Treat states whose values agree to within E as duplicates"),
+        ("--top-k K", "Print the K best distinct plans instead of one"),
+        ("--enumerate-optimal N", "Print every distinct shortest plan, up to N of them"),
+        ("--objective LIST", "Optimize machines, waste, deviation, outputs in order, later ones breaking ties"),
+        ("--rank LIST", "Order top-k plans by steps, deviation, remainder, outputs"),
+        ("--seed N", "Randomize the order of equally ranked nodes, reproducibly"),
+        ("--timeout SECONDS", "Stop searching after this long, keeping any plans already found"),
+        ("--time-budget SECONDS", "Pick depth, beam width and heuristic weight during the run to finish within this long"),
+        ("--quota SECONDS", "serve: cut each request's search off after this long, answering 503"),
+        ("--rate-limit N", "serve: answer 429 to a client's requests past N a minute"),
+        ("--concurrent N", "serve: handle up to N requests at once on threads that take turns; 503 past that"),
+        ("--decimals N", "Print rates with exactly N decimal places"),
+        ("--thousands", "Group digits of printed rates with commas"),
+        ("--output FORMAT", "human (default), json, dot, mermaid, csv, html, tikz, cytoscape"),
+        ("--stdin", "Read a JSON problem {inputs, target, tolerance} from stdin; write JSON"),
+        ("--progress jsonl", "Write progress events (rate, frontier, best deviation) to stderr"),
+        ("--log-format text|json", "Write diagnostics on stderr as text or as one JSON object per line"),
+        ("--dump-search-tree FILE", "Write the explored states (g/h/f annotated) as DOT"),
+        ("--record FILE", "Save every expansion, prune and goal of the search as a trace for replay"),
+        ("--hint FILE", "Start every plan with the JSON steps in FILE (a partial or saved plan) and search only for the rest"),
+        ("--script FILE", "Load output/accept/prune rules, e.g. \"prune = streams > 6\", replacing the target check"),
+        ("--memo FILE", "Remember plans for every sub-state across runs, and start from them when a problem recurs"),
+        ("--cache FILE", "Reuse plans from the last run when only one input changed; save this run's"),
+        ("--profile-internal", "Print how long generation, hashing, the frontier and goal checks took"),
+        ("--memory-limit MB", "Past this much search state, go on depth-first in bounded memory instead of growing"),
+        ("--history FILE", "Where bench records its runs (default bench-history.jsonl)"),
+        ("--compare", "Make bench fail on cases significantly slower than the previous run"),
+        ("--watch FILE", "Re-solve a JSON/TOML problem file on every change and show what changed"),
+        ("--problem FILE", "Solve a JSON/TOML problem file (inputs, target, tolerance, constraints)"),
+        ("--preset NAME", "Solve a built-in example problem; `presets` lists them"),
+        ("--set NAME=VALUE", "Fill {NAME} in a problem or pipeline template, e.g. inputs = [\"{miners}x480\"]"),
+        ("--best-effort-depth K", "Return the closest value reachable in K operations, even out of tolerance"),
+        ("--max-depth N", "Maximum number of operations in a plan"),
+        ("--tolerance +A/-B", "Outputs may be up to A above and B below the target, in place of canBeOffBy"),
+        ("--tolerance P% [min F]", "Outputs may be off by P percent of the target, but never less than F"),
+        ("--tolerance-mode MODE", "both, or at-least / at-most to keep outputs on one side of the target"),
+        ("--strategy NAME", "Frontier order: greedy (default), shortest, weighted:COST or beam:WIDTH"),
+        ("--portfolio LIST", "Race comma-separated strategies (or \"default\") on threads; see --portfolio-mode"),
+        ("--portfolio-mode MODE", "first: keep the first plan found (default); best: wait for all, keep the best"),
+        ("--style any|manifold|balanced", "Prefer sequential taps or a balanced tree among equal plans"),
+        ("--prefer splits|combines", "Among equally promising states, expand those reached by this kind of operation first"),
+    ];
+    for (flag, description) in options {
+        eprintln!("  {:<34} {}", flag, description);
+    }
+    eprintln!();
+    eprintln!("Environment (overridden by the flags above):");
+    for (variable, flag) in ENV_OPTIONS {
+        eprintln!("  {:<34} {}", variable, flag);
+    }
 }
 
-// Helper function to separate final values close to target and remainder
-fn find_final_and_remainder(values: &[i64], target: i64, margin: i64) -> Option<(Vec<i64>, Vec<i64>)> {
-    let mut final_values = Vec::new();
-    let mut remainder = Vec::new();
+// Bundled recipes, extended by the user's --recipes file if given
+fn load_recipes(cli: &CliArgs) -> RecipeDatabase {
+    let mut database = RecipeDatabase::bundled();
+    if let Some(path) = &cli.recipes {
+        if let Err(e) = database.load_file(path) {
+            log("error", "setup", &e.to_string(), &[]);
+            std::process::exit(1);
+        }
+    }
+    database
+}
 
-    for &value in values {
-        if (value - target).abs() <= margin {
-            final_values.push(value);
-        } else {
-            remainder.push(value);
+// Consumption of one machine running at `clock` percent
+fn machine_rate(base_rate: f64, clock: f64) -> f64 {
+    base_rate * clock / 100.0
+}
+
+// Fewest machines (each clocked at most 100%) and the shared clock percentage that exactly consume `value`
+fn clock_to_consume(value: f64, base_rate: f64) -> (u64, f64) {
+    let machines = (value / base_rate).ceil().max(1.0);
+    (machines as u64, value / (machines * base_rate) * 100.0)
+}
+
+// Breakdown for --profile-internal, on stderr so it never mixes into machine-readable output
+fn print_timings(timings: &Timings) {
+    let parts = [
+        ("Successor generation", timings.generation),
+        ("Heuristics", timings.heuristics),
+        ("Hashing and visited set", timings.hashing),
+        ("Frontier push/pop", timings.heap),
+        ("Goal checks", timings.goal_checks),
+    ];
+    let accounted: Duration = parts.iter().map(|&(_, time)| time).sum();
+    let total = timings.total.as_secs_f64().max(f64::MIN_POSITIVE);
+    if LOG_JSON.load(Ordering::Relaxed) {
+        let keys = ["generation", "heuristics", "hashing", "heap", "goal_checks"];
+        let mut counters: Vec<(&str, f64)> = keys.iter().zip(&parts).map(|(&key, &(_, time))| (key, time.as_secs_f64())).collect();
+        counters.push(("total", timings.total.as_secs_f64()));
+        log("info", "profile", "search time breakdown", &counters);
+        return;
+    }
+    eprintln!("Search time breakdown:");
+    for (name, time) in parts.into_iter().chain([("Other", timings.total.saturating_sub(accounted))]) {
+        eprintln!("  {:<24} {:>12.3?} {:>5.1}%", name, time, 100.0 * time.as_secs_f64() / total);
+    }
+    eprintln!("  {:<24} {:>12.3?}", "Total", timings.total);
+}
+
+// Explain why the search found nothing: the nearest miss, what limited the search, and what to try
+fn explain_failure(stats: &SearchStats, can_be_off_by: f64, options: &SearchOptions) -> Vec<String> {
+    let mut lines = vec![format!("Explored {} state(s).", stats.expanded)];
+
+    if let Some((value, deviation, depth)) = stats.closest {
+        lines.push(format!("Closest value reached: {} (off by {}) at depth {}", value, deviation, depth));
+        if deviation > can_be_off_by && options.output_count.is_none() && options.min_outputs <= 1 {
+            lines.push(format!("Suggestion: widen canBeOffBy to {} to accept it", (deviation * SCALE).ceil() / SCALE));
         }
     }
+    if stats.cancelled {
+        lines.push("Bound: Ctrl-C stopped the search early".to_string());
+    }
+    if stats.timed_out {
+        lines.push("Bound: the timeout stopped the search early; allow more time with --timeout".to_string());
+    }
+    if stats.depth_limited > 0 {
+        lines.push(format!(
+            "Bound: {} state(s) hit the depth limit of {}; try --max-depth {}",
+            stats.depth_limited,
+            options.max_depth,
+            options.max_depth + 2
+        ));
+    }
+    if stats.capacity_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} merge(s) exceeded the carrying capacity; a larger --max-flow or --max-value would allow them",
+            stats.capacity_rejected
+        ));
+    }
+    if stats.forbidden_rejected > 0 {
+        let bands: Vec<String> =
+            options.forbidden.iter().map(|&(low, high)| if low == high { low.to_string() } else { format!("{}..{}", low, high) }).collect();
+        lines.push(format!(
+            "Bound: {} operation(s) would have produced a rate forbidden by --forbid {}; allow more rates",
+            stats.forbidden_rejected,
+            bands.join(",")
+        ));
+    }
+    if stats.beam_pruned > 0 {
+        lines.push(format!(
+            "Bound: the beam dropped {} state(s) past its width; a wider beam:WIDTH or another --strategy may find more",
+            stats.beam_pruned
+        ));
+    }
+    if stats.limit_rejected > 0 {
+        let limits: Vec<String> = options.operation_limits.iter().map(|(operation, count)| format!("{}={}", operation.name(), count)).collect();
+        lines.push(format!(
+            "Bound: operations were used up {} time(s) under --limit {}; raise a limit to allow more",
+            stats.limit_rejected,
+            limits.join(" --limit ")
+        ));
+    }
+    if stats.footprint_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} operation(s) would have exceeded --max-footprint {}; allow more floor space",
+            stats.footprint_rejected,
+            options.max_footprint.unwrap_or(0.0)
+        ));
+    }
+    if stats.cost_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} operation(s) would have exceeded --max-cost {}; raise the budget or lower --cost",
+            stats.cost_rejected,
+            options.max_cost.unwrap_or(0.0)
+        ));
+    }
+    if stats.mixing_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} merge(s) would have mixed different inputs; drop --separate-inputs to allow them",
+            stats.mixing_rejected
+        ));
+    }
+    if stats.custom_pruned > 0 {
+        lines.push(format!("Bound: the script's prune rule dropped {} state(s) before expanding them", stats.custom_pruned));
+    }
+    if stats.chain_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} operation(s) would have passed a flow through more than --max-chain {} operations",
+            stats.chain_rejected,
+            options.max_chain.unwrap_or(0)
+        ));
+    }
+    if stats.unused_input_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} plan(s) left an input unused; drop --use-all-inputs to let some pass straight to the remainder",
+            stats.unused_input_rejected
+        ));
+    }
+    if stats.outputs_rejected > 0 {
+        lines.push(format!(
+            "Bound: {} plan(s) ended in more than --max-outputs {} stream(s); raise it to leave more remainder",
+            stats.outputs_rejected,
+            options.max_outputs.unwrap_or(0)
+        ));
+    }
+    if stats.unverified > 0 {
+        lines.push(format!(
+            "Bound: {} plan(s) found through merged near-duplicate states did not replay exactly; try a smaller --dedup-epsilon",
+            stats.unverified
+        ));
+    }
+    if can_be_off_by < 1.0 / SCALE {
+        lines.push(format!(
+            "Bound: values are tracked to {} precision, so a tolerance below that only matches exact results",
+            1.0 / SCALE
+        ));
+    }
+    lines
+}
 
-    if !final_values.is_empty() {
-        Some((final_values, remainder))
-    } else {
-        None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> CliArgs {
+        parse_args(args.iter().map(|arg| arg.to_string()).collect()).expect("valid arguments")
+    }
+
+    #[test]
+    fn plain_tolerance_follows_tolerance_mode() {
+        for args in [
+            ["calculate_outputs", "--tolerance", "2", "--tolerance-mode", "at-least"],
+            ["calculate_outputs", "--tolerance-mode", "at-least", "--tolerance", "2"],
+        ] {
+            let cli = cli(&args);
+            let band = cli.tolerance.expect("--tolerance was given").band(12.0, cli.tolerance_mode);
+            assert_eq!(band, Tolerance { below: 0.0, above: 2.0 });
+        }
+        let cli = cli(&["calculate_outputs", "--tolerance", "2"]);
+        assert_eq!(cli.tolerance.unwrap().band(12.0, cli.tolerance_mode), Tolerance { below: 2.0, above: 2.0 });
+    }
+
+    #[test]
+    fn explicit_bounds_ignore_tolerance_mode() {
+        let band = parse_tolerance("+0.5/-2").expect("valid band").band(12.0, ToleranceMode::AtLeast);
+        assert_eq!(band, Tolerance { below: 2.0, above: 0.5 });
+    }
+
+    #[test]
+    fn percent_tolerance_scales_with_the_target() {
+        let band = |text: &str, target: f64| parse_tolerance(text).expect("valid tolerance").band(target, ToleranceMode::Both);
+        assert_eq!(band("5%", 120.0), Tolerance { below: 6.0, above: 6.0 });
+        assert_eq!(band(" 10/2 % ", 120.0), Tolerance { below: 6.0, above: 6.0 });
+        // The floor wins once the share of the target drops below it
+        assert_eq!(band("1% min 0.1", 200.0), Tolerance { below: 2.0, above: 2.0 });
+        assert_eq!(band("1% min 0.1", 5.0), Tolerance { below: 0.1, above: 0.1 });
+        let at_least = parse_tolerance("5%").unwrap().band(120.0, ToleranceMode::AtLeast);
+        assert_eq!(at_least, Tolerance { below: 0.0, above: 6.0 });
+        for text in ["%", "-5%", "five%", "5% min", "5% min -1", "5%%", "0.5 min 1"] {
+            assert!(parse_tolerance(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn ranges_take_arithmetic() {
+        assert_eq!(cli(&["calculate_outputs", "--stream-range", "120/2..45*2"]).stream_range, Some((60.0, 90.0)));
+        for range in ["60", "90..60", "60..abc"] {
+            assert!(parse_args(["calculate_outputs", "--stream-range", range].map(String::from).to_vec()).is_err(), "{}", range);
+        }
+        assert_eq!(parse_range("10..20:10/2"), Some(vec![10.0, 15.0, 20.0]));
+        assert_eq!(parse_range("20..10:5"), None);
+        assert_eq!(parse_range("10..20:0"), None);
     }
 }
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::render;
+use crate::{SearchOptions, Solution, SCALE};
+
+// Most plans remembered for one sub-state and target; longer ones give way to shorter
+const PLANS_PER_STATE: usize = 4;
+// Most sub-states a memo file holds, so it cannot grow without end over many runs
+pub const MAX_STATES: usize = 100_000;
+
+// A sub-state as the memo knows it: its rates in thousandths, sorted, so the order streams were
+// listed or produced in does not matter
+fn canonical(rates: &[f64]) -> Vec<i64> {
+    let mut key: Vec<i64> = rates.iter().map(|&rate| (rate * SCALE).round() as i64).collect();
+    key.sort_unstable();
+    key
+}
+
+// Plans learned across runs, kept for every sub-state they pass through: a plan splitting 60 for
+// 12s also says how to get 12s from the [30, 30] its first split leaves. Unlike `--cache`, which holds
+// one problem's plans, the memo accumulates, so related problems profit from everything solved before
+#[derive(Clone, Debug, Default)]
+pub struct Memo {
+    states: BTreeMap<Vec<i64>, Vec<Solution>>,
+}
+
+impl Memo {
+    pub fn new() -> Memo {
+        Memo::default()
+    }
+
+    // Sub-states remembered
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    // Remember `plan` from its inputs and from every state between its steps. Returns how many
+    // sub-states gained a plan
+    pub fn learn(&mut self, plan: &Solution) -> usize {
+        let mut learned = 0;
+        let mut rates = plan.inputs.clone();
+        for start in 0..plan.steps.len() {
+            // The rest of the plan leaves the same outputs and remainder as the whole of it
+            let suffix = Solution { inputs: rates.clone(), steps: plan.steps[start..].to_vec(), penalty: 0.0, ..plan.clone() };
+            if self.remember(suffix) {
+                learned += 1;
+            }
+            let step = &plan.steps[start];
+            for &consumed in &step.consumed {
+                if let Some(index) = rates.iter().position(|&rate| (rate - consumed).abs() <= 1.0 / SCALE) {
+                    rates.remove(index);
+                }
+            }
+            rates.extend_from_slice(&step.produced);
+        }
+        learned
+    }
+
+    fn remember(&mut self, plan: Solution) -> bool {
+        let key = canonical(&plan.inputs);
+        if !self.states.contains_key(&key) && self.states.len() >= MAX_STATES {
+            return false;
+        }
+        let plans = self.states.entry(key).or_default();
+        let duplicate = plans.iter().any(|known| known.target == plan.target && known.steps == plan.steps);
+        if duplicate {
+            return false;
+        }
+        plans.push(plan);
+        plans.sort_by_key(|known| known.steps.len());
+        plans.truncate(PLANS_PER_STATE);
+        true
+    }
+
+    // Remembered plans that solve these inputs under `options`, shortest first. Plans are replayed on the
+    // inputs as given and checked against the operations, capacity and output counts; searches with
+    // constraints the memo cannot check (limits, costs, footprints, custom goals and the like) get none
+    pub fn recall(&self, inputs: &[f64], target: f64, can_be_off_by: f64, options: &SearchOptions) -> Vec<Solution> {
+        let uncheckable = !options.operation_limits.is_empty()
+            || options.max_footprint.is_some()
+            || options.max_cost.is_some()
+            || options.max_chain.is_some()
+            || options.max_outputs.is_some()
+            || !options.forbidden.is_empty()
+            || options.separate_inputs
+            || options.use_all_inputs
+            || !options.optional_inputs.is_empty()
+            || !options.input_capacities.is_empty()
+            || options.goal.is_some()
+            || options.prune.is_some();
+        if uncheckable {
+            return Vec::new();
+        }
+        let capacity = options.max_capacity();
+        let admissible = |plan: &Solution| {
+            plan.steps.iter().all(|step| {
+                options.operations.contains(&step.operation)
+                    && capacity.is_none_or(|capacity| step.produced.iter().chain(&step.consumed).all(|&rate| rate <= capacity + 1.0 / SCALE))
+            }) && plan.outputs.len() >= options.min_outputs.max(1)
+                && options.output_count.is_none_or(|count| plan.outputs.len() == count && plan.remainder.is_empty())
+                && plan.steps.len() <= options.max_depth
+        };
+        self.states
+            .get(&canonical(inputs))
+            .into_iter()
+            .flatten()
+            .filter(|plan| (plan.target - target).abs() <= 1.0 / SCALE && admissible(plan))
+            .filter_map(|plan| plan.rebased(inputs.to_vec(), can_be_off_by, 1.0 / SCALE))
+            .map(|plan| Solution { target, ..plan })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Json {
+        let plans = self.states.values().flatten().map(render::to_json).collect();
+        Json::Object(BTreeMap::from([("plans".to_string(), Json::Array(plans))]))
+    }
+
+    pub fn from_json(document: &Json) -> Result<Memo, String> {
+        let mut memo = Memo::new();
+        for plan in document.get("plans").and_then(Json::as_array).ok_or("missing array \"plans\"")? {
+            memo.remember(render::from_json(plan)?);
+        }
+        Ok(memo)
+    }
+
+    pub fn load(path: &str) -> Result<Memo, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        json::parse(&text)
+            .and_then(|document| Memo::from_json(&document))
+            .map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.to_json().to_string()).map_err(|source| Error::Io { path: path.to_string(), source })
+    }
+}
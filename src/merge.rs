@@ -0,0 +1,121 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::value::{Fixed, Value};
+use crate::{Operation, SearchOptions, Solution, Step};
+
+// Most inputs whose grouping onto belts is searched exhaustively; larger lists are packed first-fit
+pub const MAX_EXACT_INPUTS: usize = 16;
+
+// The dual of balancing: consolidate inputs into as few streams as the belt capacity allows, with as
+// few combine operations as possible. Inputs are grouped so every group fits one belt, then each group
+// is merged three at a time where combine3 is allowed. None if an input alone overflows a belt or the
+// profile allows no combine at all
+pub fn merge_streams(inputs: &[f64], options: &SearchOptions) -> Option<Solution> {
+    let capacity = options.max_capacity().unwrap_or(f64::INFINITY);
+    let allowed = |operation| options.operations.contains(&operation);
+    let (two, three) = (allowed(Operation::CombineTwo), allowed(Operation::CombineThree));
+    if inputs.is_empty() || !(two || three) || inputs.iter().any(|&rate| rate > capacity) {
+        return None;
+    }
+
+    let mut steps = Vec::new();
+    let mut outputs = Vec::new();
+    for mut group in groups(inputs, capacity) {
+        // Largest first, so the smallest streams are merged first
+        group.sort_by(|a, b| b.total_cmp(a));
+        while group.len() > 1 {
+            let (operation, parts) = match (two, three) {
+                (_, true) if group.len() >= 3 => (Operation::CombineThree, 3),
+                (true, _) => (Operation::CombineTwo, 2),
+                // Two streams left and only combine3: they stay apart
+                _ => break,
+            };
+            let consumed = group.split_off(group.len() - parts);
+            // Summed in fixed point, as the search would
+            let combined = Fixed(consumed.iter().map(|&rate| Fixed::from_f64(rate).0).sum()).to_f64();
+            let terms: Vec<String> = consumed.iter().map(|rate| format!("{}", rate)).collect();
+            let description = format!("{} -> {}", terms.join(" + "), combined);
+            steps.push(options.label_step(Step { operation, consumed, produced: vec![combined], description }));
+            let position = group.partition_point(|&rate| rate > combined);
+            group.insert(position, combined);
+        }
+        outputs.extend(group);
+    }
+    // The mean stream stands in for a target, as for an inverse search
+    let target = outputs.iter().sum::<f64>() / outputs.len() as f64;
+    Some(Solution { inputs: inputs.to_vec(), target, outputs, remainder: Vec::new(), steps, penalty: 0.0 })
+}
+
+// Inputs grouped onto as few belts as possible: exactly for short lists, first-fit decreasing otherwise
+fn groups(inputs: &[f64], capacity: f64) -> Vec<Vec<f64>> {
+    let mut items = inputs.to_vec();
+    items.sort_by(|a, b| b.total_cmp(a));
+    if items.len() <= MAX_EXACT_INPUTS {
+        let total: f64 = items.iter().sum();
+        let whole = (total / capacity) as usize; // Zero for an unlimited capacity
+        let fewest = if (whole as f64) * capacity < total { whole + 1 } else { whole.max(1) };
+        for count in fewest..items.len() {
+            let mut groups = vec![Vec::new(); count];
+            if pack(&items, &mut vec![0.0; count], &mut groups, capacity) {
+                return groups;
+            }
+        }
+        // One belt per input always fits, as no input alone overflows
+        return items.into_iter().map(|rate| vec![rate]).collect();
+    }
+    let mut groups: Vec<(f64, Vec<f64>)> = Vec::new();
+    for rate in items {
+        match groups.iter_mut().find(|(load, _)| load + rate <= capacity) {
+            Some((load, group)) => {
+                *load += rate;
+                group.push(rate);
+            }
+            None => groups.push((rate, vec![rate])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+// Place every item, largest first, on one of the belts without overflowing any. A belt carrying the
+// same load as an earlier one is skipped, as trying it again would only repeat the same placements
+fn pack(items: &[f64], loads: &mut [f64], groups: &mut [Vec<f64>], capacity: f64) -> bool {
+    let Some((&item, rest)) = items.split_first() else { return true };
+    for belt in 0..loads.len() {
+        if loads[belt] + item > capacity || loads[..belt].contains(&loads[belt]) {
+            continue;
+        }
+        let load = loads[belt];
+        loads[belt] += item;
+        groups[belt].push(item);
+        if pack(rest, loads, groups, capacity) {
+            return true;
+        }
+        loads[belt] = load;
+        groups[belt].pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_into_one_stream_when_it_fits() {
+        let plan = merge_streams(&[10.0, 20.0, 30.0], &SearchOptions::default()).expect("combines are allowed");
+        assert_eq!(plan.outputs, vec![60.0]);
+        assert_eq!(plan.steps.len(), 1);
+    }
+
+    #[test]
+    fn keeps_every_stream_within_capacity() {
+        let options = SearchOptions { max_value: Some(50.0), ..SearchOptions::default() };
+        let plan = merge_streams(&[10.0, 20.0, 30.0, 40.0], &options).expect("every input fits a belt");
+        assert!(plan.outputs.iter().all(|&output| output <= 50.0));
+        assert_eq!(plan.outputs.iter().sum::<f64>(), 100.0);
+        assert!(merge_streams(&[60.0], &options).is_none());
+    }
+}
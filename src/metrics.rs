@@ -0,0 +1,91 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::SearchStats;
+
+// Upper bounds in seconds of the solve latency histogram's buckets; slower solves land only in +Inf
+const LATENCY_BUCKETS: [f64; 7] = [0.001, 0.005, 0.025, 0.1, 0.5, 2.5, 10.0];
+
+// Counters a long-running solver keeps for monitoring, shared between the threads serving requests
+// and rendered in the Prometheus text format for `/metrics`
+#[derive(Debug, Default)]
+pub struct Metrics {
+    searches: AtomicU64, // Solves that ran a search, rather than hitting the cache
+    failures: AtomicU64, // Searches that found no plan
+    timeouts: AtomicU64,
+    expanded: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    rate_limited: AtomicU64, // Requests refused because their client was over its rate
+    rejected: AtomicU64,     // Connections refused because the server was at its concurrency limit
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()], // Cumulative, as Prometheus expects
+    latency_micros: AtomicU64,                           // Sum of all solve latencies
+}
+
+impl Metrics {
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    // One search run to answer a request; answers from a cache are recorded with `record_cache` alone
+    pub fn record_solve(&self, elapsed: Duration, stats: &SearchStats, found: bool) {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        if !found {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        if stats.timed_out {
+            self.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+        self.expanded.fetch_add(stats.expanded, Ordering::Relaxed);
+        let seconds = elapsed.as_secs_f64();
+        for (bucket, &bound) in self.latency_buckets.iter().zip(&LATENCY_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_cache(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // The metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut text = String::new();
+        let counters = [
+            ("calculate_outputs_solves_total", "Solve requests handled", load(&self.searches) + load(&self.cache_hits)),
+            ("calculate_outputs_solve_failures_total", "Searches that found no plan", load(&self.failures)),
+            ("calculate_outputs_timeouts_total", "Solves stopped by their timeout", load(&self.timeouts)),
+            ("calculate_outputs_nodes_expanded_total", "Search nodes expanded across all solves", load(&self.expanded)),
+            ("calculate_outputs_cache_hits_total", "Solves answered from the result cache", load(&self.cache_hits)),
+            ("calculate_outputs_cache_misses_total", "Solves the result cache could not answer", load(&self.cache_misses)),
+            ("calculate_outputs_rate_limited_total", "Requests refused for exceeding their client's rate", load(&self.rate_limited)),
+            ("calculate_outputs_busy_rejections_total", "Connections refused while at the concurrency limit", load(&self.rejected)),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(text, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, value);
+        }
+
+        let name = "calculate_outputs_solve_duration_seconds";
+        let _ = writeln!(text, "# HELP {} Time taken by searches, excluding cache hits\n# TYPE {} histogram", name, name);
+        for (bucket, bound) in self.latency_buckets.iter().zip(LATENCY_BUCKETS) {
+            let _ = writeln!(text, "{}_bucket{{le=\"{}\"}} {}", name, bound, load(bucket));
+        }
+        let _ = writeln!(text, "{}_bucket{{le=\"+Inf\"}} {}", name, load(&self.searches));
+        let _ = writeln!(text, "{}_sum {}", name, load(&self.latency_micros) as f64 / 1e6);
+        let _ = writeln!(text, "{}_count {}", name, load(&self.searches));
+        text
+    }
+}
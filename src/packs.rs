@@ -0,0 +1,149 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::problem::{operation_numbers, optional_count, optional_number};
+use crate::{BeltTier, Medium, Operation, Profile, SearchOptions};
+
+// Directories searched for pack files, separated like PATH, ahead of ~/.config/calculate_outputs/packs
+pub const PACKS_VARIABLE: &str = "CALC_OUTPUTS_PACKS";
+
+// A named machine set for one game or mod: which operations exist, what belts or pipes carry and what
+// devices cost. Packs are plain JSON files, so a modding community can publish one without forking:
+//
+//     {"name": "satisfactory", "description": "Splitters and mergers, conveyor Mk.1-5",
+//      "operations": ["split2", "split3", "combine2", "combine3"], "medium": "items", "units": "items/min",
+//      "belt_tiers": {"Mk.1": 60, "Mk.2": 120, "Mk.3": 270, "Mk.4": 480, "Mk.5": 780}}
+//
+// `footprints`, `costs` and `limits` take an object keyed by operation; `max_depth`, `max_value`,
+// `max_footprint` and `max_cost` a number. Crates embedding the solver register packs in code instead
+#[derive(Clone, Debug)]
+pub struct Pack {
+    pub name: String,
+    pub description: String,
+    pub options: SearchOptions,
+}
+
+impl Pack {
+    pub fn from_json(document: &Json) -> Result<Pack, String> {
+        let name = document.get("name").and_then(Json::as_str).ok_or("missing string \"name\"")?;
+        let mut options = SearchOptions::default();
+        if let Some(value) = document.get("operations") {
+            options.operations = value
+                .as_array()
+                .ok_or("\"operations\" must be an array")?
+                .iter()
+                .map(|name| {
+                    let name = name.as_str().ok_or("non-string value in \"operations\"")?;
+                    Operation::from_name(name).ok_or_else(|| format!("unknown operation {}", name))
+                })
+                .collect::<Result<Vec<Operation>, String>>()?;
+        }
+        if let Some(value) = document.get("belt_tiers") {
+            for (tier, capacity) in value.as_object().ok_or("\"belt_tiers\" must be an object of capacities by name")? {
+                let capacity = capacity.as_f64().ok_or_else(|| format!("capacity of {} must be a number", tier))?;
+                options.belt_tiers.push(BeltTier { name: tier.clone(), capacity });
+            }
+            options.belt_tiers.sort_by(|a, b| a.capacity.total_cmp(&b.capacity));
+        }
+        options.medium = match document.get("medium").map(|value| value.as_str()) {
+            None | Some(Some("items")) => Medium::Items,
+            Some(Some("fluid")) => Medium::Fluid,
+            _ => return Err("\"medium\" must be \"items\" or \"fluid\"".to_string()),
+        };
+        if let Some(value) = document.get("units") {
+            options.units = Some(value.as_str().ok_or("\"units\" must be a string")?.to_string());
+        }
+        options.footprints = operation_numbers(document, "footprints")?;
+        options.costs = operation_numbers(document, "costs")?;
+        for (operation, count) in operation_numbers(document, "limits")? {
+            if count < 0.0 || count.fract() != 0.0 {
+                return Err(format!("limit for {} must be a whole number", operation.name()));
+            }
+            options.operation_limits.push((operation, count as usize));
+        }
+        if let Some(depth) = optional_count(document, "max_depth")? {
+            options.max_depth = depth;
+        }
+        options.max_value = optional_number(document, "max_value")?;
+        options.max_footprint = optional_number(document, "max_footprint")?;
+        options.max_cost = optional_number(document, "max_cost")?;
+        Ok(Pack {
+            name: name.to_string(),
+            description: document.get("description").and_then(Json::as_str).unwrap_or("").to_string(),
+            options,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Pack, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        json::parse(&text)
+            .and_then(|document| Pack::from_json(&document))
+            .map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+}
+
+// Every pack available by name: the built-in profiles, then whatever was registered or discovered.
+// A later pack with the same name replaces an earlier one, so a local file can patch a published pack
+#[derive(Clone, Debug)]
+pub struct PackRegistry {
+    packs: Vec<Pack>,
+}
+
+impl PackRegistry {
+    pub fn builtin() -> PackRegistry {
+        let profiles = [
+            ("default", Profile::Default, "Every split and merge, no capacity limit"),
+            ("factorio", Profile::Factorio, "Factorio splitters and belts"),
+            ("pipes", Profile::Pipes, "Satisfactory pipelines and junctions"),
+        ];
+        let packs = profiles
+            .iter()
+            .map(|&(name, profile, description)| Pack { name: name.to_string(), description: description.to_string(), options: profile.search_options() })
+            .collect();
+        PackRegistry { packs }
+    }
+
+    // Built-in profiles plus every pack file in the search directories. Files that fail to load are
+    // returned alongside rather than stopping the rest
+    pub fn discover() -> (PackRegistry, Vec<Error>) {
+        let mut registry = PackRegistry::builtin();
+        let mut errors = Vec::new();
+        for directory in pack_directories() {
+            let Ok(entries) = fs::read_dir(&directory) else { continue };
+            let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+            paths.retain(|path| path.extension().is_some_and(|extension| extension == "json"));
+            paths.sort();
+            for path in paths {
+                match Pack::load(&path.to_string_lossy()) {
+                    Ok(pack) => registry.register(pack),
+                    Err(error) => errors.push(error),
+                }
+            }
+        }
+        (registry, errors)
+    }
+
+    pub fn register(&mut self, pack: Pack) {
+        self.packs.retain(|existing| existing.name != pack.name);
+        self.packs.push(pack);
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Pack> {
+        self.packs.iter().find(|pack| pack.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Pack> {
+        self.packs.iter()
+    }
+}
+
+fn pack_directories() -> Vec<PathBuf> {
+    let mut directories: Vec<PathBuf> = env::var_os(PACKS_VARIABLE).map_or_else(Vec::new, |paths| env::split_paths(&paths).collect());
+    if let Some(home) = env::var_os("HOME") {
+        directories.push(PathBuf::from(home).join(".config/calculate_outputs/packs"));
+    }
+    directories
+}
@@ -0,0 +1,162 @@
+use crate::error::{check_range, Error};
+
+// Parse comma-separated input rates, naming the first one that is not a number. Each may be arithmetic
+// as for `evaluate`, and `4x120` stands for four inputs of 120
+pub fn parse_inputs(text: &str) -> Result<Vec<f64>, Error> {
+    let mut inputs = Vec::new();
+    for (index, part) in text.split(',').enumerate() {
+        let part = part.trim();
+        let invalid = || Error::InvalidInput { index, text: part.to_string() };
+        let (count, rate) = match part.split_once(['x', 'X']) {
+            Some((count, rate)) => (count.trim().parse().ok().filter(|&count: &usize| count > 0).ok_or_else(invalid)?, rate.trim()),
+            None => (1, part),
+        };
+        let rate = evaluate(rate).ok_or_else(invalid)?;
+        inputs.extend(std::iter::repeat_n(rate, count));
+    }
+    check_range(&inputs)?;
+    Ok(inputs)
+}
+
+// A rate or an inclusive range of rates, `86.667` or `80..90`, as (low, high) with low == high for a
+// single rate. Each bound may be arithmetic as for `evaluate`
+pub fn parse_band(text: &str) -> Option<(f64, f64)> {
+    let (low, high) = match text.split_once("..") {
+        Some((low, high)) => (evaluate(low)?, evaluate(high)?),
+        None => {
+            let rate = evaluate(text)?;
+            (rate, rate)
+        }
+    };
+    (low <= high).then_some((low, high))
+}
+
+// Evaluate a number written as arithmetic: `+ - * /`, parentheses and unary signs, so rates like `780/2`
+// or `120*1.5` need no calculator. None if the text is not such an expression or does not come to a
+// finite number
+pub fn evaluate(text: &str) -> Option<f64> {
+    let mut parser = Arithmetic { text: text.as_bytes(), pos: 0 };
+    let value = parser.sum()?;
+    parser.skip_spaces();
+    (parser.pos == parser.text.len() && value.is_finite()).then_some(value)
+}
+
+struct Arithmetic<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Arithmetic<'_> {
+    fn skip_spaces(&mut self) {
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // Consume `byte` if it comes next
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_spaces();
+        let found = self.text.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat(b'+') {
+                value += self.product()?;
+            } else if self.eat(b'-') {
+                value -= self.product()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat(b'*') {
+                value *= self.factor()?;
+            } else if self.eat(b'/') {
+                value /= self.factor()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        if self.eat(b'-') {
+            return self.factor().map(|value| -value);
+        }
+        if self.eat(b'+') {
+            return self.factor();
+        }
+        if self.eat(b'(') {
+            let value = self.sum()?;
+            return self.eat(b')').then_some(value);
+        }
+        // A decimal number, optionally with an exponent like 1.5e3
+        let start = self.pos;
+        let digits = |text: &[u8], mut pos: usize| {
+            while text.get(pos).is_some_and(|&byte| byte.is_ascii_digit() || byte == b'.') {
+                pos += 1;
+            }
+            pos
+        };
+        self.pos = digits(self.text, self.pos);
+        if matches!(self.text.get(self.pos), Some(b'e' | b'E')) {
+            let sign = usize::from(matches!(self.text.get(self.pos + 1), Some(b'+' | b'-')));
+            self.pos = digits(self.text, self.pos + 1 + sign);
+        }
+        std::str::from_utf8(&self.text[start..self.pos]).ok()?.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_with_precedence() {
+        assert_eq!(evaluate("780/2"), Some(390.0));
+        assert_eq!(evaluate("1 + 2 * 3"), Some(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(evaluate("12 - 4 - 2"), Some(6.0));
+        assert_eq!(evaluate("120 / 4 / 2"), Some(15.0));
+        assert_eq!(evaluate("-2 * -3"), Some(6.0));
+        assert_eq!(evaluate("-(1 + 2)"), Some(-3.0));
+        assert_eq!(evaluate(" 1.5e2 + 2E-1 "), Some(150.2));
+    }
+
+    #[test]
+    fn rejects_what_is_not_arithmetic() {
+        for text in ["", "abc", "1 +", "(1 + 2", "1 + 2)", "2 3", "1..2", "1/0", "1e999", "nan", "inf"] {
+            assert_eq!(evaluate(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_inputs_with_repeats() {
+        assert_eq!(parse_inputs("60, 120/2").unwrap(), [60.0, 60.0]);
+        assert_eq!(parse_inputs("4x120").unwrap(), [120.0; 4]);
+        assert_eq!(parse_inputs("2X45, 3 x 20+10").unwrap(), [45.0, 45.0, 30.0, 30.0, 30.0]);
+        assert!(matches!(parse_inputs("60,abc"), Err(Error::InvalidInput { index: 1, .. })));
+        assert!(matches!(parse_inputs("0x120"), Err(Error::InvalidInput { index: 0, .. })));
+        assert!(matches!(parse_inputs("-1x120"), Err(Error::InvalidInput { index: 0, .. })));
+        assert!(matches!(parse_inputs("1e300"), Err(Error::Overflow { .. })));
+    }
+
+    #[test]
+    fn parses_rates_and_ranges() {
+        assert_eq!(parse_band("86.667"), Some((86.667, 86.667)));
+        assert_eq!(parse_band("80..90"), Some((80.0, 90.0)));
+        assert_eq!(parse_band("120/2..60+5"), Some((60.0, 65.0)));
+        assert_eq!(parse_band("90..80"), None);
+        assert_eq!(parse_band("80.."), None);
+    }
+}
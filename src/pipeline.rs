@@ -0,0 +1,148 @@
+use std::fs;
+
+use crate::error::{self, Error};
+use crate::parse;
+use crate::json::{self, Json};
+use crate::problem::{self, Problem};
+use crate::{shortest_path_to_target, SearchOptions, Solution};
+
+// What a stage hands on to the next one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feed {
+    Outputs,
+    Remainder,
+    Both,
+}
+
+impl Feed {
+    pub fn from_name(name: &str) -> Option<Feed> {
+        match name {
+            "outputs" => Some(Feed::Outputs),
+            "remainder" => Some(Feed::Remainder),
+            "both" => Some(Feed::Both),
+            _ => None,
+        }
+    }
+
+    // The next stage's inputs from this stage's plan
+    pub fn rates(self, solution: &Solution) -> Vec<f64> {
+        match self {
+            Feed::Outputs => solution.outputs.clone(),
+            Feed::Remainder => solution.remainder.clone(),
+            Feed::Both => solution.outputs.iter().chain(&solution.remainder).copied().collect(),
+        }
+    }
+}
+
+// One solve in a pipeline. Its problem's inputs are filled in from the stage before when it runs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage {
+    pub name: String,
+    pub problem: Problem,
+    pub feed: Feed,
+}
+
+// Solves run one after another, each stage's outputs (or remainder) becoming the next one's inputs,
+// the way a multi-stage factory is planned:
+//
+//     {"inputs": [480, 480],
+//      "stages": [{"name": "smelting", "target": 120, "tolerance": 1, "feed": "outputs"},
+//                 {"name": "assembly", "target": 40, "max_depth": 8}]}
+//
+// A stage takes every field a problem file does apart from "inputs", plus "name" and "feed"
+// ("outputs", "remainder" or "both"; "outputs" by default)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pipeline {
+    pub inputs: Vec<f64>,
+    pub stages: Vec<Stage>,
+}
+
+// How far a pipeline got: a plan for each stage that found one, in order, and the name and inputs of
+// the stage that found none, if any did
+#[derive(Clone, Debug, PartialEq)]
+pub struct PipelineRun {
+    pub plans: Vec<(String, Solution)>,
+    pub failed: Option<(String, Vec<f64>)>,
+}
+
+impl Pipeline {
+    pub fn from_json_str(text: &str) -> Result<Pipeline, Error> {
+        let pipeline = json::parse(text).and_then(|document| Pipeline::from_document(&document)).map_err(Error::InvalidProblem)?;
+        error::check_range(&pipeline.inputs)?;
+        Ok(pipeline)
+    }
+
+    pub fn load(path: &str) -> Result<Pipeline, Error> {
+        Pipeline::load_template(path, &[])
+    }
+
+    // Read a pipeline file after filling in its `{name}` placeholders, as for a problem template
+    pub fn load_template(path: &str, variables: &[(String, String)]) -> Result<Pipeline, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        let text = problem::substitute(&text, variables).map_err(|message| Error::InvalidFile { path: path.to_string(), message })?;
+        Pipeline::from_json_str(&text).map_err(|e| match e {
+            Error::InvalidProblem(message) => Error::InvalidFile { path: path.to_string(), message },
+            e => e,
+        })
+    }
+
+    fn from_document(document: &Json) -> Result<Pipeline, String> {
+        let inputs = document
+            .get("inputs")
+            .and_then(Json::as_array)
+            .ok_or("missing array \"inputs\"")?
+            .iter()
+            .map(|value| match value {
+                Json::String(text) => parse::parse_inputs(text).map_err(|e| e.to_string()),
+                value => value.as_f64().map(|rate| vec![rate]).ok_or_else(|| "non-numeric value in \"inputs\"".to_string()),
+            })
+            .collect::<Result<Vec<Vec<f64>>, String>>()?
+            .concat();
+        let stages = document.get("stages").and_then(Json::as_array).ok_or("missing array \"stages\"")?;
+        if stages.is_empty() {
+            return Err("\"stages\" must not be empty".to_string());
+        }
+        let stages = stages
+            .iter()
+            .enumerate()
+            .map(|(index, stage)| {
+                let name = match stage.get("name") {
+                    Some(name) => name.as_str().ok_or_else(|| format!("name of stage {} must be a string", index + 1))?.to_string(),
+                    None => format!("stage {}", index + 1),
+                };
+                let feed = match stage.get("feed") {
+                    Some(feed) => feed
+                        .as_str()
+                        .and_then(Feed::from_name)
+                        .ok_or_else(|| format!("{}: \"feed\" must be \"outputs\", \"remainder\" or \"both\"", name))?,
+                    None => Feed::Outputs,
+                };
+                // Stages are problems without inputs of their own; a stand-in lets the problem parser check the rest
+                let mut fields = stage.as_object().ok_or_else(|| format!("stage {} must be an object", index + 1))?.clone();
+                if fields.contains_key("inputs") {
+                    return Err(format!("{}: inputs come from the stage before; only the pipeline has \"inputs\"", name));
+                }
+                fields.insert("inputs".to_string(), Json::Array(vec![Json::Number(0.0)]));
+                let problem = Problem::from_document(&Json::Object(fields)).map_err(|message| format!("{}: {}", name, message))?;
+                Ok(Stage { name, problem, feed })
+            })
+            .collect::<Result<Vec<Stage>, String>>()?;
+        Ok(Pipeline { inputs, stages })
+    }
+
+    // Solve the stages in order, stopping at the first one without a plan
+    pub fn run(&self, base: &SearchOptions) -> PipelineRun {
+        let mut inputs = self.inputs.clone();
+        let mut plans = Vec::new();
+        for stage in &self.stages {
+            let problem = &stage.problem;
+            let options = problem.search_options(base);
+            let Some(solution) = shortest_path_to_target(inputs.clone(), problem.target, problem.tolerance, &options) else {
+                return PipelineRun { plans, failed: Some((stage.name.clone(), inputs)) };
+            };
+            inputs = stage.feed.rates(&solution);
+            plans.push((stage.name.clone(), solution));
+        }
+        PipelineRun { plans, failed: None }
+    }
+}
@@ -0,0 +1,113 @@
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{search_solutions, Objective, SearchControl, SearchOptions, SearchStats, Solution, SolutionSet, Strategy};
+
+// How often the portfolio checks whether the caller cancelled it while its searches run
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+// One of each kind of strategy, for when none are named
+pub const DEFAULT_PORTFOLIO: [Strategy; 4] =
+    [Strategy::Greedy, Strategy::Shortest, Strategy::Weighted { step_cost: 1.0 }, Strategy::Beam { width: 256 }];
+
+// Which strategy's answer a portfolio returns
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PortfolioMode {
+    // Whichever finds a plan first; the others are cancelled
+    #[default]
+    First,
+    // The best plan once every strategy has finished or run out of time, ranked like top-k solutions
+    Best,
+}
+
+impl PortfolioMode {
+    pub fn from_name(name: &str) -> Option<PortfolioMode> {
+        match name {
+            "first" => Some(PortfolioMode::First),
+            "best" => Some(PortfolioMode::Best),
+            _ => None,
+        }
+    }
+}
+
+// How one strategy of a portfolio fared
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub strategy: Strategy,
+    pub solutions: SolutionSet,
+    pub stats: SearchStats,
+    pub elapsed: Duration,
+}
+
+// Every strategy's outcome, in the order given, and the index of the one whose plans won
+#[derive(Clone, Debug)]
+pub struct PortfolioRun {
+    pub entries: Vec<Entry>,
+    pub winner: Option<usize>,
+}
+
+// Run the same search under several strategies at once, one thread each, since no single ordering of
+// the frontier wins on every problem. `SearchOptions::timeout` bounds each of them, so it is the
+// portfolio's time budget; cancelling `SearchOptions::control` cancels them all
+pub fn solve_portfolio(
+    inputs: Vec<f64>,
+    target: f64,
+    can_be_off_by: f64,
+    options: &SearchOptions,
+    strategies: &[Strategy],
+    mode: PortfolioMode,
+    limit: usize,
+) -> PortfolioRun {
+    let controls: Vec<SearchControl> = strategies.iter().map(|_| SearchControl::new()).collect();
+    let mut entries: Vec<Option<Entry>> = vec![None; strategies.len()];
+    let mut first = None;
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for (index, (&strategy, control)) in strategies.iter().zip(&controls).enumerate() {
+            let mut options = options.clone();
+            options.strategy = strategy;
+            options.control = Some(control.clone());
+            let (inputs, sender) = (inputs.clone(), sender.clone());
+            scope.spawn(move || {
+                let start_time = Instant::now();
+                let (solutions, stats) = search_solutions(inputs, target, can_be_off_by, &options, limit);
+                let _ = sender.send((index, Entry { strategy, solutions, stats, elapsed: start_time.elapsed() }));
+            });
+        }
+        drop(sender);
+        loop {
+            match receiver.recv_timeout(CANCEL_POLL) {
+                Ok((index, entry)) => {
+                    if mode == PortfolioMode::First && first.is_none() && !entry.solutions.is_empty() {
+                        first = Some(index);
+                        controls.iter().for_each(SearchControl::cancel);
+                    }
+                    entries[index] = Some(entry);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if options.control.as_ref().is_some_and(SearchControl::is_cancelled) {
+                        controls.iter().for_each(SearchControl::cancel);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+    let entries: Vec<Entry> = entries.into_iter().map(|entry| entry.expect("every strategy reports")).collect();
+
+    let winner = match mode {
+        PortfolioMode::First => first,
+        PortfolioMode::Best => {
+            let objectives: Vec<Objective> = options.objectives.iter().copied().chain([Objective::Steps, Objective::Deviation]).collect();
+            let measure = |solution: &Solution| objectives.iter().map(|objective| objective.measure(solution)).collect::<Vec<f64>>();
+            entries
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| entry.solutions.best().map(|best| (index, measure(best))))
+                .min_by(|(_, a), (_, b)| a.iter().zip(b).map(|(a, b)| a.total_cmp(b)).find(|order| order.is_ne()).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(index, _)| index)
+        }
+    };
+    PortfolioRun { entries, winner }
+}
@@ -0,0 +1,46 @@
+use crate::problem::Problem;
+
+// A canned problem selectable by name, as an example to start from and as the corpus `bench` times
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub problem: &'static str, // A problem document, as for --problem
+}
+
+// Ordered from trivial to a few hundred milliseconds. The first five keep the names `bench` always
+// used, so older histories still compare
+pub const PRESETS: [Preset; 8] = [
+    Preset { name: "single-split", description: "One input split down to a fifth", problem: r#"{"inputs": [60], "target": 12, "tolerance": 1}"# },
+    Preset { name: "two-inputs", description: "Two inputs mixed to reach a rate neither divides into", problem: r#"{"inputs": [120, 45], "target": 20, "tolerance": 0.5}"# },
+    Preset { name: "near-miss", description: "A target only approachable within a narrow band", problem: r#"{"inputs": [60, 40], "target": 17, "tolerance": 0.3}"# },
+    Preset { name: "fine-tolerance", description: "A hard target with little slack", problem: r#"{"inputs": [100, 37], "target": 13, "tolerance": 0.1}"# },
+    Preset { name: "large-rates", description: "Two full Mk.5 belts feeding a mid-sized target", problem: r#"{"inputs": [780, 480], "target": 55, "tolerance": 0.5}"# },
+    Preset {
+        name: "satisfactory-oil-balance",
+        description: "240 m³/min of crude oil shared evenly by eight refineries through pipe junctions",
+        problem: r#"{"inputs": [240], "target": 30, "output_count": 8, "max_depth": 8, "max_flow": 600,
+                     "operations": ["split2", "split3", "combine2", "combine3"]}"#,
+    },
+    Preset {
+        name: "satisfactory-iron-manifold",
+        description: "A 480/min miner feeding smelters that take 30/min each",
+        problem: r#"{"inputs": [480], "target": 30, "tolerance": 0}"#,
+    },
+    Preset {
+        name: "factorio-lane-balance",
+        description: "A fast belt (30/s) balanced into four equal lanes with 2-way splitters",
+        problem: r#"{"inputs": [30], "target": 7.5, "output_count": 4, "max_flow": 45, "operations": ["split2", "combine2"]}"#,
+    },
+];
+
+impl Preset {
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn problem(&self) -> Problem {
+        // Every preset is checked by `bench` and the `presets` listing, so a broken one cannot ship unnoticed
+        Problem::from_json_str(self.problem).expect("built-in preset is a valid problem")
+    }
+}
@@ -0,0 +1,431 @@
+use std::fs;
+
+use crate::error::{self, Error};
+use crate::parse;
+use crate::json::{self, Json};
+use crate::{shortest_path_to_target, BeltTier, Objective, Operation, SearchOptions, Solution};
+
+// A problem definition: inputs to split/merge, the value to reach and how far off it may be,
+// plus optional constraints that override the caller's search options
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Problem {
+    pub inputs: Vec<f64>,
+    pub target: f64,
+    pub tolerance: f64,
+    pub operations: Option<Vec<Operation>>,
+    pub max_depth: Option<usize>,
+    pub max_flow: Option<f64>, // Largest flow one belt/pipe may carry
+    pub output_count: Option<usize>,
+    pub min_outputs: Option<usize>,
+    pub max_outputs: Option<usize>,
+    pub max_chain: Option<usize>,
+    pub limits: Vec<(Operation, usize)>, // {"limits": {"split3": 4}}: most uses of each operation
+    pub footprints: Vec<(Operation, f64)>, // {"footprints": {"split2": 2}}: floor area of each device
+    pub max_footprint: Option<f64>,
+    pub costs: Vec<(Operation, f64)>,      // {"costs": {"split3": 2.5}}: cost of each device
+    pub max_cost: Option<f64>,
+    pub max_value: Option<f64>,
+    pub forbidden: Vec<(f64, f64)>, // {"forbid": [86.667, "80..90"]}: rates no operation may produce
+    pub dedup_epsilon: Option<f64>,
+    pub input_capacities: Vec<(usize, f64)>, // {"input_capacities": [null, 300]}: capacity per input, null for none
+    pub separate_inputs: bool, // {"separate_inputs": true}: inputs must not be mixed
+    pub use_all_inputs: bool,  // {"use_all_inputs": true}: no input may pass straight to the remainder
+    pub optional_inputs: Vec<(usize, f64)>, // {"optional_inputs": [null, 1.5]}: penalty per optional input, null if required
+    pub objectives: Vec<Objective>, // {"objective": ["machines", "waste"]}: optimized in order
+}
+
+// A number per input, e.g. [null, 300], with null for inputs the setting does not apply to
+fn input_numbers(document: &Json, key: &str) -> Result<Vec<(usize, f64)>, String> {
+    let mut numbers = Vec::new();
+    if let Some(value) = document.get(key) {
+        let entries = value.as_array().ok_or_else(|| format!("\"{}\" must be an array with one entry per input", key))?;
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                Json::Null => {}
+                Json::Number(number) => numbers.push((index, *number)),
+                _ => return Err(format!("entry {} of \"{}\" must be a number or null", index + 1, key)),
+            }
+        }
+    }
+    Ok(numbers)
+}
+
+fn optional_flag(document: &Json, key: &str) -> Result<bool, String> {
+    match document.get(key) {
+        Some(Json::Bool(value)) => Ok(*value),
+        Some(_) => Err(format!("\"{}\" must be true or false", key)),
+        None => Ok(false),
+    }
+}
+
+pub(crate) fn optional_number(document: &Json, key: &str) -> Result<Option<f64>, String> {
+    match document.get(key) {
+        Some(value) => rate(value).map(Some).ok_or_else(|| format!("\"{}\" must be a number", key)),
+        None => Ok(None),
+    }
+}
+
+// A number, or arithmetic written as a string like "{miners}*60" once a template's variables are filled in
+fn rate(value: &Json) -> Option<f64> {
+    match value {
+        Json::String(text) => parse::evaluate(text),
+        value => value.as_f64(),
+    }
+}
+
+// Rates and ranges of rates, e.g. [86.667, "80..90"]
+fn forbidden_bands(document: &Json) -> Result<Vec<(f64, f64)>, String> {
+    let Some(value) = document.get("forbid") else { return Ok(Vec::new()) };
+    let entries = value.as_array().ok_or("\"forbid\" must be an array of rates and ranges like \"80..90\"")?;
+    entries
+        .iter()
+        .map(|entry| match entry {
+            Json::Number(rate) => Ok((*rate, *rate)),
+            Json::String(text) => parse::parse_band(text).ok_or_else(|| format!("\"{}\" in \"forbid\" is not a rate or a range like 80..90", text)),
+            _ => Err("entries of \"forbid\" must be rates or ranges like \"80..90\"".to_string()),
+        })
+        .collect()
+}
+
+// A number per operation, e.g. {"split2": 2, "combine2": 2}
+pub(crate) fn operation_numbers(document: &Json, key: &str) -> Result<Vec<(Operation, f64)>, String> {
+    let mut numbers = Vec::new();
+    if let Some(value) = document.get(key) {
+        for (name, number) in value.as_object().ok_or_else(|| format!("\"{}\" must be an object keyed by operation", key))? {
+            let operation = Operation::from_name(name).ok_or_else(|| format!("unknown operation {} in \"{}\"", name, key))?;
+            numbers.push((operation, number.as_f64().ok_or_else(|| format!("{} in \"{}\" must be a number", name, key))?));
+        }
+    }
+    Ok(numbers)
+}
+
+pub(crate) fn optional_count(document: &Json, key: &str) -> Result<Option<usize>, String> {
+    match document.get(key) {
+        Some(value) => match value.as_f64() {
+            Some(count) if count >= 0.0 && count.fract() == 0.0 => Ok(Some(count as usize)),
+            _ => Err(format!("\"{}\" must be a whole number", key)),
+        },
+        None => Ok(None),
+    }
+}
+
+impl Problem {
+    // Parse a JSON document like {"inputs": [60], "target": 12, "tolerance": 1, "operations": ["split2"]}
+    pub fn from_json_str(text: &str) -> Result<Problem, Error> {
+        Problem::checked(json::parse(text).and_then(|document| Problem::from_document(&document)))
+    }
+
+    // Parse the same fields written as TOML `key = value` lines
+    pub fn from_toml_str(text: &str) -> Result<Problem, Error> {
+        Problem::checked(toml_document(text).and_then(|document| Problem::from_document(&document)))
+    }
+
+    // Read a problem file, choosing the format by its extension
+    pub fn load(path: &str) -> Result<Problem, Error> {
+        Problem::load_template(path, &[])
+    }
+
+    // Read a problem file after filling in its `{name}` placeholders, as `substitute` does
+    pub fn load_template(path: &str, variables: &[(String, String)]) -> Result<Problem, Error> {
+        let document = Problem::read_document(path, variables)?;
+        Problem::from_json(&document).map_err(|e| match e {
+            Error::InvalidProblem(message) => Error::InvalidFile { path: path.to_string(), message },
+            e => e,
+        })
+    }
+
+    // The parsed but uninterpreted document of a problem file, JSON whatever format it was written in,
+    // for passing a problem on without losing any of its settings
+    pub fn read_document(path: &str, variables: &[(String, String)]) -> Result<Json, Error> {
+        let invalid = |message| Error::InvalidFile { path: path.to_string(), message };
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        let text = substitute(&text, variables).map_err(invalid)?;
+        if path.ends_with(".toml") { toml_document(&text) } else { json::parse(&text) }.map_err(invalid)
+    }
+
+    pub fn from_json(document: &Json) -> Result<Problem, Error> {
+        Problem::checked(Problem::from_document(document))
+    }
+
+    fn checked(parsed: Result<Problem, String>) -> Result<Problem, Error> {
+        let problem = parsed.map_err(Error::InvalidProblem)?;
+        error::check_range(&problem.inputs)?;
+        Ok(problem)
+    }
+
+    pub(crate) fn from_document(document: &Json) -> Result<Problem, String> {
+        let mut inputs = Vec::new();
+        for value in document.get("inputs").and_then(Json::as_array).ok_or("missing array \"inputs\"")? {
+            match value {
+                Json::Number(rate) => inputs.push(*rate),
+                // Read as on the command line, so a template can write "{miners}x480"
+                Json::String(text) => inputs.extend(parse::parse_inputs(text).map_err(|e| e.to_string())?),
+                _ => return Err("non-numeric value in \"inputs\"".to_string()),
+            }
+        }
+        let target = document.get("target").and_then(rate).ok_or("missing number \"target\"")?;
+        let tolerance = match document.get("tolerance") {
+            Some(value) => rate(value).ok_or("\"tolerance\" must be a number")?,
+            None => 0.0,
+        };
+        if inputs.is_empty() {
+            return Err("\"inputs\" must not be empty".to_string());
+        }
+        let operations = match document.get("operations") {
+            Some(value) => Some(
+                value
+                    .as_array()
+                    .ok_or("\"operations\" must be an array")?
+                    .iter()
+                    .map(|name| {
+                        let name = name.as_str().ok_or("non-string value in \"operations\"")?;
+                        Operation::from_name(name).ok_or_else(|| format!("unknown operation {}", name))
+                    })
+                    .collect::<Result<Vec<Operation>, String>>()?,
+            ),
+            None => None,
+        };
+        let mut objectives = Vec::new();
+        if let Some(value) = document.get("objective") {
+            for name in value.as_array().ok_or("\"objective\" must be an array of objective names")? {
+                let name = name.as_str().ok_or("non-string value in \"objective\"")?;
+                objectives.push(Objective::from_name(name).ok_or_else(|| format!("unknown objective {}", name))?);
+            }
+        }
+        let max_flow = match document.get("max_flow") {
+            Some(value) => Some(value.as_f64().ok_or("\"max_flow\" must be a number")?),
+            None => None,
+        };
+        let mut limits = Vec::new();
+        if let Some(value) = document.get("limits") {
+            for (name, count) in value.as_object().ok_or("\"limits\" must be an object of operation counts")? {
+                let operation = Operation::from_name(name).ok_or_else(|| format!("unknown operation {} in \"limits\"", name))?;
+                match count.as_f64() {
+                    Some(count) if count >= 0.0 && count.fract() == 0.0 => limits.push((operation, count as usize)),
+                    _ => return Err(format!("limit for {} must be a whole number", name)),
+                }
+            }
+        }
+        Ok(Problem {
+            inputs,
+            target,
+            tolerance,
+            operations,
+            max_depth: optional_count(document, "max_depth")?,
+            max_flow,
+            output_count: optional_count(document, "output_count")?,
+            min_outputs: optional_count(document, "min_outputs")?,
+            max_outputs: optional_count(document, "max_outputs")?,
+            max_chain: optional_count(document, "max_chain")?,
+            limits,
+            footprints: operation_numbers(document, "footprints")?,
+            max_footprint: optional_number(document, "max_footprint")?,
+            costs: operation_numbers(document, "costs")?,
+            max_cost: optional_number(document, "max_cost")?,
+            max_value: optional_number(document, "max_value")?,
+            forbidden: forbidden_bands(document)?,
+            dedup_epsilon: optional_number(document, "dedup_epsilon")?,
+            input_capacities: input_numbers(document, "input_capacities")?,
+            separate_inputs: optional_flag(document, "separate_inputs")?,
+            use_all_inputs: optional_flag(document, "use_all_inputs")?,
+            optional_inputs: input_numbers(document, "optional_inputs")?,
+            objectives,
+        })
+    }
+
+    // The caller's options with this problem's constraints applied on top
+    pub fn search_options(&self, base: &SearchOptions) -> SearchOptions {
+        let mut options = base.clone();
+        if let Some(operations) = &self.operations {
+            options.operations = operations.clone();
+        }
+        if let Some(depth) = self.max_depth {
+            options.max_depth = depth;
+        }
+        if let Some(capacity) = self.max_flow {
+            options.belt_tiers = vec![BeltTier { name: "max flow".to_string(), capacity }];
+        }
+        if self.output_count.is_some() {
+            options.output_count = self.output_count;
+        }
+        if let Some(count) = self.min_outputs {
+            options.min_outputs = count;
+        }
+        if self.max_outputs.is_some() {
+            options.max_outputs = self.max_outputs;
+        }
+        for &(operation, count) in &self.limits {
+            options.operation_limits.retain(|&(limited, _)| limited != operation);
+            options.operation_limits.push((operation, count));
+        }
+        for &(operation, area) in &self.footprints {
+            options.footprints.retain(|&(declared, _)| declared != operation);
+            options.footprints.push((operation, area));
+        }
+        if self.max_footprint.is_some() {
+            options.max_footprint = self.max_footprint;
+        }
+        for &(operation, cost) in &self.costs {
+            options.costs.retain(|&(declared, _)| declared != operation);
+            options.costs.push((operation, cost));
+        }
+        if self.max_cost.is_some() {
+            options.max_cost = self.max_cost;
+        }
+        if self.max_value.is_some() {
+            options.max_value = self.max_value;
+        }
+        options.forbidden.extend_from_slice(&self.forbidden);
+        if self.dedup_epsilon.is_some() {
+            options.dedup_epsilon = self.dedup_epsilon;
+        }
+        for &(input, capacity) in &self.input_capacities {
+            options.input_capacities.retain(|&(declared, _)| declared != input);
+            options.input_capacities.push((input, capacity));
+        }
+        for &(input, penalty) in &self.optional_inputs {
+            options.optional_inputs.retain(|&(declared, _)| declared != input);
+            options.optional_inputs.push((input, penalty));
+        }
+        if !self.objectives.is_empty() {
+            options.objectives = self.objectives.clone();
+        }
+        options.separate_inputs |= self.separate_inputs;
+        options.use_all_inputs |= self.use_all_inputs;
+        if self.max_chain.is_some() {
+            options.max_chain = self.max_chain;
+        }
+        options
+    }
+
+    pub fn solve(&self, base: &SearchOptions) -> Option<Solution> {
+        shortest_path_to_target(self.inputs.clone(), self.target, self.tolerance, &self.search_options(base))
+    }
+}
+
+// Fill a template's `{name}` placeholders from name=value pairs, e.g. from `--set miners=3`, so one file
+// covers a family of problems: {"inputs": ["{miners}x480"], "target": "{machines}*60"}. A placeholder
+// nothing sets is an error; braces around anything but a name are left alone
+pub fn substitute(text: &str, variables: &[(String, String)]) -> Result<String, String> {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_length = after.find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_')).unwrap_or(after.len());
+        if name_length == 0 || !after[name_length..].starts_with('}') {
+            filled.push('{');
+            rest = after;
+            continue;
+        }
+        let name = &after[..name_length];
+        let (_, value) = variables
+            .iter()
+            .rev()
+            .find(|(variable, _)| variable == name)
+            .ok_or_else(|| format!("no value for {{{}}}; set one with --set {}=VALUE", name, name))?;
+        filled.push_str(value);
+        rest = &after[name_length + 1..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+// The subset of TOML a problem needs: top-level `key = value` lines whose values are numbers,
+// strings, booleans or single-line arrays of those, all of which read as JSON
+fn toml_document(text: &str) -> Result<Json, String> {
+    let mut fields = std::collections::BTreeMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| format!("expected key = value on line {}", number + 1))?;
+        let value = json::parse(value.trim()).map_err(|e| format!("invalid value on line {}: {}", number + 1, e))?;
+        fields.insert(key.trim().trim_matches('"').to_string(), value);
+    }
+    Ok(Json::Object(fields))
+}
+
+// Drop a trailing `# comment`, leaving `#` inside strings alone
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid(text: &str) -> String {
+        match Problem::from_json_str(text) {
+            Err(Error::InvalidProblem(message)) => message,
+            other => panic!("expected an invalid problem from {}, got {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn reads_valid_documents() {
+        let problem = Problem::from_json_str(
+            r#"{"inputs": [60, "2x120/2"], "target": "780/65", "tolerance": 1, "operations": ["split2", "combine3"],
+                "max_depth": 8, "limits": {"split3": 4}, "forbid": [86.667, "80..90"],
+                "input_capacities": [null, 300], "use_all_inputs": true, "objective": ["machines"]}"#,
+        )
+        .expect("valid problem");
+        assert_eq!(problem.inputs, [60.0, 60.0, 60.0]);
+        assert_eq!((problem.target, problem.tolerance), (12.0, 1.0));
+        assert_eq!(problem.operations, Some(vec![Operation::SplitTwo, Operation::CombineThree]));
+        assert_eq!(problem.max_depth, Some(8));
+        assert_eq!(problem.limits, [(Operation::SplitThree, 4)]);
+        assert_eq!(problem.forbidden, [(86.667, 86.667), (80.0, 90.0)]);
+        assert_eq!(problem.input_capacities, [(1, 300.0)]);
+        assert!(problem.use_all_inputs && !problem.separate_inputs);
+        assert_eq!(problem.objectives, [Objective::Steps]);
+
+        let toml = "# same problem\ninputs = [60, \"2x120/2\"]\ntarget = \"780/65\" # twelve\ntolerance = 1\nnote = \"# kept\"";
+        let from_toml = Problem::from_toml_str(toml).expect("valid TOML problem");
+        assert_eq!((from_toml.inputs, from_toml.target, from_toml.tolerance), (problem.inputs, 12.0, 1.0));
+        // Tolerance defaults to exact
+        assert_eq!(Problem::from_json_str(r#"{"inputs": [60], "target": 12}"#).unwrap().tolerance, 0.0);
+    }
+
+    #[test]
+    fn names_missing_fields() {
+        assert_eq!(invalid(r#"{"target": 12}"#), "missing array \"inputs\"");
+        assert_eq!(invalid(r#"{"inputs": [60]}"#), "missing number \"target\"");
+        assert_eq!(invalid(r#"{"inputs": [], "target": 12}"#), "\"inputs\" must not be empty");
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        assert!(Problem::from_json_str(r#"{"inputs": [60], "target": 12"#).is_err());
+        assert!(Problem::from_toml_str("inputs [60]").is_err());
+        for (document, message) in [
+            (r#"{"inputs": [true], "target": 12}"#, "non-numeric value in \"inputs\""),
+            (r#"{"inputs": [60], "target": 12, "tolerance": "wide"}"#, "\"tolerance\" must be a number"),
+            (r#"{"inputs": [60], "target": 12, "operations": ["split4"]}"#, "unknown operation split4"),
+            (r#"{"inputs": [60], "target": 12, "max_depth": 2.5}"#, "\"max_depth\" must be a whole number"),
+            (r#"{"inputs": [60], "target": 12, "limits": {"split2": -1}}"#, "limit for split2 must be a whole number"),
+            (r#"{"inputs": [60], "target": 12, "forbid": ["90..80"]}"#, "\"90..80\" in \"forbid\" is not a rate or a range like 80..90"),
+            (r#"{"inputs": [60], "target": 12, "use_all_inputs": 1}"#, "\"use_all_inputs\" must be true or false"),
+            (r#"{"inputs": [60], "target": 12, "input_capacities": ["wide"]}"#, "entry 1 of \"input_capacities\" must be a number or null"),
+        ] {
+            assert_eq!(invalid(document), message);
+        }
+        assert!(matches!(Problem::from_json_str(r#"{"inputs": [1e300, 1e300], "target": 12}"#), Err(Error::Overflow { .. })));
+    }
+
+    #[test]
+    fn fills_in_templates() {
+        let variables = [("miners".to_string(), "3".to_string()), ("miners".to_string(), "4".to_string())];
+        assert_eq!(substitute(r#"{"inputs": ["{miners}x480"]}"#, &variables).unwrap(), r#"{"inputs": ["4x480"]}"#);
+        assert!(substitute("{machines}", &variables).unwrap_err().contains("--set machines=VALUE"));
+    }
+}
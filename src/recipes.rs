@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::json::{self, Json};
+
+// Bundled recipe database; a user-supplied file may extend or override it
+const BUNDLED_RECIPES: &str = include_str!("recipes.json");
+
+// What one machine running a recipe at 100% clock consumes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recipe {
+    pub input: String,
+    pub rate: f64,
+}
+
+// Recipe name -> consumption, loaded from JSON of the form {"name": {"input": "...", "rate": 30}}
+#[derive(Clone, Debug, Default)]
+pub struct RecipeDatabase {
+    recipes: BTreeMap<String, Recipe>,
+}
+
+impl RecipeDatabase {
+    pub fn bundled() -> RecipeDatabase {
+        let mut database = RecipeDatabase::default();
+        database.extend_from_json(BUNDLED_RECIPES).expect("Bundled recipe database is invalid");
+        database
+    }
+
+    // Add (or override) recipes from a user-supplied JSON file
+    pub fn load_file(&mut self, path: &str) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        self.extend_from_json(&text).map_err(|e| format!("Invalid recipe file {}: {}", path, e))
+    }
+
+    fn extend_from_json(&mut self, text: &str) -> Result<(), String> {
+        let document = json::parse(text)?;
+        let entries = document.as_object().ok_or("expected an object of recipes")?;
+        for (name, entry) in entries {
+            let rate = entry
+                .get("rate")
+                .and_then(Json::as_f64)
+                .ok_or_else(|| format!("recipe {} has no numeric rate", name))?;
+            let input = entry.get("input").and_then(Json::as_str).unwrap_or("").to_string();
+            self.recipes.insert(name.clone(), Recipe { input, rate });
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.get(name)
+    }
+}
+
+// A target written as `recipe:<name> x <machines>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecipeTarget {
+    pub name: String,
+    pub machines: usize,
+}
+
+// Parse `recipe:iron-plate x 12` (the machine count is optional and defaults to one)
+pub fn parse_recipe_target(text: &str) -> Option<Result<RecipeTarget, String>> {
+    let spec = text.trim().strip_prefix("recipe:")?;
+    let (name, machines) = match spec.rsplit_once(['x', '×']) {
+        Some((name, count)) if !count.trim().is_empty() && count.trim().chars().all(|c| c.is_ascii_digit()) => {
+            (name.trim(), count.trim().parse::<usize>())
+        }
+        _ => (spec.trim(), Ok(1)),
+    };
+    Some(match machines {
+        Ok(machines) if !name.is_empty() => Ok(RecipeTarget { name: name.to_string(), machines }),
+        _ => Err(format!("Invalid recipe target: {}", text)),
+    })
+}
@@ -0,0 +1,583 @@
+use std::collections::BTreeMap;
+
+use crate::graph::{self, FlowGraph, NodeKind};
+use crate::json::Json;
+use crate::{Operation, SearchStats, Solution, Step};
+
+// Formats a solution can be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    Dot,
+    Mermaid,
+    Csv,
+    Html,
+    Tikz,
+    Cytoscape,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name {
+            "human" | "text" => Some(OutputFormat::Human),
+            "json" => Some(OutputFormat::Json),
+            "dot" => Some(OutputFormat::Dot),
+            "mermaid" => Some(OutputFormat::Mermaid),
+            "csv" => Some(OutputFormat::Csv),
+            "html" => Some(OutputFormat::Html),
+            "tikz" => Some(OutputFormat::Tikz),
+            "cytoscape" => Some(OutputFormat::Cytoscape),
+            _ => None,
+        }
+    }
+}
+
+pub fn render(solution: &Solution, format: OutputFormat) -> String {
+    render_with(solution, format, &NumberFormat::default())
+}
+
+pub fn render_with(solution: &Solution, format: OutputFormat, numbers: &NumberFormat) -> String {
+    match format {
+        OutputFormat::Human => human(solution, numbers),
+        OutputFormat::Json => format!("{}\n", to_json(solution)),
+        OutputFormat::Dot => dot(solution, numbers),
+        OutputFormat::Mermaid => mermaid(solution, numbers),
+        OutputFormat::Csv => csv(solution, numbers),
+        OutputFormat::Html => html(solution, None, numbers),
+        OutputFormat::Tikz => tikz(solution, numbers),
+        OutputFormat::Cytoscape => format!("{}\n", cytoscape(solution, numbers)),
+    }
+}
+
+// How rates are printed for people; structured formats always keep full precision
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimals: Option<usize>, // Fixed decimal places; by default rates print as computed
+    pub thousands: bool,         // Group integer digits in threes with commas
+}
+
+impl NumberFormat {
+    pub fn is_default(&self) -> bool {
+        *self == NumberFormat::default()
+    }
+
+    // A rate on its own, e.g. in a table or a diagram label
+    pub fn label(&self, rate: f64) -> String {
+        match self.decimals {
+            Some(decimals) => self.group(format!("{:.*}", decimals, rate)),
+            None => self.group(rate_label(rate)),
+        }
+    }
+
+    // A rate inside a sentence, printed like `{}` unless a format was asked for
+    pub fn value(&self, rate: f64) -> String {
+        if self.is_default() {
+            return rate.to_string();
+        }
+        self.label(rate)
+    }
+
+    // A list of rates, printed like `{:?}` unless a format was asked for
+    pub fn list(&self, rates: &[f64]) -> String {
+        if self.is_default() {
+            return format!("{:?}", rates);
+        }
+        format!("[{}]", rates.iter().map(|&rate| self.label(rate)).collect::<Vec<_>>().join(", "))
+    }
+
+    // Reformat every number inside free text such as a step description
+    pub fn text(&self, text: &str) -> String {
+        if self.is_default() {
+            return text.to_string();
+        }
+        let mut result = String::new();
+        let mut number = String::new();
+        for ch in text.chars().chain(core::iter::once(' ')) {
+            if ch.is_ascii_digit() || (ch == '.' && !number.is_empty()) {
+                number.push(ch);
+                continue;
+            }
+            if !number.is_empty() {
+                match number.parse::<f64>() {
+                    Ok(value) => result.push_str(&self.label(value)),
+                    Err(_) => result.push_str(&number),
+                }
+                number.clear();
+            }
+            result.push(ch);
+        }
+        result.pop();
+        result
+    }
+
+    fn group(&self, text: String) -> String {
+        if !self.thousands {
+            return text;
+        }
+        let (sign, unsigned) = text.strip_prefix('-').map_or(("", text.as_str()), |rest| ("-", rest));
+        let (integer, fraction) = unsigned.split_once('.').map_or((unsigned, None), |(i, f)| (i, Some(f)));
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        match fraction {
+            Some(fraction) => format!("{}{}.{}", sign, grouped, fraction),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+}
+
+// Rates rounded for labels, without trailing zeros
+fn rate_label(rate: f64) -> String {
+    let text = format!("{:.3}", rate);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn numbers(values: &[f64]) -> Json {
+    Json::Array(values.iter().map(|&v| Json::Number(v)).collect())
+}
+
+pub fn to_json(solution: &Solution) -> Json {
+    let steps = solution
+        .steps
+        .iter()
+        .map(|step| {
+            let mut fields = BTreeMap::new();
+            fields.insert("operation".to_string(), Json::String(step.operation.name().to_string()));
+            fields.insert("consumed".to_string(), numbers(&step.consumed));
+            fields.insert("produced".to_string(), numbers(&step.produced));
+            fields.insert("description".to_string(), Json::String(step.description.clone()));
+            Json::Object(fields)
+        })
+        .collect();
+
+    let mut fields = BTreeMap::new();
+    fields.insert("inputs".to_string(), numbers(&solution.inputs));
+    fields.insert("target".to_string(), Json::Number(solution.target));
+    fields.insert("outputs".to_string(), numbers(&solution.outputs));
+    fields.insert("remainder".to_string(), numbers(&solution.remainder));
+    fields.insert("steps".to_string(), Json::Array(steps));
+    if solution.penalty > 0.0 {
+        fields.insert("penalty".to_string(), Json::Number(solution.penalty));
+    }
+    Json::Object(fields)
+}
+
+fn number_list(document: &Json, key: &str) -> Result<Vec<f64>, String> {
+    document
+        .get(key)
+        .and_then(Json::as_array)
+        .ok_or_else(|| format!("missing array \"{}\"", key))?
+        .iter()
+        .map(|value| value.as_f64().ok_or_else(|| format!("non-numeric value in \"{}\"", key)))
+        .collect()
+}
+
+pub fn from_json(document: &Json) -> Result<Solution, String> {
+    let steps = document
+        .get("steps")
+        .and_then(Json::as_array)
+        .ok_or("missing array \"steps\"")?
+        .iter()
+        .map(|step| {
+            let name = step.get("operation").and_then(Json::as_str).ok_or("step without operation")?;
+            let operation = Operation::from_name(name).ok_or_else(|| format!("unknown operation {}", name))?;
+            Ok(Step {
+                operation,
+                consumed: number_list(step, "consumed")?,
+                produced: number_list(step, "produced")?,
+                description: step.get("description").and_then(Json::as_str).unwrap_or("").to_string(),
+            })
+        })
+        .collect::<Result<Vec<Step>, String>>()?;
+
+    Ok(Solution {
+        inputs: number_list(document, "inputs")?,
+        target: document.get("target").and_then(Json::as_f64).ok_or("missing number \"target\"")?,
+        outputs: number_list(document, "outputs")?,
+        remainder: number_list(document, "remainder")?,
+        steps,
+        penalty: document.get("penalty").and_then(Json::as_f64).unwrap_or(0.0),
+    })
+}
+
+pub fn human(solution: &Solution, numbers: &NumberFormat) -> String {
+    human_summary(solution, numbers) + &human_steps(solution, numbers) + &efficiency_summary(solution, numbers)
+}
+
+// Depth, outputs and remainder lines of the human format
+pub fn human_summary(solution: &Solution, numbers: &NumberFormat) -> String {
+    let mut text = String::new();
+    text.push_str(&format!("Solution found at level {}\n", solution.steps.len()));
+    text.push_str(&output_table(solution, numbers));
+    text.push_str(&format!("Remainder: {}\n", numbers.list(&solution.remainder)));
+    if solution.penalty > 0.0 {
+        text.push_str(&format!("Penalty: {} for optional input(s) left untouched\n", numbers.value(solution.penalty)));
+    }
+    text
+}
+
+// The single line worth pasting when comparing plans: throughput, waste, efficiency, devices and depth
+pub fn efficiency_summary(solution: &Solution, numbers: &NumberFormat) -> String {
+    let input: f64 = solution.inputs.iter().sum();
+    let matched: f64 = solution.outputs.iter().sum();
+    let waste = solution.remainder.iter().fold(0.0, |total, part| total + part); // `sum` of no floats is -0
+    let efficiency = if input > 0.0 { matched / input * 100.0 } else { 0.0 };
+    let depth = step_numbers(solution).iter().map(|(_, number)| number.matches('.').count() + 1).max().unwrap_or(0);
+    format!(
+        "Summary: input {}, matched {}, waste {}, efficiency {:.1}%, {} device(s), depth {}\n",
+        numbers.label(input),
+        numbers.label(matched),
+        numbers.label(waste),
+        efficiency,
+        solution.steps.len(),
+        depth
+    )
+}
+
+// One row per output comparing it with the target
+pub fn output_table(solution: &Solution, numbers: &NumberFormat) -> String {
+    let mut text = format!("{:>6} {:>10} {:>10} {:>10} {:>8}\n", "output", "target", "achieved", "deviation", "error");
+    for (index, &output) in solution.outputs.iter().enumerate() {
+        let deviation = output - solution.target;
+        // Rounded to what is shown, and clear of negative zero, so a match never reads as "-0"
+        let scale = 10f64.powi(numbers.decimals.map_or(3, |decimals| decimals as i32));
+        let shown = (deviation * scale).round() / scale + 0.0;
+        let error = if solution.target != 0.0 { format!("{:+.2}%", (deviation / solution.target * 10_000.0).round() / 100.0 + 0.0) } else { "-".to_string() };
+        text.push_str(&format!(
+            "{:>6} {:>10} {:>10} {:>10} {:>8}\n",
+            index + 1,
+            numbers.label(solution.target),
+            numbers.label(output),
+            match numbers.decimals {
+                Some(_) if shown >= 0.0 => format!("+{}", numbers.label(shown)),
+                Some(_) => numbers.label(shown),
+                None => format!("{:+}", shown),
+            },
+            error
+        ));
+    }
+    text
+}
+
+// One line per step of the human format, numbered and indented by the branch of the plan it belongs to.
+// Runs of identical sibling steps that feed nothing further collapse into one "×N:" line
+pub fn human_steps(solution: &Solution, numbers: &NumberFormat) -> String {
+    let numbered = step_numbers(solution);
+    let is_leaf = |position: usize| {
+        let prefix = format!("{}.", numbered[position].1);
+        numbered.get(position + 1).is_none_or(|(_, next)| !next.starts_with(&prefix))
+    };
+
+    let mut text = String::new();
+    let mut position = 0;
+    while position < numbered.len() {
+        let (index, number) = &numbered[position];
+        let depth = number.matches('.').count();
+        let mut end = position + 1;
+        while end < numbered.len()
+            && is_leaf(end - 1)
+            && is_leaf(end)
+            && numbered[end].1.matches('.').count() == depth
+            && solution.steps[numbered[end].0].description == solution.steps[*index].description
+        {
+            end += 1;
+        }
+        let indent = "  ".repeat(depth);
+        if end - position > 1 {
+            let last = &numbered[end - 1].1;
+            let step = numbers.text(&solution.steps[*index].to_string());
+            text.push_str(&format!("{}{}–{} ×{}: {}\n", indent, number, last, end - position, step));
+        } else {
+            text.push_str(&format!("{}{} {}\n", indent, number, numbers.text(&solution.steps[*index].to_string())));
+        }
+        position = end;
+    }
+    text
+}
+
+// Steps in tree order with numbers like 1, 1.1, 1.2, 2: a step is a child of the last step
+// whose output it consumes, and steps fed only by inputs start new top-level branches
+pub fn step_numbers(solution: &Solution) -> Vec<(usize, String)> {
+    let graph = graph::build(solution);
+    let first_step = solution.inputs.len(); // Operation nodes follow the input nodes, one per step
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); solution.steps.len()];
+    let mut roots = Vec::new();
+    for step in 0..solution.steps.len() {
+        let parent = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.to == first_step + step && edge.from >= first_step)
+            .map(|edge| edge.from - first_step)
+            .max();
+        match parent {
+            Some(parent) => children[parent].push(step),
+            None => roots.push(step),
+        }
+    }
+
+    let mut numbered = Vec::new();
+    let mut pending: Vec<(usize, String)> = roots.iter().enumerate().rev().map(|(i, &step)| (step, format!("{}", i + 1))).collect();
+    while let Some((step, number)) = pending.pop() {
+        for (i, &child) in children[step].iter().enumerate().rev() {
+            pending.push((child, format!("{}.{}", number, i + 1)));
+        }
+        numbered.push((step, number));
+    }
+    numbered
+}
+
+fn dot(solution: &Solution, numbers: &NumberFormat) -> String {
+    let graph = graph::build(solution);
+    let mut text = String::from("digraph plan {\n    rankdir=LR;\n");
+    for node in &graph.nodes {
+        let shape = match node.kind {
+            NodeKind::Input => "invhouse",
+            NodeKind::Operation(_) => "box",
+            NodeKind::Output => "house",
+            NodeKind::Remainder => "ellipse",
+        };
+        text.push_str(&format!("    n{} [label=\"{}\", shape={}];\n", node.id, node.label, shape));
+    }
+    for edge in &graph.edges {
+        text.push_str(&format!("    n{} -> n{} [label=\"{}\"];\n", edge.from, edge.to, numbers.label(edge.rate)));
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn mermaid(solution: &Solution, numbers: &NumberFormat) -> String {
+    let graph = graph::build(solution);
+    let mut text = String::from("flowchart LR\n");
+    for node in &graph.nodes {
+        let shape = match node.kind {
+            NodeKind::Input => format!("n{}[/\"{}\"/]", node.id, node.label),
+            NodeKind::Operation(_) => format!("n{}[\"{}\"]", node.id, node.label),
+            NodeKind::Output => format!("n{}((\"{}\"))", node.id, node.label),
+            NodeKind::Remainder => format!("n{}([\"{}\"])", node.id, node.label),
+        };
+        text.push_str(&format!("    {}\n", shape));
+    }
+    for edge in &graph.edges {
+        text.push_str(&format!("    n{} -->|{}| n{}\n", edge.from, numbers.label(edge.rate), edge.to));
+    }
+    text
+}
+
+// A tikzpicture for LaTeX documents; needs \\usetikzlibrary{shapes.geometric}
+fn tikz(solution: &Solution, numbers: &NumberFormat) -> String {
+    let graph = graph::build(solution);
+    let cells = layout(&graph);
+    let mut text = String::from("\\begin{tikzpicture}[>=stealth, every node/.style={draw, font=\\small, minimum height=7mm}]\n");
+    for node in &graph.nodes {
+        let (col, row) = cells[node.id];
+        let style = match node.kind {
+            NodeKind::Input => "trapezium, trapezium left angle=70, trapezium right angle=110",
+            NodeKind::Operation(_) => "rectangle",
+            NodeKind::Output => "rectangle, rounded corners, fill=green!15",
+            NodeKind::Remainder => "ellipse, fill=yellow!15",
+        };
+        let label = node.label.replace('_', "\\_");
+        text.push_str(&format!("  \\node[{}] (n{}) at ({:.1}, {:.1}) {{{}}};\n", style, node.id, col as f64 * 3.0, 0.0 - row as f64 * 1.2, label));
+    }
+    for edge in &graph.edges {
+        text.push_str(&format!(
+            "  \\draw[->] (n{}) -- node[draw=none, fill=white, font=\\scriptsize] {{{}}} (n{});\n",
+            edge.from,
+            numbers.label(edge.rate),
+            edge.to
+        ));
+    }
+    text.push_str("\\end{tikzpicture}\n");
+    text
+}
+
+// Graph JSON in the {"elements": {"nodes": [...], "edges": [...]}} shape Cytoscape.js loads directly
+pub fn cytoscape(solution: &Solution, numbers: &NumberFormat) -> Json {
+    let graph = graph::build(solution);
+    let data = |fields: Vec<(&str, Json)>| {
+        let fields = fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+        let mut element = BTreeMap::new();
+        element.insert("data".to_string(), Json::Object(fields));
+        Json::Object(element)
+    };
+
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let kind = match node.kind {
+                NodeKind::Input => "input",
+                NodeKind::Operation(_) => "operation",
+                NodeKind::Output => "output",
+                NodeKind::Remainder => "remainder",
+            };
+            data(vec![
+                ("id", Json::String(format!("n{}", node.id))),
+                ("label", Json::String(node.label.clone())),
+                ("kind", Json::String(kind.to_string())),
+            ])
+        })
+        .collect();
+    let edges = graph
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(index, edge)| {
+            data(vec![
+                ("id", Json::String(format!("e{}", index))),
+                ("source", Json::String(format!("n{}", edge.from))),
+                ("target", Json::String(format!("n{}", edge.to))),
+                ("rate", Json::Number(edge.rate)),
+                ("label", Json::String(numbers.label(edge.rate))),
+            ])
+        })
+        .collect();
+
+    let mut elements = BTreeMap::new();
+    elements.insert("nodes".to_string(), Json::Array(nodes));
+    elements.insert("edges".to_string(), Json::Array(edges));
+    let mut document = BTreeMap::new();
+    document.insert("elements".to_string(), Json::Object(elements));
+    Json::Object(document)
+}
+
+fn csv(solution: &Solution, numbers: &NumberFormat) -> String {
+    let join = |values: &[f64]| values.iter().map(|&v| numbers.label(v)).collect::<Vec<_>>().join(";");
+    let mut text = String::from("step,operation,consumed,produced\n");
+    for (index, step) in solution.steps.iter().enumerate() {
+        text.push_str(&format!("{},{},{},{}\n", index + 1, step.operation.name(), join(&step.consumed), join(&step.produced)));
+    }
+    text
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// (column, row) grid cell of every node, laying the flow out left to right:
+// each node sits one column after the furthest node feeding it
+fn layout(graph: &FlowGraph) -> Vec<(usize, usize)> {
+    let mut column = vec![0; graph.nodes.len()];
+    for edge in &graph.edges {
+        // Edges are built in node order, so every source is placed before its targets
+        column[edge.to] = column[edge.to].max(column[edge.from] + 1);
+    }
+    let mut rows_used = vec![0; graph.nodes.len() + 1];
+    let mut cells = vec![(0, 0); graph.nodes.len()];
+    for node in &graph.nodes {
+        let col = column[node.id];
+        cells[node.id] = (col, rows_used[col]);
+        rows_used[col] += 1;
+    }
+    cells
+}
+
+fn svg(solution: &Solution, numbers: &NumberFormat) -> String {
+    const WIDTH: usize = 110;
+    const HEIGHT: usize = 36;
+    const COLUMN: usize = 170;
+    const ROW: usize = 60;
+
+    let graph = graph::build(solution);
+    let cells = layout(&graph);
+    let position: Vec<(usize, usize)> = cells.iter().map(|&(col, row)| (20 + col * COLUMN, 20 + row * ROW)).collect();
+    let width = 40 + (cells.iter().map(|cell| cell.0).max().unwrap_or(0) + 1) * COLUMN;
+    let height = 40 + (cells.iter().map(|cell| cell.1).max().unwrap_or(0) + 1) * ROW;
+
+    let mut text = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">\n", width, height);
+    for edge in &graph.edges {
+        let (x1, y1) = position[edge.from];
+        let (x2, y2) = position[edge.to];
+        let (x1, y1, x2, y2) = (x1 + WIDTH, y1 + HEIGHT / 2, x2, y2 + HEIGHT / 2);
+        text.push_str(&format!("  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#888\"/>\n", x1, y1, x2, y2));
+        text.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#555\">{}</text>\n",
+            (x1 + x2) / 2,
+            (y1 + y2) / 2 - 4,
+            numbers.label(edge.rate)
+        ));
+    }
+    for node in &graph.nodes {
+        let (x, y) = position[node.id];
+        let fill = match node.kind {
+            NodeKind::Input => "#dbeafe",
+            NodeKind::Operation(_) => "#f3f4f6",
+            NodeKind::Output => "#dcfce7",
+            NodeKind::Remainder => "#fef3c7",
+        };
+        text.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\" fill=\"{}\" stroke=\"#333\"/>\n",
+            x, y, WIDTH, HEIGHT, fill
+        ));
+        text.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+            x + WIDTH / 2,
+            y + HEIGHT / 2 + 4,
+            escape_html(&node.label)
+        ));
+    }
+    text.push_str("</svg>\n");
+    text
+}
+
+// A single self-contained page: problem summary, diagram, step table and (when known) search statistics
+pub fn html(solution: &Solution, stats: Option<&SearchStats>, numbers: &NumberFormat) -> String {
+    let list = |values: &[f64]| values.iter().map(|&v| numbers.label(v)).collect::<Vec<_>>().join(", ");
+    let mut text = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Split/merge plan</title>\n");
+    text.push_str("<style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px;text-align:left}</style>\n");
+    text.push_str("</head>\n<body>\n<h1>Split/merge plan</h1>\n");
+
+    text.push_str("<h2>Problem</h2>\n<table>\n");
+    text.push_str(&format!("<tr><th>Inputs</th><td>{}</td></tr>\n", list(&solution.inputs)));
+    text.push_str(&format!("<tr><th>Target</th><td>{}</td></tr>\n", numbers.label(solution.target)));
+    text.push_str(&format!("<tr><th>Outputs</th><td>{}</td></tr>\n", list(&solution.outputs)));
+    text.push_str(&format!("<tr><th>Remainder</th><td>{}</td></tr>\n", list(&solution.remainder)));
+    text.push_str(&format!("<tr><th>Steps</th><td>{}</td></tr>\n</table>\n", solution.steps.len()));
+
+    text.push_str("<h2>Diagram</h2>\n");
+    text.push_str(&svg(solution, numbers));
+
+    text.push_str("<h2>Steps</h2>\n<table>\n<tr><th>#</th><th>Operation</th><th>Consumed</th><th>Produced</th><th>Description</th></tr>\n");
+    for (index, step) in solution.steps.iter().enumerate() {
+        text.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            index + 1,
+            step.operation.name(),
+            list(&step.consumed),
+            list(&step.produced),
+            escape_html(&step.description)
+        ));
+    }
+    text.push_str("</table>\n");
+
+    if let Some(stats) = stats {
+        text.push_str("<h2>Search statistics</h2>\n<table>\n");
+        text.push_str(&format!("<tr><th>Nodes expanded</th><td>{}</td></tr>\n", stats.expanded));
+        text.push_str(&format!("<tr><th>Stopped at depth limit</th><td>{}</td></tr>\n", stats.depth_limited));
+        text.push_str(&format!("<tr><th>Rejected for capacity</th><td>{}</td></tr>\n", stats.capacity_rejected));
+        text.push_str(&format!("<tr><th>Timed out</th><td>{}</td></tr>\n", stats.timed_out));
+        text.push_str("</table>\n");
+    }
+    text.push_str("</body>\n</html>\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_outputs_show_no_negative_deviation() {
+        let solution = Solution { inputs: vec![60.0], target: 20.0, outputs: vec![19.9999999, 20.0], remainder: Vec::new(), steps: Vec::new(), penalty: 0.0 };
+        for numbers in [NumberFormat::default(), NumberFormat { decimals: Some(2), thousands: false }] {
+            let table = output_table(&solution, &numbers);
+            assert!(!table.contains("-0"), "{}", table);
+            assert_eq!(table.matches("+0.00%").count(), 2, "{}", table);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::hint::Hint;
+use crate::{search_solutions, SearchOptions, Solution, Step, Strategy, SCALE};
+
+// An existing plan brought back in line with inputs that drifted, with what it took
+#[derive(Clone, Debug)]
+pub struct Repair {
+    pub plan: Solution,
+    pub kept: usize,     // Old steps left exactly as they were
+    pub adjusted: usize, // Old steps left in place, carrying different rates
+    pub dropped: usize,  // Old steps taken out
+    pub added: usize,    // New steps
+}
+
+impl Repair {
+    // Devices that have to be built, removed or rebuilt: rate changes alone need none
+    pub fn modifications(&self) -> usize {
+        self.dropped + self.added
+    }
+}
+
+// A stream of the old plan: its rate then and now, and whether it came from a drifted input
+#[derive(Clone, Copy)]
+struct Stream {
+    old: f64,
+    new: f64,
+    tainted: bool,
+}
+
+// Take the first available stream whose old rate matches `rate`
+fn take(available: &mut Vec<Stream>, rate: f64) -> Option<Stream> {
+    let position = available.iter().position(|stream| (stream.old - rate).abs() <= 1.0 / SCALE)?;
+    Some(available.remove(position))
+}
+
+// Repair `old` for new `inputs`, listed in the same order as the old ones, changing as few devices as
+// possible. First the plan is replayed device for device with the new rates: if its outputs stay within
+// `can_be_off_by` and every rate within capacity, nothing needs rebuilding. Otherwise every step touched
+// by a drifted input is dropped, the untouched ones are kept, and the shortest completion from what they
+// leave is searched for. None if the old plan does not replay on its own inputs or nothing completes it
+pub fn repair(old: &Solution, inputs: Vec<f64>, can_be_off_by: f64, options: &SearchOptions) -> Option<Repair> {
+    if inputs.len() != old.inputs.len() {
+        return None;
+    }
+    let mut available: Vec<Stream> = old
+        .inputs
+        .iter()
+        .zip(&inputs)
+        .map(|(&old, &new)| Stream { old, new, tainted: (old - new).abs() > 1.0 / SCALE })
+        .collect();
+    let mut replayed = Vec::new();
+    let mut untouched = Vec::new();
+    for step in &old.steps {
+        let consumed = step.consumed.iter().map(|&rate| take(&mut available, rate)).collect::<Option<Vec<Stream>>>()?;
+        let tainted = consumed.iter().any(|stream| stream.tainted);
+        // Steps the drift does not reach stay exactly as saved
+        let moved = match tainted {
+            true => Step::new(step.operation, consumed.iter().map(|stream| stream.new).collect()),
+            false => step.clone(),
+        };
+        available.extend(step.produced.iter().zip(&moved.produced).map(|(&old, &new)| Stream { old, new, tainted }));
+        if !tainted {
+            untouched.push(step.clone());
+        }
+        replayed.push(moved);
+    }
+
+    // The same devices, carrying the new rates
+    let outputs = old.outputs.iter().map(|&rate| take(&mut available, rate)).collect::<Option<Vec<Stream>>>()?;
+    let capacity = options.max_capacity();
+    let within_capacity =
+        |step: &Step| capacity.is_none_or(|capacity| step.consumed.iter().chain(&step.produced).all(|&rate| rate <= capacity + 1.0 / SCALE));
+    let still_works = outputs.iter().all(|stream| (stream.new - old.target).abs() <= can_be_off_by + 1.0 / SCALE) && replayed.iter().all(within_capacity);
+    if still_works {
+        let adjusted = replayed.iter().zip(&old.steps).filter(|(new, old)| new.consumed != old.consumed).count();
+        let plan = Solution {
+            inputs,
+            outputs: outputs.iter().map(|stream| stream.new).collect(),
+            remainder: available.iter().map(|stream| stream.new).collect(),
+            steps: replayed,
+            ..old.clone()
+        };
+        return Some(Repair { plan, kept: old.steps.len() - adjusted, adjusted, dropped: 0, added: 0 });
+    }
+
+    // Keep what the drift did not reach and search, fewest operations first, for the rest
+    let hint = Hint { steps: untouched };
+    let start = hint.apply(&inputs).ok()?;
+    let mut options = options.clone();
+    options.strategy = Strategy::Shortest;
+    options.max_depth = options.max_depth.max(old.steps.len()).saturating_sub(hint.steps.len());
+    let (completions, _) = search_solutions(start, old.target, can_be_off_by, &options, 1);
+    let completion = completions.into_iter().next()?;
+    let added = completion.steps.len();
+    let kept = hint.steps.len();
+    Some(Repair { plan: hint.complete(inputs, completion), kept, adjusted: 0, dropped: old.steps.len() - kept, added })
+}
@@ -0,0 +1,458 @@
+use std::fs;
+
+use crate::error::Error;
+use crate::{GoalInfo, GoalPredicate, PrunePredicate, State};
+
+// Goal tests and pruning rules written in a small expression language and loaded at runtime, for
+// setups the command-line flags cannot describe. A script is a list of rules, one per line:
+//
+//     # Two outputs of 40, from plans that never hold more than six streams
+//     output = abs(x - 40) <= 0.5
+//     accept = outputs >= 2
+//     prune = streams > 6
+//
+// `output` is tested on every stream, with `x` its value; the streams it accepts are the outputs and
+// the rest is remainder. `accept` decides whether the state is a goal (by default, any output will do)
+// and `prune` drops states before they are expanded. Every rule may use `depth` (operations so far),
+// `streams` (how many values the state holds) and `total` (their sum); `accept` may also use `outputs`
+// and `output_total`. Values are numbers, with 0 for false: `+ - * /`, comparisons, `&& || !`,
+// `abs min max`, and `count sum any all` of a per-stream expression in `x`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    pub output: Option<Expr>,
+    pub accept: Option<Expr>,
+    pub prune: Option<Expr>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Variable(Variable),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variable {
+    X,
+    Depth,
+    Streams,
+    Total,
+    Outputs,
+    OutputTotal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Function {
+    Abs,
+    Min,
+    Max,
+    Count, // Streams the expression holds for
+    Sum,   // Of the streams the expression holds for
+    Any,
+    All,
+}
+
+// What a rule's variables stand for while it is evaluated
+struct Scope<'a> {
+    state: &'a State,
+    x: f64,
+    outputs: &'a [usize],
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Script, String> {
+        let mut script = Script { output: None, accept: None, prune: None };
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |message: String| format!("line {}: {}", number + 1, message);
+            let (name, body) = line.split_once('=').ok_or_else(|| at_line("expected \"rule = expression\"".to_string()))?;
+            let (slot, context) = match name.trim() {
+                "output" => (&mut script.output, Context::Stream),
+                "accept" => (&mut script.accept, Context::Accept),
+                "prune" => (&mut script.prune, Context::State),
+                other => return Err(at_line(format!("unknown rule \"{}\" (expected output, accept or prune)", other))),
+            };
+            if slot.is_some() {
+                return Err(at_line(format!("rule \"{}\" is defined twice", name.trim())));
+            }
+            *slot = Some(parse_expression(body, context).map_err(at_line)?);
+        }
+        if script.accept.is_some() && script.output.is_none() {
+            return Err("an accept rule needs an output rule choosing the outputs".to_string());
+        }
+        Ok(script)
+    }
+
+    pub fn load(path: &str) -> Result<Script, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        Script::parse(&text).map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+
+    // The goal the script describes, if it has an output rule
+    pub fn goal(&self) -> Option<GoalPredicate> {
+        let output = self.output.clone()?;
+        let accept = self.accept.clone();
+        Some(GoalPredicate::new(move |state: &State| {
+            let outputs: Vec<usize> = (0..state.values.len())
+                .filter(|&index| truthy(output.eval(&Scope { state, x: state.values[index], outputs: &[] })))
+                .collect();
+            let accepted = match &accept {
+                Some(accept) => truthy(accept.eval(&Scope { state, x: 0.0, outputs: &outputs })),
+                None => !outputs.is_empty(),
+            };
+            accepted.then_some(GoalInfo { outputs })
+        }))
+    }
+
+    pub fn pruning(&self) -> Option<PrunePredicate> {
+        let prune = self.prune.clone()?;
+        Some(PrunePredicate::new(move |state: &State| truthy(prune.eval(&Scope { state, x: 0.0, outputs: &[] }))))
+    }
+}
+
+fn truthy(value: f64) -> bool {
+    value != 0.0 && !value.is_nan()
+}
+
+fn boolean(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+impl Expr {
+    fn eval(&self, scope: &Scope) -> f64 {
+        match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(variable) => match variable {
+                Variable::X => scope.x,
+                Variable::Depth => scope.state.depth as f64,
+                Variable::Streams => scope.state.values.len() as f64,
+                Variable::Total => scope.state.values.iter().sum(),
+                Variable::Outputs => scope.outputs.len() as f64,
+                Variable::OutputTotal => scope.outputs.iter().map(|&index| scope.state.values[index]).sum(),
+            },
+            Expr::Not(operand) => boolean(!truthy(operand.eval(scope))),
+            Expr::Negate(operand) => -operand.eval(scope),
+            Expr::Binary(operator, left, right) => {
+                let left = left.eval(scope);
+                // Logical operators short-circuit
+                match operator {
+                    Operator::And => return boolean(truthy(left) && truthy(right.eval(scope))),
+                    Operator::Or => return boolean(truthy(left) || truthy(right.eval(scope))),
+                    _ => {}
+                }
+                let right = right.eval(scope);
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Less => boolean(left < right),
+                    Operator::LessOrEqual => boolean(left <= right),
+                    Operator::Greater => boolean(left > right),
+                    Operator::GreaterOrEqual => boolean(left >= right),
+                    Operator::Equal => boolean(left == right),
+                    Operator::NotEqual => boolean(left != right),
+                    Operator::And | Operator::Or => unreachable!(),
+                }
+            }
+            Expr::Call(function, arguments) => {
+                let per_stream = || {
+                    scope.state.values.iter().map(|&x| (x, truthy(arguments[0].eval(&Scope { state: scope.state, x, outputs: scope.outputs }))))
+                };
+                match function {
+                    Function::Abs => arguments[0].eval(scope).abs(),
+                    Function::Min => arguments[0].eval(scope).min(arguments[1].eval(scope)),
+                    Function::Max => arguments[0].eval(scope).max(arguments[1].eval(scope)),
+                    Function::Count => per_stream().filter(|&(_, holds)| holds).count() as f64,
+                    Function::Sum => per_stream().filter(|&(_, holds)| holds).map(|(x, _)| x).sum(),
+                    Function::Any => boolean(per_stream().any(|(_, holds)| holds)),
+                    Function::All => boolean(per_stream().all(|(_, holds)| holds)),
+                }
+            }
+        }
+    }
+}
+
+// Which variables a rule may refer to
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    Stream, // One stream: `x` is its value
+    State,
+    Accept, // The whole state, after its outputs are chosen
+}
+
+fn parse_expression(text: &str, context: Context) -> Result<Expr, String> {
+    let mut parser = Parser { bytes: text.as_bytes(), pos: 0, context };
+    let expr = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected characters"));
+    }
+    Ok(expr)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    context: Context,
+}
+
+// Binary operators from loosest to tightest binding, each level's spellings longest first
+const COMPARISONS: [(&str, Operator); 6] = [
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+];
+const SUMS: [(&str, Operator); 2] = [("+", Operator::Add), ("-", Operator::Subtract)];
+const PRODUCTS: [(&str, Operator); 2] = [("*", Operator::Multiply), ("/", Operator::Divide)];
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at column {}", message, self.pos + 1)
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.peek();
+        if self.bytes[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Binary(Operator::Or, Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.comparison()?;
+        while self.eat("&&") {
+            expr = Expr::Binary(Operator::And, Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let expr = self.sum()?;
+        match COMPARISONS.iter().find(|(token, _)| self.eat(token)) {
+            Some(&(_, operator)) => Ok(Expr::Binary(operator, Box::new(expr), Box::new(self.sum()?))),
+            None => Ok(expr),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(&(_, operator)) = SUMS.iter().find(|(token, _)| self.eat(token)) {
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while let Some(&(_, operator)) = PRODUCTS.iter().find(|(token, _)| self.eat(token)) {
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let expr = self.or()?;
+                if !self.eat(")") {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(expr)
+            }
+            Some(byte) if byte.is_ascii_digit() || byte == b'.' => {
+                let start = self.pos;
+                while self.pos < self.bytes.len() && (self.bytes[self.pos].is_ascii_digit() || self.bytes[self.pos] == b'.') {
+                    self.pos += 1;
+                }
+                let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("");
+                text.parse().map(Expr::Number).map_err(|_| self.error(&format!("invalid number \"{}\"", text)))
+            }
+            Some(byte) if byte.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self.pos < self.bytes.len() && (self.bytes[self.pos].is_ascii_alphanumeric() || self.bytes[self.pos] == b'_') {
+                    self.pos += 1;
+                }
+                let name = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("");
+                if self.peek() == Some(b'(') {
+                    self.call(name)
+                } else {
+                    self.variable(name)
+                }
+            }
+            Some(_) => Err(self.error("expected a number, name or '('")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> Result<Expr, String> {
+        let variable = match name {
+            "true" => return Ok(Expr::Number(1.0)),
+            "false" => return Ok(Expr::Number(0.0)),
+            "x" => Variable::X,
+            "depth" => Variable::Depth,
+            "streams" => Variable::Streams,
+            "total" => Variable::Total,
+            "outputs" => Variable::Outputs,
+            "output_total" => Variable::OutputTotal,
+            _ => return Err(self.error(&format!("unknown name \"{}\"", name))),
+        };
+        let allowed = match variable {
+            Variable::X => self.context == Context::Stream,
+            Variable::Outputs | Variable::OutputTotal => self.context == Context::Accept,
+            _ => true,
+        };
+        if !allowed {
+            return Err(self.error(&format!("\"{}\" is not available here", name)));
+        }
+        Ok(Expr::Variable(variable))
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let (function, arity) = match name {
+            "abs" => (Function::Abs, 1),
+            "min" => (Function::Min, 2),
+            "max" => (Function::Max, 2),
+            "count" => (Function::Count, 1),
+            "sum" => (Function::Sum, 1),
+            "any" => (Function::Any, 1),
+            "all" => (Function::All, 1),
+            _ => return Err(self.error(&format!("unknown function \"{}\"", name))),
+        };
+        self.pos += 1; // The '(' seen by `primary`
+        // Aggregates evaluate their argument once per stream
+        let outer = self.context;
+        if matches!(function, Function::Count | Function::Sum | Function::Any | Function::All) {
+            self.context = Context::Stream;
+        }
+        let mut arguments = vec![self.or()?];
+        while self.eat(",") {
+            arguments.push(self.or()?);
+        }
+        self.context = outer;
+        if !self.eat(")") {
+            return Err(self.error("expected ')'"));
+        }
+        if arguments.len() != arity {
+            return Err(self.error(&format!("{} takes {} argument(s), not {}", name, arity, arguments.len())));
+        }
+        Ok(Expr::Call(function, arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> f64 {
+        let expr = parse_expression(text, Context::State).unwrap_or_else(|message| panic!("{}: {}", text, message));
+        expr.eval(&Scope { state: &State { values: vec![10.0, 20.0, 30.0], depth: 2 }, x: 0.0, outputs: &[] })
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        assert_eq!(value("1 + 2 * 3"), 7.0);
+        assert_eq!(value("8 - 2 - 1"), 5.0);
+        assert_eq!(value("2 <= 2"), 1.0);
+        assert_eq!(value("2 < 2"), 0.0);
+        assert_eq!(value("3 >= 2 + 1"), 1.0);
+        assert_eq!(value("1 != 2"), 1.0);
+        assert_eq!(value("!1 == 0"), 1.0);
+        assert_eq!(value("!(1 == 0)"), 1.0);
+        assert_eq!(value("1 == 1 && 0 || 1"), 1.0);
+        assert_eq!(value("0 && 1 / 0"), 0.0);
+        assert_eq!(value("-depth + streams"), 1.0);
+        assert_eq!(value("count(x > 15) + sum(x < 25) + any(x == 30) + all(x > 10)"), 2.0 + 30.0 + 1.0);
+    }
+
+    #[test]
+    fn names_are_checked_against_their_rule() {
+        assert!(Script::parse("prune = x > 1").unwrap_err().contains("\"x\" is not available here"));
+        assert!(Script::parse("output = outputs > 1").unwrap_err().contains("\"outputs\" is not available here"));
+        assert!(Script::parse("prune = output_total > 1").is_err());
+        assert!(Script::parse("prune = count(x > 1) > 2").is_ok());
+        assert!(Script::parse("output = x > 1\naccept = outputs >= 2 && output_total > 50").is_ok());
+        assert!(Script::parse("accept = outputs >= 2").unwrap_err().contains("needs an output rule"));
+        assert!(Script::parse("prune = depth\nprune = streams").unwrap_err().starts_with("line 2:"));
+        assert!(Script::parse("goal = 1").unwrap_err().contains("unknown rule"));
+    }
+
+    #[test]
+    fn functions_take_their_arity() {
+        assert_eq!(value("min(4, max(1, 2))"), 2.0);
+        assert_eq!(value("abs(1 - 3)"), 2.0);
+        for text in ["abs(1, 2)", "min(1)", "count()", "sqrt(4)", "abs(1", "1 +", "2 3"] {
+            assert!(parse_expression(text, Context::State).is_err(), "{}", text);
+        }
+        assert!(parse_expression("min(1)", Context::State).unwrap_err().contains("min takes 2 argument(s), not 1"));
+    }
+
+    #[test]
+    fn rules_decide_goals_and_pruning() {
+        let script = Script::parse("# two outputs of 40\noutput = abs(x - 40) <= 0.5\naccept = outputs >= 2\nprune = streams > 3").unwrap();
+        let goal = script.goal().expect("an output rule makes a goal");
+        let prune = script.pruning().expect("a prune rule");
+        let state = State { values: vec![40.0, 20.0, 39.75], depth: 3 };
+        assert_eq!((goal.0)(&state), Some(GoalInfo { outputs: vec![0, 2] }));
+        assert!(!(prune.0)(&state));
+        let state = State { values: vec![40.0, 20.0, 10.0, 10.0], depth: 4 };
+        assert_eq!((goal.0)(&state), None);
+        assert!((prune.0)(&state));
+        // Without an accept rule any output will do
+        let goal = Script::parse("output = x == 20").unwrap().goal().unwrap();
+        assert_eq!((goal.0)(&State { values: vec![40.0, 20.0], depth: 1 }), Some(GoalInfo { outputs: vec![1] }));
+        assert!(Script::parse("prune = depth > 5").unwrap().goal().is_none());
+    }
+}
@@ -0,0 +1,1770 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use core::time::Duration;
+
+// Hash-based lookups with std; an ordered map keep the search usable with only `alloc`
+#[cfg(feature = "std")]
+type Visited<V> = std::collections::HashMap<StateKey<V>, usize, BuildStateHasher>;
+#[cfg(not(feature = "std"))]
+type Visited<V> = alloc::collections::BTreeMap<StateKey<V>, usize>;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::arena::{Arena, Slot, StateData};
+use crate::bucket_queue::BucketQueue;
+use crate::feasibility::{self, Infeasibility};
+use crate::rng::Rng;
+use crate::solution_set::{Objective, SolutionSet};
+#[cfg(feature = "std")]
+use crate::state_key::BuildStateHasher;
+use crate::state_key::{state_hash, value_hash, Packing, StateKey, StateValues};
+use crate::value::{Fixed, Value};
+
+#[derive(Clone, Debug)]
+struct Node {
+    link: usize,       // Index of the node's link: where its state is stored and how it was reached
+    hash: u64,         // Wrapping sum of the values' hashes, kept up to date as operations replace values
+    depth: usize,      // Track depth
+    estimated_cost: i64, // Used for priority in A* (difference from target)
+    tie_break: i64,      // Orders nodes of equal cost by preferred plan style
+}
+
+// Equality matches the priority ordering below
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+// Custom Ord for frontier priority based on estimated cost (A* heuristic)
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimated_cost.cmp(&self.estimated_cost) // Min-heap behavior
+            .then_with(|| other.tie_break.cmp(&self.tie_break))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub const SCALE: f64 = 1000.0;
+pub const MAX_DEPTH: usize = 6; // Reduced max depth to limit path expansion
+#[cfg(feature = "std")]
+const PAUSE_POLL: Duration = Duration::from_millis(20); // How often a paused search checks whether to go on
+const MEMORY_CHECK_INTERVAL: u64 = 1024; // Expansions between estimates of the search's memory use, with a memory limit
+const FALLBACK_SEEDS: usize = 64; // Best frontier nodes the memory-light continuation starts from
+const FALLBACK_TABLE_MIN: usize = 1 << 10; // Bounds on the slots of its table of recently seen states
+const FALLBACK_TABLE_MAX: usize = 1 << 20;
+const RANKING_CANDIDATES: usize = 16; // Extra plans weighed against each other when the shortest may not rank best
+#[cfg(feature = "parallel")]
+const PARALLEL_VALUES: usize = 12; // States with at least this many values have their successors evaluated across threads
+
+// Operations the search may apply to a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operation {
+    SplitTwo,
+    SplitThree,
+    CombineTwo,
+    CombineThree,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 4] = [Operation::SplitTwo, Operation::SplitThree, Operation::CombineTwo, Operation::CombineThree];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::SplitTwo => "split2",
+            Operation::SplitThree => "split3",
+            Operation::CombineTwo => "combine2",
+            Operation::CombineThree => "combine3",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Operation> {
+        Operation::ALL.into_iter().find(|operation| operation.name() == name)
+    }
+
+    // How many rates the operation consumes
+    pub fn arity(self) -> usize {
+        match self {
+            Operation::SplitTwo | Operation::SplitThree => 1,
+            Operation::CombineTwo => 2,
+            Operation::CombineThree => 3,
+        }
+    }
+}
+
+// One operation in a plan: the values it consumed and produced
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub operation: Operation,
+    pub consumed: Vec<f64>,
+    pub produced: Vec<f64>,
+    pub description: String, // Human-readable form, e.g. "60 -> [30, 30]"
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+// A plan reaching the target and what it leaves over
+#[derive(Clone, Debug, PartialEq)]
+pub struct Solution {
+    pub inputs: Vec<f64>,
+    pub target: f64,
+    pub outputs: Vec<f64>,
+    pub remainder: Vec<f64>,
+    pub steps: Vec<Step>,
+    pub penalty: f64, // Counted as extra steps: optional inputs the plan left untouched
+}
+
+impl Solution {
+    // Replay the plan from the inputs: every step must consume rates that are actually available and
+    // every output must be one of the final rates, within `precision`, and within `can_be_off_by` of the target
+    pub fn verify(&self, can_be_off_by: f64, precision: f64) -> bool {
+        self.remaining_after(&self.inputs, can_be_off_by, precision).is_some()
+    }
+
+    // The same plan run on different inputs, as long as every step still finds the rates it consumes and
+    // the outputs stay within `can_be_off_by`; only the remainder changes. A plan that never touched the
+    // changed inputs always carries over
+    pub fn rebased(&self, inputs: Vec<f64>, can_be_off_by: f64, precision: f64) -> Option<Solution> {
+        let remainder = self.remaining_after(&inputs, can_be_off_by, precision)?;
+        Some(Solution { inputs, remainder, ..self.clone() })
+    }
+
+    // Rates left over once the plan has run on `inputs` and the outputs are taken out
+    fn remaining_after(&self, inputs: &[f64], can_be_off_by: f64, precision: f64) -> Option<Vec<f64>> {
+        fn take(rates: &mut Vec<f64>, rate: f64, precision: f64) -> bool {
+            match rates.iter().position(|&available| (available - rate).abs() <= precision) {
+                Some(index) => {
+                    rates.remove(index);
+                    true
+                }
+                None => false,
+            }
+        }
+        let mut rates = inputs.to_vec();
+        for step in &self.steps {
+            if !step.consumed.iter().all(|&rate| take(&mut rates, rate, precision)) {
+                return None;
+            }
+            rates.extend_from_slice(&step.produced);
+        }
+        let matched = self.outputs.iter().all(|&output| (output - self.target).abs() <= can_be_off_by + precision && take(&mut rates, output, precision));
+        matched.then_some(rates)
+    }
+}
+
+// What is being moved: solid items on belts, or fluids through pipes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Medium {
+    Items,
+    Fluid,
+}
+
+// A belt (or lane of a belt, or pipe) and the rate it can carry
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeltTier {
+    pub name: String,
+    pub capacity: f64,
+}
+
+// Settings that shape the search: allowed operations and carrying capacities
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub operations: Vec<Operation>,
+    pub belt_tiers: Vec<BeltTier>, // Sorted by ascending capacity
+    pub medium: Medium,
+    pub units: Option<String>, // Rate units used when printing capacities
+    pub max_depth: usize,
+    pub output_count: Option<usize>, // Require exactly this many matching outputs and no remainder
+    pub min_outputs: usize,          // Require at least this many matching outputs
+    pub max_outputs: Option<usize>,  // Allow at most this many final streams, matched plus remainder
+    pub style: Style,
+    pub prefer: Preference, // Breaks ties left by the style in favour of one kind of operation
+    pub seed: Option<u64>, // Randomizes the order of equally ranked nodes, reproducibly
+    pub timeout: Option<Duration>, // Needs a clock, so only honoured with the std feature
+    pub yield_interval: Option<u64>, // Expansions between yielding the thread, so searches sharing a machine take turns; needs std
+    pub control: Option<SearchControl>, // Lets another thread cancel the search or watch its progress
+    pub events: Option<EventSink>,      // Receives every expansion, prune and goal as it happens
+    pub operation_limits: Vec<(Operation, usize)>, // Most times each listed operation may appear in a plan
+    pub footprints: Vec<(Operation, f64)>, // Floor area of each operation's device; unlisted ones take 1
+    pub max_footprint: Option<f64>,        // Total floor area the plan's devices may cover
+    pub separate_inputs: bool,             // Never merge flows that came from different inputs
+    pub max_chain: Option<usize>,          // Most operations any one flow may pass through
+    pub costs: Vec<(Operation, f64)>,      // Cost of each operation's device; unlisted ones cost 1
+    pub max_cost: Option<f64>,             // Total cost the plan's devices may add up to
+    pub max_value: Option<f64>,            // No intermediate value may exceed this, whatever the belt tiers
+    pub forbidden: Vec<(f64, f64)>,        // Inclusive ranges no operation may produce a value in; (v, v) forbids v alone
+    pub input_capacities: Vec<(usize, f64)>, // (input index, capacity) for inputs on a smaller belt or pipe
+    pub use_all_inputs: bool,              // Every input must be used, not passed straight to the remainder
+    pub optional_inputs: Vec<(usize, f64)>, // (input index, penalty in steps) for inputs a plan may leave untouched
+    pub dedup_epsilon: Option<f64>,        // Treat states whose values all agree to this precision as duplicates
+    pub profile_internal: bool,            // Time each part of the search into `SearchStats::timings`; needs std
+    pub goal: Option<GoalPredicate>,       // Replaces the target and output-count checks when set
+    pub objectives: Vec<Objective>,        // Optimized in order, each breaking ties of the one before; then steps, deviation
+    pub best_effort: bool,                 // With no plan within tolerance, return the one coming closest instead
+    pub keep_ties: bool,                   // Also expand states reached again at the same depth, so no equally good plan is lost
+    pub prune: Option<PrunePredicate>,     // Drops the states it accepts before they are expanded
+    pub memory_limit: Option<usize>,       // Bytes of search state past which it goes on depth-first, without a visited set
+    pub strategy: Strategy,                // How the frontier is ordered, without an output count to meet
+}
+
+// How the search orders its frontier. With `output_count` set it always goes fewest operations first
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    // Nearest the target first: quick to find a plan, not always the shortest
+    #[default]
+    Greedy,
+    // A*: fewest operations first, nearest the target among equals
+    Shortest,
+    // Deviation plus `step_cost` (a rate) per operation: 0 is greedy, a large cost approaches shortest
+    Weighted { step_cost: f64 },
+    // Greedy, but at most `width` states are queued at each depth. Parents are expanded best first,
+    // so it is their successors that fill the beam
+    Beam { width: usize },
+}
+
+impl Strategy {
+    // "greedy", "shortest" (or "astar"), "weighted:COST" or "beam:WIDTH"
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        let (kind, setting) = name.split_once(':').unwrap_or((name, ""));
+        match (kind, setting) {
+            ("greedy", "") => Some(Strategy::Greedy),
+            ("shortest" | "astar", "") => Some(Strategy::Shortest),
+            ("weighted", cost) => cost.parse().ok().filter(|&cost: &f64| cost >= 0.0).map(|step_cost| Strategy::Weighted { step_cost }),
+            ("beam", width) => width.parse().ok().filter(|&width| width > 0).map(|width| Strategy::Beam { width }),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Strategy::Greedy => "greedy".to_string(),
+            Strategy::Shortest => "shortest".to_string(),
+            Strategy::Weighted { step_cost } => format!("weighted:{}", step_cost),
+            Strategy::Beam { width } => format!("beam:{}", width),
+        }
+    }
+}
+
+// Why a state was not explored further
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PruneReason {
+    DepthLimit,
+    Capacity,
+    Duplicate,
+    Custom, // Rejected by `SearchOptions::prune`
+    Beam,   // Its depth's share of the beam was already taken
+    Forbidden, // Produced a value in one of `SearchOptions::forbidden`
+}
+
+// Something the search did, with the state encoded as its scaled integer values
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchEvent {
+    Expanded { depth: usize, values: Vec<i64> },
+    // A successor queued with its priority (f) and distance-from-target heuristic (h); g is the depth
+    Generated { parent: Vec<i64>, depth: usize, values: Vec<i64>, priority: i64, heuristic: i64 },
+    Pruned { depth: usize, values: Vec<i64>, reason: PruneReason },
+    Goal { depth: usize, values: Vec<i64> },
+}
+
+// Callback receiving search events; cheap to clone into options
+#[derive(Clone)]
+pub struct EventSink(pub Arc<dyn Fn(&SearchEvent) + Send + Sync>);
+
+impl fmt::Debug for EventSink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventSink")
+    }
+}
+
+impl EventSink {
+    fn emit(sink: &Option<EventSink>, event: impl FnOnce() -> SearchEvent) {
+        if let Some(EventSink(callback)) = sink {
+            callback(&event());
+        }
+    }
+}
+
+// A state offered to a custom goal predicate, with its rates back in floating point
+#[derive(Clone, Debug, PartialEq)]
+pub struct State {
+    pub values: Vec<f64>,
+    pub depth: usize, // Operations applied so far
+}
+
+// What a custom goal predicate accepted: indices into `State::values` of the streams that count as
+// outputs. Every other value becomes remainder
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoalInfo {
+    pub outputs: Vec<usize>,
+}
+
+// Custom success condition replacing the target check, e.g. two outputs of 120 and one at most 30.
+// The target still guides which states are explored first, so a goal far from it may take long to reach
+#[derive(Clone)]
+pub struct GoalPredicate(pub Arc<GoalFn>);
+
+pub type GoalFn = dyn Fn(&State) -> Option<GoalInfo> + Send + Sync;
+
+impl fmt::Debug for GoalPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GoalPredicate")
+    }
+}
+
+// Custom pruning rule: states it returns true for are dropped instead of expanded, e.g. anything with
+// more than six streams. A rule that drops a goal's ancestors loses that goal
+#[derive(Clone)]
+pub struct PrunePredicate(pub Arc<PruneFn>);
+
+pub type PruneFn = dyn Fn(&State) -> bool + Send + Sync;
+
+impl fmt::Debug for PrunePredicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PrunePredicate")
+    }
+}
+
+impl PrunePredicate {
+    pub fn new(predicate: impl Fn(&State) -> bool + Send + Sync + 'static) -> PrunePredicate {
+        PrunePredicate(Arc::new(predicate))
+    }
+}
+
+impl GoalPredicate {
+    pub fn new(predicate: impl Fn(&State) -> Option<GoalInfo> + Send + Sync + 'static) -> GoalPredicate {
+        GoalPredicate(Arc::new(predicate))
+    }
+
+    // A total demand met by however many streams it takes, each between `low` and `high`: the output
+    // count is left to the search. Outputs are the in-range streams whose sum comes closest to `total`,
+    // fewest streams first on a tie. Found as a subset sum over thousandths, keeping the fewest streams
+    // per sum up to `total + can_be_off_by`, so the work grows with the distinct sums, not 2^streams
+    pub fn demand(total: f64, can_be_off_by: f64, low: f64, high: f64) -> GoalPredicate {
+        let thousandths = |value: f64| round(value * SCALE);
+        let (total, margin) = (thousandths(total), thousandths(can_be_off_by));
+        GoalPredicate::new(move |state: &State| {
+            let mut fewest: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+            fewest.insert(0, Vec::new());
+            for (index, &value) in state.values.iter().enumerate().filter(|&(_, value)| (low..=high).contains(value)) {
+                let step = thousandths(value);
+                let extended: Vec<(i64, Vec<usize>)> = fewest
+                    .iter()
+                    .filter(|&(&sum, _)| sum + step <= total + margin)
+                    .map(|(&sum, streams)| (sum + step, streams.iter().copied().chain([index]).collect()))
+                    .collect();
+                for (sum, streams) in extended {
+                    if fewest.get(&sum).is_none_or(|known| streams.len() < known.len()) {
+                        fewest.insert(sum, streams);
+                    }
+                }
+            }
+            fewest
+                .into_iter()
+                .filter(|(sum, streams)| !streams.is_empty() && (sum - total).abs() <= margin)
+                .min_by_key(|(sum, streams)| ((sum - total).abs(), streams.len()))
+                .map(|(_, outputs)| GoalInfo { outputs })
+        })
+    }
+
+    // Split a state into outputs and remainder the way `find_final_and_remainder` does
+    fn check<V: Value>(&self, values: &[V], depth: usize) -> Option<(Vec<V>, Vec<V>)> {
+        let state = State { values: values.iter().map(|value| value.to_f64()).collect(), depth };
+        let GoalInfo { outputs } = (self.0)(&state)?;
+        if outputs.is_empty() || outputs.iter().any(|&index| index >= values.len()) {
+            return None;
+        }
+        let (mut final_values, mut remainder) = (Vec::new(), Vec::new());
+        for (index, &value) in values.iter().enumerate() {
+            if outputs.contains(&index) {
+                final_values.push(value);
+            } else {
+                remainder.push(value);
+            }
+        }
+        Some((final_values, remainder))
+    }
+}
+
+// Shared handle for cancelling a running search and reading its progress from elsewhere
+#[derive(Clone, Debug, Default)]
+pub struct SearchControl {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    expanded: Arc<AtomicU64>,
+    frontier: Arc<AtomicU64>,
+    best_deviation: Arc<AtomicU64>, // Bits of the negated deviation, so zero means none seen yet
+}
+
+impl SearchControl {
+    pub fn new() -> SearchControl {
+        SearchControl::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(AtomicOrdering::Relaxed)
+    }
+
+    // Hold the search before its next expansion until resumed (or cancelled)
+    pub fn pause(&self) {
+        self.paused.store(true, AtomicOrdering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, AtomicOrdering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(AtomicOrdering::Relaxed)
+    }
+
+    // States expanded so far by the search using this control
+    pub fn expanded(&self) -> u64 {
+        self.expanded.load(AtomicOrdering::Relaxed)
+    }
+
+    // States waiting in the queue
+    pub fn frontier(&self) -> u64 {
+        self.frontier.load(AtomicOrdering::Relaxed)
+    }
+
+    // Smallest distance from the target any value has reached so far
+    pub fn best_deviation(&self) -> Option<f64> {
+        match self.best_deviation.load(AtomicOrdering::Relaxed) {
+            0 => None,
+            bits => Some(-f64::from_bits(bits)),
+        }
+    }
+}
+
+impl SearchOptions {
+    // What one device performing `operation` costs
+    pub fn cost(&self, operation: Operation) -> f64 {
+        self.costs.iter().find(|&&(declared, _)| declared == operation).map_or(1.0, |&(_, cost)| cost)
+    }
+
+    // Floor area one device performing `operation` covers
+    pub fn footprint(&self, operation: Operation) -> f64 {
+        self.footprints.iter().find(|&&(declared, _)| declared == operation).map_or(1.0, |&(_, area)| area)
+    }
+
+    // Largest rate any single value may reach, if belt tiers or a value ceiling are in play
+    pub fn max_capacity(&self) -> Option<f64> {
+        let tiers = self.belt_tiers.iter().map(|tier| tier.capacity).reduce(f64::max);
+        match (tiers, self.max_value) {
+            (Some(capacity), Some(ceiling)) => Some(capacity.min(ceiling)),
+            (capacity, ceiling) => capacity.or(ceiling),
+        }
+    }
+
+    // Capacity of the stream feeding input `index`, which every value mostly derived from it must respect
+    pub fn input_capacity(&self, index: usize) -> Option<f64> {
+        self.input_capacities.iter().find(|&&(input, _)| input == index).map(|&(_, capacity)| capacity)
+    }
+
+    // What leaving input `index` untouched costs, if it is optional
+    pub fn optional_penalty(&self, index: usize) -> Option<f64> {
+        self.optional_inputs.iter().find(|&&(input, _)| input == index).map(|&(_, penalty)| penalty)
+    }
+
+    // Smallest belt tier that can carry the given rate
+    pub fn belt_for(&self, value: f64) -> Option<&BeltTier> {
+        self.belt_tiers.iter().find(|tier| value <= tier.capacity)
+    }
+
+    // Name the physical device performing a step: fluids split and merge through junctions
+    pub fn label_step(&self, mut step: Step) -> Step {
+        if self.medium == Medium::Fluid {
+            step.description = format!("junction: {}", step.description);
+        }
+        step
+    }
+
+    // Render a rate with the configured units, if any
+    pub fn format_rate(&self, rate: f64) -> String {
+        match &self.units {
+            Some(units) => format!("{} {}", rate, units),
+            None => rate.to_string(),
+        }
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            operations: vec![Operation::SplitTwo, Operation::SplitThree, Operation::CombineTwo, Operation::CombineThree],
+            belt_tiers: vec![],
+            medium: Medium::Items,
+            units: None,
+            max_depth: MAX_DEPTH,
+            output_count: None,
+            min_outputs: 1,
+            max_outputs: None,
+            style: Style::Any,
+            prefer: Preference::None,
+            seed: None,
+            timeout: None,
+            yield_interval: None,
+            control: None,
+            events: None,
+            operation_limits: vec![],
+            footprints: vec![],
+            max_footprint: None,
+            separate_inputs: false,
+            max_chain: None,
+            costs: vec![],
+            max_cost: None,
+            max_value: None,
+            forbidden: vec![],
+            input_capacities: vec![],
+            use_all_inputs: false,
+            optional_inputs: vec![],
+            dedup_epsilon: None,
+            profile_internal: false,
+            goal: None,
+            objectives: vec![],
+            best_effort: false,
+            keep_ties: false,
+            prune: None,
+            memory_limit: None,
+            strategy: Strategy::Greedy,
+        }
+    }
+}
+
+// Layout preferred when several plans are equally good
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Any,
+    Manifold, // Sequential taps: long chains of operations
+    Balanced, // Balanced tree: every flow passes through as few operations as possible
+}
+
+impl Style {
+    pub fn from_name(name: &str) -> Option<Style> {
+        match name {
+            "any" => Some(Style::Any),
+            "manifold" => Some(Style::Manifold),
+            "balanced" => Some(Style::Balanced),
+            _ => None,
+        }
+    }
+
+    // Lower is preferred; based on the longest chain of operations any value has passed through
+    pub fn tie_break(self, chains: &[usize]) -> i64 {
+        let longest = chains.iter().copied().max().unwrap_or(0) as i64;
+        match self {
+            Style::Any => 0,
+            Style::Manifold => -longest,
+            Style::Balanced => longest,
+        }
+    }
+}
+
+// Kind of operation tried first among equally ranked nodes, for problems known to be mostly
+// splitting or mostly merging
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Preference {
+    #[default]
+    None,
+    Splits,
+    Combines,
+}
+
+impl Preference {
+    pub fn from_name(name: &str) -> Option<Preference> {
+        match name {
+            "none" => Some(Preference::None),
+            "splits" => Some(Preference::Splits),
+            "combines" => Some(Preference::Combines),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preference::None => "none",
+            Preference::Splits => "splits",
+            Preference::Combines => "combines",
+        }
+    }
+
+    // Lower is preferred: 0 for an operation of the preferred kind, 1 otherwise
+    pub fn tie_break(self, operation: Operation) -> i64 {
+        let split = matches!(operation, Operation::SplitTwo | Operation::SplitThree);
+        match self {
+            Preference::None => 0,
+            Preference::Splits => i64::from(!split),
+            Preference::Combines => i64::from(split),
+        }
+    }
+}
+
+// Game presets restricting the search to buildable operations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    Default,
+    Factorio,
+    Pipes,
+}
+
+impl Profile {
+    pub fn from_name(name: &str) -> Option<Profile> {
+        match name {
+            "default" => Some(Profile::Default),
+            "factorio" => Some(Profile::Factorio),
+            "pipes" => Some(Profile::Pipes),
+            _ => None,
+        }
+    }
+
+    pub fn search_options(self) -> SearchOptions {
+        match self {
+            Profile::Default => SearchOptions::default(),
+            Profile::Factorio => {
+                // Splitters are 2-way only; each belt lane carries half the belt
+                let belts = [("transport belt", 15.0), ("fast transport belt", 30.0), ("express transport belt", 45.0)];
+                let mut belt_tiers: Vec<BeltTier> = belts
+                    .iter()
+                    .flat_map(|&(name, capacity)| {
+                        vec![
+                            BeltTier { name: format!("lane of {}", name), capacity: capacity / 2.0 },
+                            BeltTier { name: name.to_string(), capacity },
+                        ]
+                    })
+                    .collect();
+                belt_tiers.sort_by(|a, b| a.capacity.total_cmp(&b.capacity));
+                SearchOptions {
+                    operations: vec![Operation::SplitTwo, Operation::CombineTwo],
+                    belt_tiers,
+                    medium: Medium::Items,
+                    units: Some("items/s".to_string()),
+                    footprints: vec![(Operation::SplitTwo, 2.0), (Operation::CombineTwo, 2.0)], // Splitters are 1x2 tiles
+                    ..SearchOptions::default()
+                }
+            }
+            Profile::Pipes => {
+                // Junctions have four ports, so one flow splits up to three ways or three flows merge into one
+                SearchOptions {
+                    operations: vec![Operation::SplitTwo, Operation::SplitThree, Operation::CombineTwo, Operation::CombineThree],
+                    belt_tiers: vec![
+                        BeltTier { name: "Pipeline Mk.1".to_string(), capacity: 300.0 },
+                        BeltTier { name: "Pipeline Mk.2".to_string(), capacity: 600.0 },
+                    ],
+                    medium: Medium::Fluid,
+                    units: Some("m³/min".to_string()),
+                    ..SearchOptions::default()
+                }
+            }
+        }
+    }
+}
+
+// Identity of a state for duplicate detection: its values, plus whatever about their history
+// (inputs they came from, operations they passed through, whose capacity binds them) limits what may follow
+fn state_key<V: Value>(options: &SearchOptions, packing: Option<Packing>, hash: u64, state: &StateData<V>) -> StateKey<V> {
+
+    let mut history = vec![];
+    if options.separate_inputs {
+        history.extend_from_slice(&state.origins);
+    }
+    if options.max_chain.is_some() {
+        history.extend(state.chains.iter().map(|&chain| chain as u64));
+    }
+    if !options.input_capacities.is_empty() {
+        history.extend(state.sources.iter().map(|&source| source as u64));
+    }
+    // Near-identical states share a key: values are rounded to the nearest multiple of epsilon and rehashed
+    if let Some(epsilon) = options.dedup_epsilon {
+        let quantized: Vec<i64> = state.values.iter().map(|&v| round(v.to_f64() / epsilon)).collect();
+        return StateKey { hash: state_hash(&quantized), values: StateValues::Quantized(quantized), history };
+    }
+    StateKey { hash, values: Packing::pack(packing, &state.values), history }
+}
+
+// Nearest integer, halves away from zero; `f64::round` needs std
+fn round(value: f64) -> i64 {
+    if value < 0.0 { (value - 0.5) as i64 } else { (value + 0.5) as i64 }
+}
+
+// Compact encoding of a state for search events: each value in thousandths
+fn encode<V: Value>(values: &[V]) -> Vec<i64> {
+    values.iter().map(|&v| v.cost()).collect()
+}
+
+// New function for calculating heuristic based on proximity to target
+fn calculate_heuristic<V: Value>(values: &[V], target: V) -> i64 {
+    values.iter().map(|&v| v.abs_diff(target).cost()).sum()
+}
+
+// Priority of a state: closeness to target, or for fixed output counts the fewest operations still possible
+fn estimate_cost<V: Value>(values: &[V], depth: usize, target: V, margin: V, options: &SearchOptions) -> i64 {
+    let deviation = calculate_heuristic(values, target);
+    match options.output_count {
+        Some(count) => {
+            // Each operation adds at most two values and touches at most three, so this never overestimates
+            let missing = count.saturating_sub(values.len());
+            let mismatched = values.iter().filter(|&&v| v.abs_diff(target) > margin).count();
+            let remaining = missing.div_ceil(2).max(mismatched.div_ceil(3));
+            ((depth + remaining) as i64) * (1 << 40) + deviation
+        }
+        None => match options.strategy {
+            Strategy::Greedy | Strategy::Beam { .. } => deviation,
+            Strategy::Shortest => (depth as i64) * (1 << 40) + deviation,
+            Strategy::Weighted { step_cost } => deviation + (depth as f64 * step_cost * SCALE) as i64,
+        },
+    }
+}
+
+// Operation to split a value into two equal parts
+fn split_into_two(input: f64) -> (f64, f64) {
+    (input / 2.0, input / 2.0)
+}
+
+// Operation to split a value into three equal parts
+fn split_into_three(input: f64) -> (f64, f64, f64) {
+    let part = input / 3.0;
+    (part, part, part)
+}
+
+// Operation to combine two values by summing them
+fn combine_two(a: f64, b: f64) -> f64 {
+    a + b
+}
+
+// Operation to combine three values by summing them
+fn combine_three(a: f64, b: f64, c: f64) -> f64 {
+    a + b + c
+}
+
+// How a node was reached. Plans are rebuilt from these only for goals, rather than every node
+// carrying a copy of its whole path
+struct Link<V> {
+    parent: Option<usize>, // Parent's link; `None` for the start
+    slot: Slot,            // The node's state in the arena
+    reached_by: Option<Move<V>>,
+}
+
+// Operations on the way to a node, last first
+fn operations_to<V>(links: &[Link<V>], link: usize) -> impl Iterator<Item = Operation> + '_ {
+    let mut next = Some(link);
+    core::iter::from_fn(move || {
+        let link = &links[next?];
+        next = link.parent;
+        link.reached_by.as_ref().map(|candidate| candidate.operation)
+    })
+}
+
+// The steps leading from the inputs to a node, replayed against each parent's stored state
+fn plan_to<V: Value>(links: &[Link<V>], arena: &Arena<V>, options: &SearchOptions, link: usize) -> Vec<Step> {
+    let mut chain = vec![link];
+    while let Some(parent) = links[*chain.last().unwrap()].parent {
+        chain.push(parent);
+    }
+    let mut parent_state = StateData::new();
+    let mut steps = Vec::new();
+    for pair in chain.windows(2).rev() {
+        arena.load(links[pair[1]].slot, &mut parent_state);
+        if let Some(candidate) = &links[pair[0]].reached_by {
+            steps.push(options.label_step(candidate.step(&parent_state.values)));
+        }
+    }
+    steps
+}
+
+// One operation applied to a state, kept as indices until the search decides to build it
+#[derive(Clone, Copy)]
+struct Move<V> {
+    operation: Operation,
+    consumed: [usize; 3], // Ascending indices, the first `count` of them used
+    count: usize,
+    part: V,      // Each produced value
+    parts: usize, // How many values are produced
+}
+
+impl<V: Value> Move<V> {
+    fn consumed(&self) -> &[usize] {
+        &self.consumed[..self.count]
+    }
+
+    // Write the resulting state into `next`: the consumed values are replaced by the produced ones, which
+    // extend the longest consumed chain by one and carry over the inputs they came from. Returns the
+    // state's hash and whether the operation merged flows from different inputs
+    fn apply(&self, state: &StateData<V>, hash: u64, next: &mut StateData<V>) -> (u64, bool) {
+        let consumed = self.consumed();
+        let chain = consumed.iter().map(|&index| state.chains[index]).max().unwrap_or(0) + 1;
+        let origin = consumed.iter().fold(0, |bits, &index| bits | state.origins[index]);
+        let mixes_inputs = consumed.iter().any(|&index| state.origins[index] != origin);
+        // A merge is mostly made of its largest part, so it takes that part's source
+        let source = consumed.iter().rev().max_by_key(|&&index| state.values[index]).map_or(0, |&index| state.sources[index]);
+        let mut hash = hash;
+        next.clear();
+        for index in 0..state.len() {
+            if consumed.contains(&index) {
+                hash = hash.wrapping_sub(value_hash(&state.values[index]));
+            } else {
+                next.push(state.values[index], state.chains[index], state.origins[index], state.sources[index]);
+            }
+        }
+        for _ in 0..self.parts {
+            hash = hash.wrapping_add(value_hash(&self.part));
+            next.push(self.part, chain, origin, source);
+        }
+        (hash, mixes_inputs)
+    }
+
+    // The step as a plan reports it, with rates shown in floating point
+    fn step(&self, values: &[V]) -> Step {
+        Step::new(self.operation, self.consumed().iter().map(|&index| values[index].to_f64()).collect())
+    }
+}
+
+impl Step {
+    // `operation` applied to `consumed`, which must hold as many rates as the operation takes
+    pub fn new(operation: Operation, consumed: Vec<f64>) -> Step {
+        let (produced, description) = match operation {
+            Operation::SplitTwo => {
+                let (part1, part2) = split_into_two(consumed[0]);
+                (vec![part1, part2], format!("{} -> [{}, {}]", consumed[0], part1, part2))
+            }
+            Operation::SplitThree => {
+                let (part1, part2, part3) = split_into_three(consumed[0]);
+                (vec![part1, part2, part3], format!("{} -> [{}, {}, {}]", consumed[0], part1, part2, part3))
+            }
+            Operation::CombineTwo => {
+                let combined = combine_two(consumed[0], consumed[1]);
+                (vec![combined], format!("{} + {} -> {}", consumed[0], consumed[1], combined))
+            }
+            Operation::CombineThree => {
+                let combined = combine_three(consumed[0], consumed[1], consumed[2]);
+                (vec![combined], format!("{} + {} + {} -> {}", consumed[0], consumed[1], consumed[2], combined))
+            }
+        };
+        Step { operation, consumed, produced, description }
+    }
+}
+
+// Operation sets as bits in `Operation::ALL` order, for picking a search kernel at compile time
+const ALL_OPERATIONS: u8 = 0b1111;
+
+// Every way to apply the allowed operations to the value at index `i`, operation by operation. The
+// default set takes a kernel monomorphized for it, where the per-operation dispatch folds away
+fn collect_moves<V: Value>(operations: &[Operation], values: &[V], i: usize, moves: &mut Vec<Move<V>>) {
+    if operations == Operation::ALL {
+        collect_moves_in::<ALL_OPERATIONS, V>(values, i, moves);
+    } else {
+        moves.clear();
+        for &operation in operations {
+            push_moves(operation, values, i, moves);
+        }
+    }
+}
+
+fn collect_moves_in<const OPERATIONS: u8, V: Value>(values: &[V], i: usize, moves: &mut Vec<Move<V>>) {
+    moves.clear();
+    for (bit, operation) in Operation::ALL.into_iter().enumerate() {
+        if OPERATIONS & (1 << bit) != 0 {
+            push_moves(operation, values, i, moves);
+        }
+    }
+}
+
+// Every way to apply one operation to the value at index `i`
+#[inline(always)]
+fn push_moves<V: Value>(operation: Operation, values: &[V], i: usize, moves: &mut Vec<Move<V>>) {
+    let value = values[i];
+
+    match operation {
+        Operation::SplitTwo => {
+            moves.push(Move { operation, consumed: [i, 0, 0], count: 1, part: value.divide(2), parts: 2 });
+        }
+        Operation::SplitThree => {
+            moves.push(Move { operation, consumed: [i, 0, 0], count: 1, part: value.divide(3), parts: 3 });
+        }
+        Operation::CombineTwo => {
+            for (j, &other_value) in values.iter().enumerate().skip(i + 1) {
+                let merged = V::sum(&[value, other_value]);
+                moves.push(Move { operation, consumed: [i, j, 0], count: 2, part: merged, parts: 1 });
+            }
+        }
+        Operation::CombineThree => {
+            for j in (i+1)..values.len() {
+                for k in (j+1)..values.len() {
+                    let merged = V::sum(&[value, values[j], values[k]]);
+                    moves.push(Move { operation, consumed: [i, j, k], count: 3, part: merged, parts: 1 });
+                }
+            }
+        }
+    }
+}
+
+// What the search ran into, used to explain a failure
+#[derive(Clone, Debug, Default)]
+pub struct SearchStats {
+    pub expanded: u64,
+    pub depth_limited: u64,     // Nodes not expanded because they reached max depth
+    pub capacity_rejected: u64, // Successors dropped for exceeding the belt/pipe capacity
+    pub limit_rejected: u64,    // Operations not tried because the plan already used them up to their limit
+    pub footprint_rejected: u64, // Operations not tried because their device would not fit the floor space
+    pub cost_rejected: u64,     // Operations not tried because their device would exceed the cost budget
+    pub mixing_rejected: u64,   // Merges dropped for mixing flows from different inputs
+    pub chain_rejected: u64,    // Successors dropped for passing a flow through too many operations
+    pub custom_pruned: u64,     // Nodes not expanded because the custom pruning rule rejected them
+    pub beam_pruned: u64,       // Successors dropped because their depth's beam was full
+    pub forbidden_rejected: u64, // Successors dropped for producing a forbidden value
+    pub unused_input_rejected: u64, // Goals dropped for passing an input straight to the remainder (with use_all_inputs)
+    pub outputs_rejected: u64,  // Goals dropped for ending in more streams than max_outputs
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub unverified: u64,        // Goals dropped because their plan did not replay exactly (with --dedup-epsilon)
+    pub closest: Option<(f64, f64, usize)>, // (value, deviation from target, depth) of the nearest value seen
+    pub timings: Timings,       // Only filled in with `SearchOptions::profile_internal`
+    pub infeasible: Option<Infeasibility>, // Why the search was skipped, when the pre-check proved it hopeless
+    pub fallback_at: Option<u64>, // Nodes expanded when the memory limit moved the search to its memory-light continuation
+}
+
+// Time spent in each part of the search, to localize a slowdown without an external profiler
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timings {
+    pub generation: Duration,  // Building successors and checking them against the constraints
+    pub heuristics: Duration,  // Estimating the cost of open successors
+    pub hashing: Duration,     // Building state keys and looking them up in the visited set
+    pub heap: Duration,        // Pushing to and popping from the frontier
+    pub goal_checks: Duration, // Testing popped states against the target
+    pub total: Duration,       // Everything, including what the parts above leave out
+}
+
+// Times one stretch of the search when profiling; without a clock it measures nothing
+struct Stopwatch {
+    #[cfg(feature = "std")]
+    started: Option<Instant>,
+}
+
+impl Stopwatch {
+    fn start(enabled: bool) -> Stopwatch {
+        #[cfg(feature = "std")]
+        return Stopwatch { started: enabled.then(Instant::now) };
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = enabled;
+            Stopwatch {}
+        }
+    }
+
+    fn stop(self, into: &mut Duration) {
+        #[cfg(feature = "std")]
+        if let Some(started) = self.started {
+            *into += started.elapsed();
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = into;
+    }
+}
+
+// Main A* function to find the shortest path to target
+pub fn shortest_path_to_target(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) -> Option<Solution> {
+    search_solutions(inputs, target, can_be_off_by, options, 1).0.into_iter().next()
+}
+
+// Continue the A* search past the first goal until `limit` structurally distinct plans are found,
+// returned best first (fewest steps, then smallest deviation from target)
+pub fn search_solutions(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions, limit: usize) -> (SolutionSet, SearchStats) {
+    search_solutions_in::<Fixed>(inputs, target, can_be_off_by, options, limit)
+}
+
+// `search_solutions` in a chosen numeric representation, e.g. `Rational` for exact thirds
+pub fn search_solutions_in<V: Value>(
+    inputs: Vec<f64>,
+    target: f64,
+    can_be_off_by: f64,
+    options: &SearchOptions,
+    limit: usize,
+) -> (SolutionSet, SearchStats) {
+    // A plan skipping an optional input may rank below a longer one found later that taps it, and one
+    // tied on the primary objective may lose on the next, so look further
+    let wanted = if options.optional_inputs.is_empty() && options.objectives.is_empty() { limit } else { limit + RANKING_CANDIDATES };
+    if let Some(proof) = feasibility::precheck(&inputs, target, can_be_off_by, options) {
+        return (SolutionSet::new(), SearchStats { infeasible: Some(proof), ..SearchStats::default() });
+    }
+    let mut search = Search::<V>::new(inputs, target, can_be_off_by, options);
+    while search.found().len() < wanted && search.next_solution().is_some() {}
+    let closest = if options.best_effort && search.found().is_empty() { search.closest_solution() } else { None };
+    let (mut solutions, stats) = search.finish();
+    if let Some(closest) = closest {
+        solutions.insert(closest);
+    }
+    let objectives: Vec<Objective> = options.objectives.iter().copied().chain([Objective::Steps, Objective::Deviation]).collect();
+    solutions.rank(&objectives);
+    (solutions.into_iter().take(limit).collect(), stats)
+}
+
+// Every structurally distinct plan as short as the shortest one, up to `limit` of them. A first search
+// finds how short a plan can be; a second, capped at that depth, keeps states reached by equally short
+// paths instead of dropping all but the first, and runs until the frontier is exhausted or `limit` is reached
+pub fn enumerate_optimal(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions, limit: usize) -> (SolutionSet, SearchStats) {
+    let (first, stats) = search_solutions(inputs.clone(), target, can_be_off_by, options, 1);
+    let Some(shortest) = first.best().map(|solution| solution.steps.len()) else {
+        return (first, stats);
+    };
+    let mut options = options.clone();
+    options.max_depth = options.max_depth.min(shortest);
+    options.keep_ties = true;
+    let mut search = Search::<Fixed>::new(inputs, target, can_be_off_by, &options);
+    while search.found().len() < limit && search.next_solution().is_some() {}
+    let (found, stats) = search.finish();
+    // The heuristic is not admissible, so the capped search may still turn up something shorter
+    let fewest = found.iter().map(|solution| solution.steps.len()).min().unwrap_or(shortest);
+    let mut solutions: SolutionSet = found.into_iter().filter(|solution| solution.steps.len() == fewest).collect();
+    solutions.rank(&[Objective::Steps, Objective::Deviation]);
+    (solutions, stats)
+}
+
+// An A* search kept alive between solutions: each `next_solution` call continues from the
+// existing frontier, so asking for another plan never repeats work already done
+pub struct Search<V: Value = Fixed> {
+    inputs: Vec<f64>,
+    target: f64,
+    options: SearchOptions,
+    scaled_target: V,
+    scaled_margin: V,
+    scaled_capacity: Option<V>,
+    input_capacities: Vec<Option<V>>, // Indexed by input
+    forbidden: Vec<(V, V)>,
+    priority_queue: BucketQueue<Node>,
+    arena: Arena<V>,
+    links: Vec<Link<V>>, // Indexed by `Node::link`
+    visited: Visited<V>,
+    packing: Option<Packing>,
+    current: StateData<V>, // Reused buffers: the state being expanded, a successor, and the moves from it
+    next: StateData<V>,
+    moves: Vec<Move<V>>,
+    batch: StateData<V>, // Successors of the node being expanded, back to back
+    batched: Vec<Batched<V>>,
+    found: SolutionSet,
+    stats: SearchStats,
+    rng: Option<Rng>,
+    closest_link: Option<usize>, // The state holding the value nearest the target so far
+    fallback: Option<Fallback<V>>, // Set once the search outgrew `SearchOptions::memory_limit`
+    beam_counts: Vec<usize>,       // States queued at each depth, with a beam strategy
+    #[cfg(feature = "std")]
+    started: Instant,
+}
+
+// What the search turns into once its frontier and visited set outgrow the memory limit: a depth-first
+// search down to the depth limit (deepest node next, the most promising first among equals), so the
+// frontier stays a few nodes per level. It starts from the best nodes the frontier held, then from the
+// start state again, so no plan the full search would find is lost. Instead of the visited set, a
+// fixed-size table remembers recent states; a state it has forgotten is simply explored again
+struct Fallback<V> {
+    table: Vec<Option<FallbackEntry<V>>>, // Indexed by state hash; the newest state wins a slot
+}
+
+// A remembered state with the depth it was first reached at, as in `Visited`
+type FallbackEntry<V> = (StateKey<V>, usize);
+
+impl<V: Value> Fallback<V> {
+    // Keep the best frontier nodes and the start state, freeing the rest of the frontier and the
+    // visited set, with a table taking about a quarter of the memory limit
+    fn start(queue: &mut BucketQueue<Node>, visited: &mut Visited<V>, links: &[Link<V>], arena: &Arena<V>, limit: usize) -> Fallback<V> {
+        let seeds: Vec<Node> = core::iter::from_fn(|| queue.pop()).take(FALLBACK_SEEDS).collect();
+        *queue = BucketQueue::new();
+        *visited = Visited::default();
+        let mut start = StateData::new();
+        arena.load(links[0].slot, &mut start);
+        let root = Node { link: 0, hash: state_hash(&start.values), depth: 0, estimated_cost: 0, tie_break: 0 };
+        for node in seeds.into_iter().chain([root]) {
+            queue.push(-(node.depth as i64), node);
+        }
+        let slots = (limit / 4 / size_of::<Option<FallbackEntry<V>>>()).clamp(FALLBACK_TABLE_MIN, FALLBACK_TABLE_MAX);
+        Fallback { table: (0..slots).map(|_| None).collect() }
+    }
+
+    // Whether a state is new or reached in fewer steps than the table remembers, recording it if so
+    fn open(&mut self, key: StateKey<V>, depth: usize) -> bool {
+        let index = (key.hash % self.table.len() as u64) as usize;
+        let slot = &mut self.table[index];
+        if slot.as_ref().is_some_and(|(seen, seen_depth)| *seen == key && *seen_depth <= depth) {
+            return false;
+        }
+        *slot = Some((key, depth));
+        true
+    }
+}
+
+// Rough bytes held by a search's stored states, links, frontier and visited set
+fn memory_used<V: Value>(arena: &Arena<V>, links: &[Link<V>], queue: &BucketQueue<Node>, visited: &Visited<V>) -> usize {
+    let per_value = size_of::<V>() + 2 * size_of::<usize>() + size_of::<u64>();
+    // Keys mostly pack their values inline; the map adds about a word per entry of its own
+    let per_key = size_of::<StateKey<V>>() + 2 * size_of::<usize>();
+    arena.len() * per_value + size_of_val(links) + queue.len() * size_of::<Node>() + visited.len() * per_key
+}
+
+// Drop every stored state that neither an open node nor the closest state leads back to, renumbering
+// the links that remain. Only done in the continuation, whose frontier is small
+fn compact<V: Value>(arena: &mut Arena<V>, links: &mut Vec<Link<V>>, queue: &mut BucketQueue<Node>, closest_link: &mut Option<usize>) {
+    let open: Vec<Node> = core::iter::from_fn(|| queue.pop()).collect();
+    let mut kept = vec![usize::MAX; links.len()]; // New index of each link kept
+    let mut order = Vec::new();
+    for root in open.iter().map(|node| node.link).chain(*closest_link) {
+        let mut next = Some(root);
+        while let Some(link) = next {
+            if kept[link] != usize::MAX {
+                break;
+            }
+            kept[link] = 0;
+            order.push(link);
+            next = links[link].parent;
+        }
+    }
+    // Parents before children, so every kept link's parent has its new index when it is renumbered
+    order.sort_unstable();
+    let mut compacted_arena = Arena::new();
+    let mut compacted_links = Vec::with_capacity(order.len());
+    let mut state = StateData::new();
+    for &link in &order {
+        kept[link] = compacted_links.len();
+        arena.load(links[link].slot, &mut state);
+        compacted_links.push(Link {
+            parent: links[link].parent.map(|parent| kept[parent]),
+            slot: compacted_arena.alloc(&state),
+            reached_by: links[link].reached_by,
+        });
+    }
+    *arena = compacted_arena;
+    *links = compacted_links;
+    for mut node in open {
+        node.link = kept[node.link];
+        queue.push(-(node.depth as i64), node);
+    }
+    *closest_link = closest_link.map(|link| kept[link]);
+}
+
+impl<V: Value> Search<V> {
+    pub fn new(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) -> Search<V> {
+        // Scaling parameters
+        let scaled_target = V::from_f64(target);
+        let scaled_margin = V::from_f64(can_be_off_by);
+
+        // Initialize with the starting node
+        let mut start_state = StateData::new();
+        for (index, &input) in inputs.iter().enumerate() {
+            // Inputs past the 64th share origin bits, which only matters with `separate_inputs`
+            start_state.push(V::from_f64(input), 0, 1 << (index % 64), index);
+        }
+        let mut arena = Arena::new();
+        let links = vec![Link { parent: None, slot: arena.alloc(&start_state), reached_by: None }];
+        let start = Node {
+            estimated_cost: estimate_cost(&start_state.values, 0, scaled_target, scaled_margin, options),
+            hash: state_hash(&start_state.values),
+            link: 0,
+            depth: 0,
+            tie_break: 0,
+        };
+        let input_capacities = (0..inputs.len()).map(|index| options.input_capacity(index).map(V::from_f64)).collect();
+        let packing = Packing::for_inputs(&start_state.values);
+        let mut visited = Visited::default();
+        visited.insert(state_key(options, packing, start.hash, &start_state), start.depth);
+        let mut priority_queue = BucketQueue::new();
+        priority_queue.push(start.estimated_cost, start);
+
+        Search {
+            inputs,
+            target,
+            options: options.clone(),
+            scaled_target,
+            scaled_margin,
+            scaled_capacity: options.max_capacity().map(V::from_f64),
+            input_capacities,
+            // A single value is matched to the search's precision, since 86.667 is stored as 86.666
+            forbidden: options
+                .forbidden
+                .iter()
+                .map(|&(low, high)| if low == high { (V::from_f64(low - 1.0 / SCALE), V::from_f64(high + 1.0 / SCALE)) } else { (V::from_f64(low), V::from_f64(high)) })
+                .collect(),
+            priority_queue,
+            arena,
+            links,
+            visited,
+            packing,
+            current: start_state,
+            next: StateData::new(),
+            moves: Vec::new(),
+            batch: StateData::new(),
+            batched: Vec::new(),
+            found: SolutionSet::new(),
+            stats: SearchStats::default(),
+            rng: options.seed.map(Rng::new),
+            closest_link: None,
+            fallback: None,
+            beam_counts: Vec::new(),
+            #[cfg(feature = "std")]
+            started: Instant::now(),
+        }
+    }
+
+    // Solutions returned so far, in the order they were found
+    pub fn found(&self) -> &SolutionSet {
+        &self.found
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    // The plan to the value nearest the target among the states explored, within tolerance or not
+    pub fn closest_solution(&self) -> Option<Solution> {
+        let link = self.closest_link?;
+        let mut state = StateData::new();
+        self.arena.load(self.links[link].slot, &mut state);
+        let (nearest, _) = state.values.iter().enumerate().min_by_key(|&(_, &v)| v.abs_diff(self.scaled_target))?;
+        let remainder = state.values.iter().enumerate().filter(|&(index, _)| index != nearest).map(|(_, v)| v.to_f64()).collect();
+        Some(Solution {
+            inputs: self.inputs.clone(),
+            target: self.target,
+            outputs: vec![state.values[nearest].to_f64()],
+            remainder,
+            steps: plan_to(&self.links, &self.arena, &self.options, link),
+            penalty: 0.0,
+        })
+    }
+
+    pub fn finish(self) -> (SolutionSet, SearchStats) {
+        (self.found, self.stats)
+    }
+
+    // Run until the next structurally new plan, or `None` once the frontier is exhausted
+    // (or the search timed out or was cancelled)
+    pub fn next_solution(&mut self) -> Option<Solution> {
+        let whole = Stopwatch::start(self.options.profile_internal);
+        let solution = self.advance();
+        whole.stop(&mut self.stats.timings.total);
+        solution
+    }
+
+    fn advance(&mut self) -> Option<Solution> {
+        if self.stats.timed_out || self.stats.cancelled {
+            return None;
+        }
+        let options = &self.options;
+        let profiling = options.profile_internal;
+        let stats = &mut self.stats;
+        let (state, next, moves) = (&mut self.current, &mut self.next, &mut self.moves);
+        let (batch, batched) = (&mut self.batch, &mut self.batched);
+
+        loop {
+            // Past the memory limit, trade A*'s frontier and visited set for the memory-light continuation,
+            // then keep only the stored states that open nodes still lead back to
+            if let Some(limit) = options.memory_limit {
+                if stats.expanded.is_multiple_of(MEMORY_CHECK_INTERVAL)
+                    && memory_used(&self.arena, &self.links, &self.priority_queue, &self.visited) > limit
+                {
+                    if self.fallback.is_none() {
+                        stats.fallback_at = Some(stats.expanded);
+                        self.fallback = Some(Fallback::start(&mut self.priority_queue, &mut self.visited, &self.links, &self.arena, limit));
+                    }
+                    compact(&mut self.arena, &mut self.links, &mut self.priority_queue, &mut self.closest_link);
+                }
+            }
+            let watch = Stopwatch::start(profiling);
+            let Some(current) = self.priority_queue.pop() else { break };
+            watch.stop(&mut stats.timings.heap);
+            self.arena.load(self.links[current.link].slot, state);
+            // Checking the clock on every pop would dominate cheap expansions
+            stats.expanded += 1;
+            #[cfg(feature = "std")]
+            if let Some(timeout) = options.timeout {
+                if stats.expanded.is_multiple_of(1024) && self.started.elapsed() >= timeout {
+                    stats.timed_out = true;
+                    return None;
+                }
+            }
+            #[cfg(feature = "std")]
+            if options.yield_interval.is_some_and(|interval| interval > 0 && stats.expanded.is_multiple_of(interval)) {
+                std::thread::yield_now();
+            }
+            if let Some(control) = &options.control {
+                control.expanded.store(stats.expanded, AtomicOrdering::Relaxed);
+                control.frontier.store(self.priority_queue.len() as u64, AtomicOrdering::Relaxed);
+                // Sleeping needs std; without it a pause request is ignored
+                #[cfg(feature = "std")]
+                while control.is_paused() && !control.is_cancelled() {
+                    std::thread::sleep(PAUSE_POLL);
+                }
+                if control.is_cancelled() {
+                    stats.cancelled = true;
+                    return None;
+                }
+            }
+
+            // Remember the value that came nearest the target, for failure reports
+            if let Some(&nearest) = state.values.iter().min_by_key(|&&v| v.abs_diff(self.scaled_target)) {
+                let deviation = nearest.abs_diff(self.scaled_target).to_f64();
+                if stats.closest.is_none_or(|(_, best, _)| deviation < best) {
+                    stats.closest = Some((nearest.to_f64(), deviation, current.depth));
+                    self.closest_link = Some(current.link);
+                    if let Some(control) = &options.control {
+                        control.best_deviation.store((-deviation).to_bits(), AtomicOrdering::Relaxed);
+                    }
+                }
+            }
+
+            // Evaluate for target proximity
+            let watch = Stopwatch::start(profiling);
+            let goal = match &options.goal {
+                Some(predicate) => predicate.check(&state.values, current.depth),
+                None => find_final_and_remainder(state, self.scaled_target, self.scaled_margin, options, stats),
+            };
+            watch.stop(&mut stats.timings.goal_checks);
+            if let Some(final_values) = goal {
+                let (final_output_scaled, remainder_scaled) = final_values;
+                let outputs = final_output_scaled.iter().map(|&v| v.to_f64()).collect();
+                let remainder = remainder_scaled.iter().map(|&v| v.to_f64()).collect();
+
+                // The same operations applied in a different order are the same plan
+                let penalty = state
+                    .values
+                    .iter()
+                    .zip(&state.chains)
+                    .zip(&state.sources)
+                    .filter(|&((&value, &chain), _)| chain == 0 && value.abs_diff(self.scaled_target) > self.scaled_margin)
+                    .filter_map(|(_, &source)| options.optional_penalty(source))
+                    .sum();
+                let solution = Solution { inputs: self.inputs.clone(), target: self.target, outputs, remainder, steps: plan_to(&self.links, &self.arena, options, current.link), penalty };
+                // Merged near-duplicates make the search approximate; only plans that replay exactly count
+                // A custom goal's outputs need not be near the target, so then only the replay itself is checked
+                let can_be_off_by = if options.goal.is_some() { f64::INFINITY } else { self.scaled_margin.to_f64() };
+                if options.dedup_epsilon.is_some() && !solution.verify(can_be_off_by, 1.0 / SCALE) {
+                    stats.unverified += 1;
+                    continue;
+                }
+                if self.found.insert(solution.clone()) {
+                    EventSink::emit(&options.events, || SearchEvent::Goal { depth: current.depth, values: encode(&state.values) });
+                    return Some(solution);
+                }
+                continue;
+            }
+
+            // Avoid unnecessary depth
+            if current.depth >= options.max_depth {
+                stats.depth_limited += 1;
+                EventSink::emit(&options.events, || SearchEvent::Pruned {
+                    depth: current.depth,
+                    values: encode(&state.values),
+                    reason: PruneReason::DepthLimit,
+                });
+                continue;
+            }
+            if let Some(prune) = &options.prune {
+                let candidate = State { values: state.values.iter().map(|value| value.to_f64()).collect(), depth: current.depth };
+                if (prune.0)(&candidate) {
+                    stats.custom_pruned += 1;
+                    EventSink::emit(&options.events, || SearchEvent::Pruned {
+                        depth: current.depth,
+                        values: encode(&state.values),
+                        reason: PruneReason::Custom,
+                    });
+                    continue;
+                }
+            }
+            EventSink::emit(&options.events, || SearchEvent::Expanded { depth: current.depth, values: encode(&state.values) });
+
+            // Explore operations: split/combine. Budgets and limits depend only on the plan so far
+            let plan: Vec<Operation> = operations_to(&self.links, current.link).collect();
+            let footprint_used: f64 = plan.iter().map(|&operation| options.footprint(operation)).sum();
+            let cost_spent: f64 = plan.iter().map(|&operation| options.cost(operation)).sum();
+            let mut operations = Vec::new();
+            for &operation in &options.operations {
+                if options.max_cost.is_some_and(|budget| cost_spent + options.cost(operation) > budget) {
+                    stats.cost_rejected += state.len() as u64;
+                } else if options.max_footprint.is_some_and(|budget| footprint_used + options.footprint(operation) > budget) {
+                    stats.footprint_rejected += state.len() as u64;
+                } else if options.operation_limits.iter().any(|&(limited, limit)| {
+                    limited == operation && plan.iter().filter(|&&used| used == operation).count() >= limit
+                }) {
+                    stats.limit_rejected += state.len() as u64;
+                } else {
+                    operations.push(operation);
+                }
+            }
+
+            let evaluator = Evaluator {
+                options,
+                capacity: self.scaled_capacity,
+                input_capacities: &self.input_capacities,
+                forbidden: &self.forbidden,
+                target: self.scaled_target,
+                margin: self.scaled_margin,
+                depth: current.depth + 1,
+            };
+            let mut consider = |candidate: &Move<V>, successor: &StateData<V>, hash: u64, verdict: Verdict, estimated_cost: i64| {
+                match verdict {
+                    Verdict::MixesInputs => {
+                        stats.mixing_rejected += 1;
+                        return;
+                    }
+                    Verdict::ChainTooLong => {
+                        stats.chain_rejected += 1;
+                        return;
+                    }
+                    Verdict::OverCapacity => {
+                        stats.capacity_rejected += 1;
+                        EventSink::emit(&options.events, || SearchEvent::Pruned {
+                            depth: current.depth + 1,
+                            values: encode(&successor.values),
+                            reason: PruneReason::Capacity,
+                        });
+                        return;
+                    }
+                    Verdict::Forbidden => {
+                        stats.forbidden_rejected += 1;
+                        EventSink::emit(&options.events, || SearchEvent::Pruned {
+                            depth: current.depth + 1,
+                            values: encode(&successor.values),
+                            reason: PruneReason::Forbidden,
+                        });
+                        return;
+                    }
+                    Verdict::Open => {}
+                }
+
+                // Order equally promising states by the preferred style; this only decides which is expanded
+                // first, never whether a state reached again counts as a duplicate
+                let mut tie_break = options.style.tie_break(&successor.chains);
+                if options.prefer != Preference::None {
+                    tie_break = tie_break * 2 + options.prefer.tie_break(candidate.operation);
+                }
+                if let Some(rng) = self.rng.as_mut() {
+                    // Random low-order bits only reorder nodes the style already ranks equally
+                    tie_break = (tie_break << 32) | (rng.next_u64() >> 32) as i64;
+                }
+                if let Strategy::Beam { width } = options.strategy {
+                    if self.beam_counts.len() <= current.depth + 1 {
+                        self.beam_counts.resize(current.depth + 2, 0);
+                    }
+                    if self.beam_counts[current.depth + 1] >= width {
+                        stats.beam_pruned += 1;
+                        EventSink::emit(&options.events, || SearchEvent::Pruned {
+                            depth: current.depth + 1,
+                            values: encode(&successor.values),
+                            reason: PruneReason::Beam,
+                        });
+                        return;
+                    }
+                }
+                let watch = Stopwatch::start(profiling);
+                let key = state_key(options, self.packing, hash, successor);
+                let open = match self.fallback.as_mut() {
+                    Some(fallback) => fallback.open(key, current.depth + 1),
+                    None => {
+                        let depth = current.depth + 1;
+                        let open = self.visited.get(&key).is_none_or(|&seen| seen > depth || (options.keep_ties && seen == depth));
+                        if open {
+                            self.visited.insert(key, depth);
+                        }
+                        open
+                    }
+                };
+                watch.stop(&mut stats.timings.hashing);
+                if open {
+                    if let Some(count) = self.beam_counts.get_mut(current.depth + 1) {
+                        *count += 1;
+                    }
+                    self.links.push(Link { parent: Some(current.link), slot: self.arena.alloc(successor), reached_by: Some(*candidate) });
+                    let new_node = Node {
+                        link: self.links.len() - 1,
+                        hash,
+                        depth: current.depth + 1,
+                        estimated_cost,
+                        tie_break,
+                    };
+                    EventSink::emit(&options.events, || SearchEvent::Generated {
+                        parent: encode(&state.values),
+                        depth: new_node.depth,
+                        values: encode(&successor.values),
+                        priority: new_node.estimated_cost,
+                        heuristic: calculate_heuristic(&successor.values, self.scaled_target),
+                    });
+                    // The continuation goes deepest first
+                    let priority = if self.fallback.is_some() { -(new_node.depth as i64) } else { new_node.estimated_cost };
+                    let watch = Stopwatch::start(profiling);
+                    self.priority_queue.push(priority, new_node);
+                    watch.stop(&mut stats.timings.heap);
+                } else {
+                    EventSink::emit(&options.events, || SearchEvent::Pruned {
+                        depth: current.depth + 1,
+                        values: encode(&successor.values),
+                        reason: PruneReason::Duplicate,
+                    });
+                }
+            };
+
+            // Large states have O(n³) three-way merges; evaluate those across threads, then take them in order
+            #[cfg(feature = "parallel")]
+            if state.len() >= PARALLEL_VALUES {
+                // Generation and scoring overlap on the worker threads, so they are timed together
+                let watch = Stopwatch::start(profiling);
+                let evaluated = evaluator.evaluate_all(state, current.hash, &operations);
+                watch.stop(&mut stats.timings.generation);
+                for (candidate, successor, hash, verdict, estimated_cost) in evaluated {
+                    consider(&candidate, &successor, hash, verdict, estimated_cost);
+                }
+                continue;
+            }
+
+            // Generate every successor back to back, score the open ones in one pass over that contiguous
+            // memory, then hand them to the frontier in generation order
+            let watch = Stopwatch::start(profiling);
+            batch.clear();
+            batched.clear();
+            for i in 0..state.len() {
+                collect_moves(&operations, &state.values, i, moves);
+                for candidate in moves.iter() {
+                    let (hash, verdict) = evaluator.evaluate(state, current.hash, candidate, next);
+                    batched.push(Batched { candidate: *candidate, hash, verdict, start: batch.len(), len: next.len(), estimated_cost: 0 });
+                    batch.extend(next);
+                }
+            }
+            watch.stop(&mut stats.timings.generation);
+            let watch = Stopwatch::start(profiling);
+            for entry in batched.iter_mut().filter(|entry| matches!(entry.verdict, Verdict::Open)) {
+                entry.estimated_cost = evaluator.estimate(&batch.values[entry.start..entry.start + entry.len]);
+            }
+            watch.stop(&mut stats.timings.heuristics);
+            for entry in batched.drain(..) {
+                batch.copy_range(entry.start, entry.len, next);
+                consider(&entry.candidate, next, entry.hash, entry.verdict, entry.estimated_cost);
+            }
+        }
+        None
+    }
+}
+
+// What became of a successor before the visited set was consulted
+enum Verdict {
+    MixesInputs,
+    ChainTooLong,
+    OverCapacity,
+    Forbidden,
+    Open, // Passed every check; the visited set decides the rest
+}
+
+// A successor built on a worker thread: the move, its state and hash, the verdict on it and its estimated cost
+#[cfg(feature = "parallel")]
+type Evaluated<V> = (Move<V>, StateData<V>, u64, Verdict, i64);
+
+// A successor generated into an expansion's batch, waiting for its estimated cost
+struct Batched<V> {
+    candidate: Move<V>,
+    hash: u64,
+    verdict: Verdict,
+    start: usize, // Position of its values in the batch
+    len: usize,
+    estimated_cost: i64,
+}
+
+// The read-only half of expanding a node, which threads can share
+struct Evaluator<'a, V> {
+    options: &'a SearchOptions,
+    capacity: Option<V>,
+    input_capacities: &'a [Option<V>], // Indexed by input
+    forbidden: &'a [(V, V)],
+    target: V,
+    margin: V,
+    depth: usize, // Of the successors
+}
+
+impl<V: Value> Evaluator<'_, V> {
+    // Build the successor into `next` and check it against every constraint that needs only the state itself
+    fn evaluate(&self, state: &StateData<V>, hash: u64, candidate: &Move<V>, next: &mut StateData<V>) -> (u64, Verdict) {
+        let options = self.options;
+        let (hash, mixes_inputs) = candidate.apply(state, hash, next);
+        if options.separate_inputs && mixes_inputs {
+            return (hash, Verdict::MixesInputs);
+        }
+        if options.max_chain.is_some_and(|max| next.chains.iter().any(|&chain| chain > max)) {
+            return (hash, Verdict::ChainTooLong);
+        }
+
+        // Reject values a single belt/pipe could not carry, or that the stream they mostly came from could not
+        let over_capacity = self.capacity.is_some_and(|capacity| next.values.iter().any(|&v| v > capacity))
+            || next.values.iter().zip(&next.sources).any(|(&v, &source)| self.input_capacities[source].is_some_and(|capacity| v > capacity));
+        if over_capacity {
+            return (hash, Verdict::OverCapacity);
+        }
+        // Only what the operation produced is checked; inputs are whatever they are
+        if self.forbidden.iter().any(|&(low, high)| candidate.part >= low && candidate.part <= high) {
+            return (hash, Verdict::Forbidden);
+        }
+        (hash, Verdict::Open)
+    }
+
+    fn estimate(&self, values: &[V]) -> i64 {
+        estimate_cost(values, self.depth, self.target, self.margin, self.options)
+    }
+
+    // Every successor of `state` under `operations`, in the order the sequential loop would produce them.
+    // Values are dealt out to threads round-robin, since low indices have the most merges
+    #[cfg(feature = "parallel")]
+    fn evaluate_all(&self, state: &StateData<V>, hash: u64, operations: &[Operation]) -> Vec<Evaluated<V>> {
+        let threads = std::thread::available_parallelism().map_or(1, |count| count.get()).min(state.len());
+        let mut by_value: Vec<Vec<Evaluated<V>>> = (0..state.len()).map(|_| Vec::new()).collect();
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|thread| {
+                    scope.spawn(move || {
+                        let mut moves = Vec::new();
+                        let mut evaluated = Vec::new();
+                        for i in (thread..state.len()).step_by(threads) {
+                            let mut successors = Vec::new();
+                            collect_moves(operations, &state.values, i, &mut moves);
+                            for candidate in moves.drain(..) {
+                                let mut next = StateData::new();
+                                let (hash, verdict) = self.evaluate(state, hash, &candidate, &mut next);
+                                let estimated_cost = if matches!(verdict, Verdict::Open) { self.estimate(&next.values) } else { 0 };
+                                successors.push((candidate, next, hash, verdict, estimated_cost));
+                            }
+                            evaluated.push((i, successors));
+                        }
+                        evaluated
+                    })
+                })
+                .collect();
+            for worker in workers {
+                for (i, successors) in worker.join().expect("successor thread panicked") {
+                    by_value[i] = successors;
+                }
+            }
+        });
+        by_value.into_iter().flatten().collect()
+    }
+}
+
+// Further solutions on demand, best-first within the search's ordering
+impl<V: Value> Iterator for Search<V> {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        self.next_solution()
+    }
+}
+
+// Helper function to separate final values close to target and remainder. States that would be goals
+// but for `use_all_inputs` or `max_outputs` are counted in `stats`, so a failure can say which one bit
+fn find_final_and_remainder<V: Value>(
+    state: &StateData<V>,
+    target: V,
+    margin: V,
+    options: &SearchOptions,
+    stats: &mut SearchStats,
+) -> Option<(Vec<V>, Vec<V>)> {
+    let values = &state.values;
+    let mut final_values = Vec::new();
+    let mut remainder = Vec::new();
+    let mut unused_input = false;
+
+    for ((&value, &chain), &source) in values.iter().zip(&state.chains).zip(&state.sources) {
+        if value.abs_diff(target) <= margin {
+            final_values.push(value);
+        } else {
+            // An input no operation touched is being left out of the plan entirely
+            unused_input |= options.use_all_inputs && chain == 0 && options.optional_penalty(source).is_none();
+            remainder.push(value);
+        }
+    }
+
+    if let Some(count) = options.output_count {
+        if final_values.len() != count || !remainder.is_empty() {
+            return None;
+        }
+    }
+
+    if final_values.is_empty() || final_values.len() < options.min_outputs {
+        return None;
+    }
+    if unused_input {
+        stats.unused_input_rejected += 1;
+        return None;
+    }
+    // Every stream, remainder included, needs somewhere to go
+    if options.max_outputs.is_some_and(|max| values.len() > max) {
+        stats.outputs_rejected += 1;
+        return None;
+    }
+    Some((final_values, remainder))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Fixed;
+
+    #[test]
+    fn split_in_halves_hashes_like_its_state() {
+        let mut state = StateData::new();
+        state.push(Fixed(60_000), 0, 1, 0);
+        state.push(Fixed(40_000), 0, 2, 1);
+        let split = Move { operation: Operation::SplitTwo, consumed: [0, 0, 0], count: 1, part: Fixed(60_000).divide(2), parts: 2 };
+        let mut next = StateData::new();
+        let (hash, _) = split.apply(&state, state_hash(&state.values), &mut next);
+        assert_eq!(hash, state_hash(&next.values));
+        // The two halves must not cancel out, leaving just the untouched value
+        assert_ne!(hash, state_hash(&[Fixed(40_000)]));
+    }
+
+    #[test]
+    fn forbidden_rates_are_never_produced() {
+        let options = SearchOptions { forbidden: vec![(30.0, 30.0)], ..SearchOptions::default() };
+        let (solutions, stats) = search_solutions(vec![60.0], 15.0, 0.0, &options, 1);
+        let plan = solutions.best().expect("15 is reachable without 30");
+        assert!(plan.steps.iter().flat_map(|step| &step.produced).all(|&rate| (rate - 30.0).abs() > 1.0 / SCALE));
+        assert!(stats.forbidden_rejected > 0);
+    }
+
+    #[test]
+    fn demand_goals_pick_the_fewest_streams_among_many() {
+        let goal = GoalPredicate::demand(50.0, 0.0, 5.0, 15.0);
+        // Far more in-range streams than subsets could be tried of
+        let mut values = vec![10.0; 40];
+        values.extend([12.5, 12.5, 25.0, 2.0]);
+        let outputs = (goal.0)(&State { values, depth: 6 }).expect("50 is five streams of 10").outputs;
+        assert_eq!(outputs.len(), 5);
+        let goal = GoalPredicate::demand(40.0, 0.001, 10.0, 15.0);
+        let found = (goal.0)(&State { values: vec![13.333, 13.333, 13.333, 14.0], depth: 3 }).expect("within a thousandth");
+        assert_eq!(found.outputs, [0, 1, 2]);
+        assert_eq!((goal.0)(&State { values: vec![25.0, 12.0], depth: 1 }), None);
+    }
+
+    #[test]
+    fn goal_constraints_count_only_the_plans_they_cut() {
+        let options = SearchOptions { max_depth: 2, max_outputs: Some(1), use_all_inputs: true, ..SearchOptions::default() };
+        let (solutions, stats) = search_solutions(vec![60.0, 45.0], 20.0, 0.0, &options, 1);
+        assert!(solutions.is_empty());
+        assert!(stats.unused_input_rejected > 0 && stats.outputs_rejected > 0);
+        let options = SearchOptions { max_depth: 2, max_outputs: Some(9), use_all_inputs: true, ..SearchOptions::default() };
+        let (solutions, stats) = search_solutions(vec![60.0], 20.0, 0.0, &options, 1);
+        assert!(!solutions.is_empty());
+        assert_eq!((stats.unused_input_rejected, stats.outputs_rejected), (0, 0));
+    }
+}
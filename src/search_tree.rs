@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{EventSink, PruneReason, SearchEvent, SCALE};
+
+#[derive(Clone, Debug, Default)]
+struct TreeNode {
+    depth: usize,
+    priority: i64,
+    heuristic: i64,
+    expanded: bool,
+    goal: bool,
+    pruned: Option<PruneReason>,
+}
+
+// The part of the search space a search actually explored, rebuilt from its events.
+// Only the first `limit` states are kept so large searches still produce a readable graph
+#[derive(Debug)]
+pub struct SearchTree {
+    nodes: BTreeMap<Vec<i64>, TreeNode>,
+    edges: Vec<(Vec<i64>, Vec<i64>)>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl SearchTree {
+    pub fn new(limit: usize) -> SearchTree {
+        SearchTree { nodes: BTreeMap::new(), edges: Vec::new(), limit, truncated: false }
+    }
+
+    // An event sink filling a shared tree, to pass as `SearchOptions::events`
+    pub fn recorder(limit: usize) -> (EventSink, Arc<Mutex<SearchTree>>) {
+        let tree = Arc::new(Mutex::new(SearchTree::new(limit)));
+        let shared = Arc::clone(&tree);
+        let sink = EventSink(Arc::new(move |event: &SearchEvent| shared.lock().unwrap().record(event)));
+        (sink, tree)
+    }
+
+    fn node(&mut self, values: &[i64], depth: usize) -> Option<&mut TreeNode> {
+        if !self.nodes.contains_key(values) {
+            if self.nodes.len() >= self.limit {
+                self.truncated = true;
+                return None;
+            }
+            self.nodes.insert(values.to_vec(), TreeNode { depth, ..TreeNode::default() });
+        }
+        self.nodes.get_mut(values)
+    }
+
+    pub fn record(&mut self, event: &SearchEvent) {
+        match event {
+            SearchEvent::Expanded { depth, values } => {
+                if let Some(node) = self.node(values, *depth) {
+                    node.expanded = true;
+                }
+            }
+            SearchEvent::Generated { parent, depth, values, priority, heuristic } => {
+                if self.nodes.contains_key(parent) {
+                    if let Some(node) = self.node(values, *depth) {
+                        node.priority = *priority;
+                        node.heuristic = *heuristic;
+                        self.edges.push((parent.clone(), values.clone()));
+                    }
+                }
+            }
+            SearchEvent::Pruned { depth, values, reason } => {
+                // Duplicates are states already in the tree, reached another way
+                if *reason != PruneReason::Duplicate {
+                    if let Some(node) = self.node(values, *depth) {
+                        node.pruned = Some(*reason);
+                    }
+                }
+            }
+            SearchEvent::Goal { depth, values } => {
+                if let Some(node) = self.node(values, *depth) {
+                    node.goal = true;
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    // Graphviz DOT with g (depth), h (heuristic) and f (queue priority, both in thousandths) on every state
+    pub fn to_dot(&self) -> String {
+        let ids: BTreeMap<&Vec<i64>, usize> = self.nodes.keys().enumerate().map(|(id, values)| (values, id)).collect();
+        let mut text = String::from("digraph search {\n    rankdir=TB;\n    node [shape=box, fontsize=10];\n");
+        if self.truncated {
+            text.push_str(&format!("    label=\"truncated to the first {} states\";\n", self.limit));
+        }
+        for (values, node) in &self.nodes {
+            let rates: Vec<String> = values.iter().map(|&v| format!("{}", v as f64 / SCALE)).collect();
+            let style = if node.goal {
+                ", style=filled, fillcolor=palegreen"
+            } else if node.pruned.is_some() {
+                ", style=dashed"
+            } else if node.expanded {
+                ", style=filled, fillcolor=lightgrey"
+            } else {
+                ""
+            };
+            let mut label = format!("[{}]\\ng={} h={} f={}", rates.join(", "), node.depth, node.heuristic, node.priority);
+            if let Some(reason) = node.pruned {
+                label.push_str(&format!("\\npruned: {:?}", reason));
+            }
+            text.push_str(&format!("    s{} [label=\"{}\"{}];\n", ids[values], label, style));
+        }
+        for (from, to) in &self.edges {
+            text.push_str(&format!("    s{} -> s{};\n", ids[from], ids[to]));
+        }
+        text.push_str("}\n");
+        text
+    }
+}
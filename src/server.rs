@@ -0,0 +1,333 @@
+#[cfg(feature = "parallel")]
+use std::collections::{BTreeMap, VecDeque};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+#[cfg(feature = "parallel")]
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+use std::sync::Mutex;
+#[cfg(feature = "parallel")]
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+#[cfg(feature = "parallel")]
+use crate::events::{self, ProgressEvent};
+#[cfg(feature = "parallel")]
+use crate::json::Json;
+use crate::metrics::Metrics;
+use crate::problem::Problem;
+use crate::render;
+#[cfg(feature = "parallel")]
+use crate::websocket;
+use crate::{search_solutions, SearchOptions, Solution};
+#[cfg(feature = "parallel")]
+use crate::{EventSink, SearchControl, SearchEvent, SCALE};
+
+// Most distinct problems the result cache remembers before it starts over
+const CACHE_ENTRIES: usize = 256;
+// Largest request body accepted, so one client cannot make the server buffer without end
+const MAX_BODY: usize = 1 << 20;
+// Expansions between a request's search yielding its thread, so concurrent requests take turns
+const YIELD_INTERVAL: u64 = 256;
+// Span over which `Limits::rate` counts each client's requests
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+// Time between the snapshots a live client receives
+#[cfg(feature = "parallel")]
+const LIVE_INTERVAL: Duration = Duration::from_millis(250);
+// Queued states between those sampled for a live snapshot, and the most one snapshot carries
+#[cfg(feature = "parallel")]
+const SAMPLE_EVERY: u64 = 64;
+#[cfg(feature = "parallel")]
+const SAMPLE_SIZE: usize = 16;
+// Distinct plans a live search reports before it finishes
+#[cfg(feature = "parallel")]
+const LIVE_PLANS: usize = 5;
+// Clients tracked for rate limiting before those idle for a whole window are forgotten
+const MAX_CLIENTS: usize = 4096;
+
+// What one request or one client may take of the server, so a pathological problem or a busy client
+// cannot starve everyone else
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Limits {
+    pub quota: Option<Duration>, // Most time one request's search may run; past it the request is answered 503
+    pub rate: Option<u32>,       // Requests one client address may make per minute; past it they are answered 429
+    pub concurrent: usize,       // Requests served at once, each on its own thread; 0 or 1 serves them in turn
+}
+
+// A minimal HTTP service around the solver:
+//   POST /solve    a JSON problem as for `solve --stdin`; answers the plan as JSON, or `null` with 422
+//   GET /live      a WebSocket taking a JSON problem as its first message and streaming the search back
+//                  as JSON messages: "progress" snapshots with a sample of the frontier, each "candidate"
+//                  plan as it is found, then "finished" (or "error"). Needs the parallel feature
+//   GET /metrics   solve counts, latencies, nodes expanded, timeouts and cache hits for Prometheus
+// Identical request bodies are answered from a cache. With the parallel feature and `Limits::concurrent`
+// above one, connections are served on threads whose searches yield to each other
+pub struct Server {
+    options: SearchOptions,
+    limits: Limits,
+    metrics: Metrics,
+    cache: Mutex<HashMap<String, Option<Solution>>>,
+    clients: Mutex<HashMap<IpAddr, (Instant, u32)>>, // Start of each client's window and its requests in it
+}
+
+impl Server {
+    pub fn new(options: SearchOptions) -> Server {
+        Server::with_limits(options, Limits::default())
+    }
+
+    pub fn with_limits(options: SearchOptions, limits: Limits) -> Server {
+        Server { options, limits, metrics: Metrics::new(), cache: Mutex::new(HashMap::new()), clients: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Serve requests until the listener fails
+    pub fn run(&self, address: &str) -> Result<(), Error> {
+        let io_error = |source| Error::Io { path: address.to_string(), source };
+        let listener = TcpListener::bind(address).map_err(io_error)?;
+        #[cfg(feature = "parallel")]
+        if self.limits.concurrent > 1 {
+            let active = AtomicUsize::new(0);
+            return thread::scope(|scope| {
+                for stream in listener.incoming() {
+                    let mut stream = stream.map_err(io_error)?;
+                    if active.fetch_add(1, Ordering::SeqCst) >= self.limits.concurrent {
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        self.metrics.record_rejected();
+                        let _ = respond(&mut stream, "503 Service Unavailable", "text/plain", "too many requests in progress; try again\n");
+                        continue;
+                    }
+                    let active = &active;
+                    scope.spawn(move || {
+                        let _ = self.handle(stream);
+                        active.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+                Ok(())
+            });
+        }
+        for stream in listener.incoming() {
+            // A client hanging up mid-request is its own problem, not the server's
+            let _ = self.handle(stream.map_err(io_error)?);
+        }
+        Ok(())
+    }
+
+    // Whether `client` may make another request in the current window
+    fn admit(&self, client: IpAddr) -> bool {
+        let Some(rate) = self.limits.rate else { return true };
+        let mut clients = self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        if clients.len() >= MAX_CLIENTS {
+            clients.retain(|_, (start, _)| now.duration_since(*start) < RATE_WINDOW);
+        }
+        let (start, count) = clients.entry(client).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            (*start, *count) = (now, 0);
+        }
+        *count += 1;
+        *count <= rate
+    }
+
+    fn handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut reader = BufReader::new(&stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut content_length = 0;
+        let mut websocket_key = None;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                } else if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                    websocket_key = Some(value.trim().to_string());
+                }
+            }
+        }
+        if content_length > MAX_BODY {
+            return respond(&mut stream, "413 Payload Too Large", "text/plain", "request body too large\n");
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        let body = String::from_utf8_lossy(&body);
+
+        let mut parts = request_line.split_whitespace();
+        match (parts.next().unwrap_or(""), parts.next().unwrap_or("")) {
+            ("GET", "/metrics") => respond(&mut stream, "200 OK", "text/plain; version=0.0.4", &self.metrics.render()),
+            ("POST", "/solve") | ("GET", "/live") if !stream.peer_addr().is_ok_and(|peer| self.admit(peer.ip())) => {
+                self.metrics.record_rate_limited();
+                respond(&mut stream, "429 Too Many Requests", "text/plain", "request rate limit reached; try again in a minute\n")
+            }
+            ("POST", "/solve") => match self.solve(&body) {
+                Ok(Answer::Found(solution)) => respond(&mut stream, "200 OK", "application/json", &render::to_json(&solution).to_string()),
+                Ok(Answer::NotFound) => respond(&mut stream, "422 Unprocessable Entity", "application/json", "null"),
+                Ok(Answer::OverQuota(quota)) => respond(
+                    &mut stream,
+                    "503 Service Unavailable",
+                    "text/plain",
+                    &format!("the search ran past this server's quota of {:?} per request\n", quota),
+                ),
+                Err(error) => respond(&mut stream, "400 Bad Request", "text/plain", &format!("{}\n", error)),
+            },
+            ("GET", "/live") => match websocket_key {
+                Some(key) => self.live(&mut reader, &stream, &key),
+                None => respond(&mut stream, "426 Upgrade Required", "text/plain", "/live streams a search over a WebSocket\n"),
+            },
+            _ => respond(&mut stream, "404 Not Found", "text/plain", "try POST /solve, GET /live or GET /metrics\n"),
+        }
+    }
+
+    // Stream a search to a WebSocket client until it finishes or the client goes away
+    #[cfg(feature = "parallel")]
+    fn live(&self, reader: &mut impl Read, mut writer: &TcpStream, key: &str) -> std::io::Result<()> {
+        websocket::handshake(&mut writer, key)?;
+        let Some(text) = websocket::read_text(reader, &mut writer, MAX_BODY)? else { return Ok(()) };
+        let problem = match Problem::from_json_str(&text) {
+            Ok(problem) => problem,
+            Err(error) => {
+                websocket::write_text(&mut writer, &live_message("error", vec![("message", Json::String(error.to_string()))]))?;
+                return websocket::write_close(&mut writer);
+            }
+        };
+        let mut options = self.limited(problem.search_options(&self.options));
+        let control = SearchControl::new();
+        options.control = Some(control.clone());
+        // Every so many queued states, the newest is kept for the next snapshot's sample of the frontier
+        let sample = Arc::new(Mutex::new(VecDeque::new()));
+        let generated = AtomicU64::new(0);
+        let sampler = Arc::clone(&sample);
+        options.events = Some(EventSink(Arc::new(move |event: &SearchEvent| {
+            if let SearchEvent::Generated { depth, values, .. } = event {
+                if generated.fetch_add(1, Ordering::Relaxed).is_multiple_of(SAMPLE_EVERY) {
+                    let mut sample = sampler.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if sample.len() >= SAMPLE_SIZE {
+                        sample.pop_front();
+                    }
+                    sample.push_back((*depth, values.clone()));
+                }
+            }
+        })));
+
+        let start_time = Instant::now();
+        let (receiver, handle) = events::stream_progress(problem.inputs, problem.target, problem.tolerance, &options, LIVE_PLANS, LIVE_INTERVAL);
+        for event in receiver {
+            let message = match event {
+                ProgressEvent::Progress { elapsed, expanded, frontier, best_deviation } => {
+                    let states = sample.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).drain(..).collect::<Vec<_>>();
+                    let states = states.into_iter().map(|(depth, values)| {
+                        let values = values.into_iter().map(|value| Json::Number(value as f64 / SCALE)).collect();
+                        Json::Object(BTreeMap::from([("depth".to_string(), Json::Number(depth as f64)), ("values".to_string(), Json::Array(values))]))
+                    });
+                    live_message(
+                        "progress",
+                        vec![
+                            ("elapsed", Json::Number(elapsed.as_secs_f64())),
+                            ("expanded", Json::Number(expanded as f64)),
+                            ("frontier", Json::Number(frontier as f64)),
+                            ("best_deviation", best_deviation.map_or(Json::Null, Json::Number)),
+                            ("sample", Json::Array(states.collect())),
+                        ],
+                    )
+                }
+                ProgressEvent::Found(solution) => live_message("candidate", vec![("plan", render::to_json(&solution))]),
+                ProgressEvent::Finished { elapsed, stats } => live_message(
+                    "finished",
+                    vec![
+                        ("elapsed", Json::Number(elapsed.as_secs_f64())),
+                        ("expanded", Json::Number(stats.expanded as f64)),
+                        ("timed_out", Json::Bool(stats.timed_out)),
+                    ],
+                ),
+            };
+            if websocket::write_text(&mut writer, &message).is_err() {
+                // Nobody is watching any more, so there is no one to search for
+                control.cancel();
+                break;
+            }
+        }
+        if let Ok((solutions, stats)) = handle.join() {
+            self.metrics.record_solve(start_time.elapsed(), &stats, !solutions.is_empty());
+        }
+        websocket::write_close(&mut writer)
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn live(&self, _: &mut impl Read, mut writer: &TcpStream, _: &str) -> std::io::Result<()> {
+        respond(&mut writer, "501 Not Implemented", "text/plain", "/live needs a build with the parallel feature\n")
+    }
+
+    // The server's options for one request, within its quota and taking turns with concurrent requests
+    fn limited(&self, mut options: SearchOptions) -> SearchOptions {
+        if let Some(quota) = self.limits.quota {
+            options.timeout = Some(options.timeout.map_or(quota, |timeout| timeout.min(quota)));
+        }
+        if self.limits.concurrent > 1 {
+            options.yield_interval = Some(YIELD_INTERVAL);
+        }
+        options
+    }
+
+    fn solve(&self, body: &str) -> Result<Answer, Error> {
+        let key = body.trim().to_string();
+        let cache = || self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(cached) = cache().get(&key) {
+            self.metrics.record_cache(true);
+            return Ok(cached.clone().map_or(Answer::NotFound, Answer::Found));
+        }
+        let problem = Problem::from_json_str(body)?;
+        self.metrics.record_cache(false);
+        let start_time = Instant::now();
+        let options = problem.search_options(&self.options);
+        // The quota only answers for a cut-off search when it is what cut it off, not an earlier timeout
+        let quota = self.limits.quota.filter(|&quota| options.timeout.is_none_or(|timeout| quota < timeout));
+        let options = self.limited(options);
+        let (solutions, stats) = search_solutions(problem.inputs, problem.target, problem.tolerance, &options, 1);
+        let solution = solutions.into_iter().next();
+        self.metrics.record_solve(start_time.elapsed(), &stats, solution.is_some());
+        // Running out of time says nothing about the problem, so only finished searches are cached
+        if solution.is_none() && stats.timed_out {
+            return Ok(quota.map_or(Answer::NotFound, Answer::OverQuota));
+        }
+        let mut cache = cache();
+        if cache.len() >= CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(key, solution.clone());
+        Ok(solution.map_or(Answer::NotFound, Answer::Found))
+    }
+}
+
+// One message to a live client: {"type": kind, ...fields}
+#[cfg(feature = "parallel")]
+fn live_message(kind: &str, fields: Vec<(&str, Json)>) -> String {
+    let mut object: BTreeMap<String, Json> = fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect();
+    object.insert("type".to_string(), Json::String(kind.to_string()));
+    Json::Object(object).to_string()
+}
+
+// How a solve request is answered
+enum Answer {
+    Found(Solution),
+    NotFound,
+    OverQuota(Duration), // The search was cut off by `Limits::quota`
+}
+
+fn respond(stream: &mut impl Write, status: &str, content_type: &str, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,130 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::Solution;
+
+// Measures solutions can be ranked by; lower is better for each
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    Steps,     // Number of operations
+    Deviation, // Total distance of the outputs from the target
+    Remainder, // Total flow left over
+    Outputs,   // Number of outputs, counted negatively so more ranks first
+}
+
+impl Objective {
+    pub fn from_name(name: &str) -> Option<Objective> {
+        match name {
+            "steps" | "machines" => Some(Objective::Steps),
+            "deviation" => Some(Objective::Deviation),
+            "remainder" | "waste" => Some(Objective::Remainder),
+            "outputs" => Some(Objective::Outputs),
+            _ => None,
+        }
+    }
+
+    pub fn measure(self, solution: &Solution) -> f64 {
+        match self {
+            Objective::Steps => solution.steps.len() as f64 + solution.penalty,
+            Objective::Deviation => solution.outputs.iter().map(|&v| (v - solution.target).abs()).sum(),
+            Objective::Remainder => solution.remainder.iter().sum(),
+            Objective::Outputs => -(solution.outputs.len() as f64),
+        }
+    }
+}
+
+// The same operations applied in a different order are the same plan
+fn plan_key(solution: &Solution) -> Vec<String> {
+    let mut key: Vec<String> = solution.steps.iter().map(|step| step.description.clone()).collect();
+    key.sort();
+    key
+}
+
+// Distinct plans for one problem, kept in rank order
+#[derive(Clone, Debug, Default)]
+pub struct SolutionSet {
+    solutions: Vec<Solution>,
+    plans: Vec<Vec<String>>, // Plan key of every solution ever inserted
+}
+
+impl SolutionSet {
+    pub fn new() -> SolutionSet {
+        SolutionSet::default()
+    }
+
+    // Add a solution unless a structurally equivalent plan is already present
+    pub fn insert(&mut self, solution: Solution) -> bool {
+        let key = plan_key(&solution);
+        if self.plans.contains(&key) {
+            return false;
+        }
+        self.plans.push(key);
+        self.solutions.push(solution);
+        true
+    }
+
+    // Reorder by the objectives in priority order, later ones breaking ties of earlier ones
+    pub fn rank(&mut self, objectives: &[Objective]) {
+        self.solutions.sort_by(|a, b| {
+            objectives
+                .iter()
+                .map(|objective| objective.measure(a).total_cmp(&objective.measure(b)))
+                .find(|&ordering| ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    // Reorder by any caller-supplied key
+    pub fn rank_by_key<K: Ord>(&mut self, key: impl FnMut(&Solution) -> K) {
+        self.solutions.sort_by_key(key);
+    }
+
+    pub fn best(&self) -> Option<&Solution> {
+        self.solutions.first()
+    }
+
+    pub fn len(&self) -> usize {
+        self.solutions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.solutions.is_empty()
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, Solution> {
+        self.solutions.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Solution> {
+        self.solutions
+    }
+}
+
+impl IntoIterator for SolutionSet {
+    type Item = Solution;
+    type IntoIter = alloc::vec::IntoIter<Solution>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.solutions.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SolutionSet {
+    type Item = &'a Solution;
+    type IntoIter = core::slice::Iter<'a, Solution>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.solutions.iter()
+    }
+}
+
+impl FromIterator<Solution> for SolutionSet {
+    fn from_iter<I: IntoIterator<Item = Solution>>(iter: I) -> SolutionSet {
+        let mut set = SolutionSet::new();
+        for solution in iter {
+            set.insert(solution);
+        }
+        set
+    }
+}
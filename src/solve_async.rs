@@ -0,0 +1,73 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::{shortest_path_to_target, SearchControl, SearchOptions, Solution};
+
+// Result slot shared between the search thread and the future awaiting it
+#[derive(Default)]
+struct Shared {
+    result: Option<Option<Solution>>,
+    waker: Option<Waker>,
+}
+
+// Future resolving to the solution of a search running on its own thread.
+// It works with any executor; dropping it (e.g. the losing branch of tokio's `select!`) cancels the search.
+pub struct SolveHandle {
+    shared: Arc<Mutex<Shared>>,
+    control: SearchControl,
+}
+
+impl SolveHandle {
+    // States expanded so far, for progress reporting while awaiting
+    pub fn expanded(&self) -> u64 {
+        self.control.expanded()
+    }
+
+    pub fn cancel(&self) {
+        self.control.cancel();
+    }
+}
+
+impl Future for SolveHandle {
+    type Output = Option<Solution>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for SolveHandle {
+    fn drop(&mut self) {
+        self.control.cancel();
+    }
+}
+
+// Start a search off the caller's thread so async servers don't block their executor
+pub fn solve_async(inputs: Vec<f64>, target: f64, can_be_off_by: f64, options: &SearchOptions) -> SolveHandle {
+    let control = SearchControl::new();
+    let mut options = options.clone();
+    options.control = Some(control.clone());
+    let shared = Arc::new(Mutex::new(Shared::default()));
+
+    let worker_shared = Arc::clone(&shared);
+    thread::spawn(move || {
+        let solution = shortest_path_to_target(inputs, target, can_be_off_by, &options);
+        let mut shared = worker_shared.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        shared.result = Some(solution);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    });
+
+    SolveHandle { shared, control }
+}
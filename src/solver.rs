@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::problem::Problem;
+use crate::recipes::RecipeDatabase;
+use crate::{SearchOptions, Solution};
+
+// A long-lived solver that answers many problems, possibly from several threads at once.
+// Everything a query mutates is created per call; only the answer cache is shared
+pub struct Solver {
+    options: SearchOptions,
+    recipes: RecipeDatabase,
+    cache: Mutex<HashMap<String, Option<Solution>>>,
+}
+
+// Queries may be answered concurrently from one instance
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Solver>();
+};
+
+impl Solver {
+    pub fn new(options: SearchOptions) -> Solver {
+        Solver { options, recipes: RecipeDatabase::bundled(), cache: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn with_recipes(mut self, recipes: RecipeDatabase) -> Solver {
+        self.recipes = recipes;
+        self
+    }
+
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    pub fn recipes(&self) -> &RecipeDatabase {
+        &self.recipes
+    }
+
+    // Solve one problem, reusing the answer if the same problem was solved before.
+    // The cache is not locked while searching, so slow queries don't hold up others
+    pub fn solve(&self, problem: &Problem) -> Option<Solution> {
+        let key = format!("{:?}", problem);
+        if let Some(answer) = self.cache.lock().unwrap().get(&key) {
+            return answer.clone();
+        }
+
+        // A cancel flag on the shared options would otherwise stop every query at once
+        let mut options = self.options.clone();
+        options.control = None;
+        let answer = problem.solve(&options);
+        self.cache.lock().unwrap().insert(key, answer.clone());
+        answer
+    }
+
+    // Number of distinct problems answered so far
+    pub fn cached(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+}
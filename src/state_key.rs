@@ -0,0 +1,189 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::BuildHasherDefault;
+use core::hash::{Hash, Hasher};
+
+use crate::rng::Rng;
+use crate::value::Value;
+
+// Hash of one value. A state's hash is the wrapping sum of its values' hashes, so an operation updates
+// it by subtracting what it consumed and adding what it produced. A sum rather than an XOR, which would
+// cancel repeated values in pairs and make a split in halves vanish from the hash
+pub(crate) fn value_hash<V: Hash>(value: &V) -> u64 {
+    let mut hasher = Fnv::default();
+    value.hash(&mut hasher);
+    // FNV alone spreads nearby integers poorly; one SplitMix64 round mixes every bit
+    Rng::new(hasher.0).next_u64()
+}
+
+pub(crate) fn state_hash<V: Hash>(values: &[V]) -> u64 {
+    values.iter().fold(0, |hash: u64, value| hash.wrapping_add(value_hash(value)))
+}
+
+// Visited-set key. The hash is carried along from the parent state rather than recomputed, and is
+// the only thing hashed; equality still compares the values, so colliding states stay distinct
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct StateKey<V> {
+    pub hash: u64,
+    pub values: StateValues<V>,
+    pub history: Vec<u64>, // Per-value details that matter under some constraints
+}
+
+// A state's values, bit-packed inline when they are few and small enough, which on game-sized
+// problems is nearly always and saves a heap allocation per visited state
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum StateValues<V> {
+    Packed(u8, [u128; 2]), // Value count, then the values at a fixed width each
+    Full(Vec<V>),
+    Quantized(Vec<i64>), // Values as multiples of a coarser epsilon
+}
+
+// Fixed bit width for packing one search's values, wide enough for anything its inputs can reach
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Packing {
+    bits: u32,
+}
+
+impl Packing {
+    // Merges only ever add inputs together, so no value exceeds their sum
+    pub fn for_inputs<V: Value>(inputs: &[V]) -> Option<Packing> {
+        let mut total: u64 = 0;
+        for &input in inputs {
+            total = total.checked_add(input.packed()?)?;
+        }
+        Some(Packing { bits: (64 - total.leading_zeros()).max(1) })
+    }
+
+    pub fn pack<V: Value>(packing: Option<Packing>, values: &[V]) -> StateValues<V> {
+        packing.and_then(|packing| packing.try_pack(values)).unwrap_or_else(|| StateValues::Full(values.to_vec()))
+    }
+
+    fn try_pack<V: Value>(self, values: &[V]) -> Option<StateValues<V>> {
+        let bits = self.bits as usize;
+        if values.len() * bits > 256 {
+            return None;
+        }
+        let mut words = [0u128; 2];
+        for (index, &value) in values.iter().enumerate() {
+            let value = value.packed().filter(|&value| value >> bits == 0)? as u128;
+            let offset = index * bits;
+            words[offset / 128] |= value << (offset % 128);
+            if offset % 128 + bits > 128 {
+                words[1] |= value >> (128 - offset % 128);
+            }
+        }
+        Some(StateValues::Packed(values.len() as u8, words))
+    }
+}
+
+impl<V> Hash for StateKey<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+// FNV-1a, for hashing single values without std's randomly keyed SipHash
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+// Passes a `StateKey`'s precomputed hash straight through; only the std hash map needs it
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub(crate) struct StateHasher(u64);
+
+#[cfg(feature = "std")]
+impl Hasher for StateHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 << 8) | byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) type BuildStateHasher = BuildHasherDefault<StateHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Fixed;
+
+    // Reads the values back out of a packed state
+    fn unpack(count: u8, words: [u128; 2], bits: u32) -> Vec<u64> {
+        let bits = bits as usize;
+        (0..count as usize)
+            .map(|index| {
+                let offset = index * bits;
+                let mut value = words[offset / 128] >> (offset % 128);
+                if offset % 128 + bits > 128 {
+                    value |= words[1] << (128 - offset % 128);
+                }
+                (value & ((1u128 << bits) - 1)) as u64
+            })
+            .collect()
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_fresh_one() {
+        let before = [Fixed(60_000), Fixed(40_000), Fixed(60_000)];
+        // Merge the two 60s into 120, keeping the 40
+        let mut hash = state_hash(&before);
+        hash = hash.wrapping_sub(value_hash(&before[0])).wrapping_sub(value_hash(&before[2]));
+        hash = hash.wrapping_add(value_hash(&Fixed(120_000)));
+        assert_eq!(hash, state_hash(&[Fixed(40_000), Fixed(120_000)]));
+        // Order does not matter, and repeated values do not cancel
+        assert_eq!(state_hash(&[Fixed(1), Fixed(2)]), state_hash(&[Fixed(2), Fixed(1)]));
+        assert_ne!(state_hash(&[Fixed(5), Fixed(5)]), 0);
+    }
+
+    #[test]
+    fn packs_values_that_fit_and_falls_back_otherwise() {
+        let inputs = [Fixed(60_000), Fixed(40_000)];
+        let packing = Packing::for_inputs(&inputs).expect("small non-negative inputs pack");
+        assert_eq!(packing.bits, 17);
+        let values = [Fixed(100_000), Fixed(0), Fixed(33_333)];
+        match Packing::pack(Some(packing), &values) {
+            StateValues::Packed(count, words) => assert_eq!(unpack(count, words, packing.bits), [100_000, 0, 33_333]),
+            other => panic!("expected a packed state, got {:?}", other),
+        }
+        // Values spanning both words come back whole
+        let many: Vec<Fixed> = (0..15).map(|index| Fixed(index * 7_001)).collect();
+        match Packing::pack(Some(packing), &many) {
+            StateValues::Packed(count, words) => {
+                assert_eq!(unpack(count, words, packing.bits), many.iter().map(|value| value.0 as u64).collect::<Vec<_>>())
+            }
+            other => panic!("expected a packed state, got {:?}", other),
+        }
+
+        // Too wide for the packing, too many for 256 bits, or not packable at all
+        assert!(matches!(Packing::pack(Some(packing), &[Fixed(1 << 17)]), StateValues::Full(_)));
+        assert!(matches!(Packing::pack(Some(packing), &[Fixed(1); 16]), StateValues::Full(_)));
+        assert!(Packing::for_inputs(&[Fixed(-1)]).is_none());
+        assert!(matches!(Packing::pack::<Fixed>(None, &values), StateValues::Full(_)));
+    }
+}
@@ -0,0 +1,50 @@
+// Which side of the target outputs may fall on
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToleranceMode {
+    #[default]
+    Both,
+    AtLeast, // Never below the target, for machines that must not be underfed
+    AtMost,  // Never above it
+}
+
+impl ToleranceMode {
+    pub fn from_name(name: &str) -> Option<ToleranceMode> {
+        match name {
+            "both" => Some(ToleranceMode::Both),
+            "at-least" => Some(ToleranceMode::AtLeast),
+            "at-most" => Some(ToleranceMode::AtMost),
+            _ => None,
+        }
+    }
+}
+
+// How far an output may fall below and above the target
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub below: f64,
+    pub above: f64,
+}
+
+impl Tolerance {
+    // `can_be_off_by` on the sides `mode` allows
+    pub fn new(can_be_off_by: f64, mode: ToleranceMode) -> Tolerance {
+        match mode {
+            ToleranceMode::Both => Tolerance { below: can_be_off_by, above: can_be_off_by },
+            ToleranceMode::AtLeast => Tolerance { below: 0.0, above: can_be_off_by },
+            ToleranceMode::AtMost => Tolerance { below: can_be_off_by, above: 0.0 },
+        }
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.below == self.above
+    }
+
+    // The band as the target and canBeOffBy the search takes: its middle and half its width. A symmetric
+    // band leaves the target as it is
+    pub fn centered(&self, target: f64) -> (f64, f64) {
+        if self.is_symmetric() {
+            return (target, self.above);
+        }
+        (target + (self.above - self.below) / 2.0, (self.above + self.below) / 2.0)
+    }
+}
@@ -0,0 +1,193 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use crate::error::Error;
+use crate::{EventSink, PruneReason, SearchEvent};
+
+// Identifies a trace file and its layout version
+const MAGIC: &[u8; 8] = b"CALCTRC1";
+
+// Everything needed to run a search again, and every decision it made the first time: the command
+// line that chose its options, the problem it was given and each expansion, successor, prune and goal
+// in order. Replaying runs the same search and compares event by event, so a change to pruning or the
+// heuristics shows up as the first event that differs. Searches cut short by a timeout stop at a
+// different point each run, so only their common prefix is comparable
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace {
+    pub arguments: Vec<String>, // Command-line options, without the program name or --record
+    pub inputs: Vec<f64>,
+    pub target: f64,
+    pub tolerance: f64,
+    pub events: Vec<SearchEvent>,
+}
+
+// An event sink collecting every event, passing each on to `next` as well, to pass as
+// `SearchOptions::events`
+pub fn recorder(next: Option<EventSink>) -> (EventSink, Arc<Mutex<Vec<SearchEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::clone(&events);
+    let sink = EventSink(Arc::new(move |event: &SearchEvent| {
+        shared.lock().unwrap().push(event.clone());
+        if let Some(next) = &next {
+            (next.0)(event);
+        }
+    }));
+    (sink, events)
+}
+
+// Index of the first event where two runs part ways, or None if they match throughout
+pub fn first_divergence(recorded: &[SearchEvent], replayed: &[SearchEvent]) -> Option<usize> {
+    match recorded.iter().zip(replayed).position(|(a, b)| a != b) {
+        Some(index) => Some(index),
+        None if recorded.len() != replayed.len() => Some(recorded.len().min(replayed.len())),
+        None => None,
+    }
+}
+
+impl Trace {
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.encode()).map_err(|source| Error::Io { path: path.to_string(), source })
+    }
+
+    pub fn load(path: &str) -> Result<Trace, Error> {
+        let bytes = fs::read(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        Trace::decode(&bytes).map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+
+    // Little-endian throughout; lists are prefixed with their length
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        put_u64(&mut bytes, self.arguments.len() as u64);
+        for argument in &self.arguments {
+            put_u64(&mut bytes, argument.len() as u64);
+            bytes.extend_from_slice(argument.as_bytes());
+        }
+        put_u64(&mut bytes, self.inputs.len() as u64);
+        for &value in self.inputs.iter().chain([&self.target, &self.tolerance]) {
+            put_u64(&mut bytes, value.to_bits());
+        }
+        put_u64(&mut bytes, self.events.len() as u64);
+        for event in &self.events {
+            match event {
+                SearchEvent::Expanded { depth, values } => {
+                    bytes.push(0);
+                    put_u64(&mut bytes, *depth as u64);
+                    put_values(&mut bytes, values);
+                }
+                SearchEvent::Generated { parent, depth, values, priority, heuristic } => {
+                    bytes.push(1);
+                    put_values(&mut bytes, parent);
+                    put_u64(&mut bytes, *depth as u64);
+                    put_values(&mut bytes, values);
+                    put_u64(&mut bytes, *priority as u64);
+                    put_u64(&mut bytes, *heuristic as u64);
+                }
+                SearchEvent::Pruned { depth, values, reason } => {
+                    bytes.push(2);
+                    put_u64(&mut bytes, *depth as u64);
+                    put_values(&mut bytes, values);
+                    bytes.push(match reason {
+                        PruneReason::DepthLimit => 0,
+                        PruneReason::Capacity => 1,
+                        PruneReason::Duplicate => 2,
+                        PruneReason::Custom => 3,
+                        PruneReason::Beam => 4,
+                        PruneReason::Forbidden => 5,
+                    });
+                }
+                SearchEvent::Goal { depth, values } => {
+                    bytes.push(3);
+                    put_u64(&mut bytes, *depth as u64);
+                    put_values(&mut bytes, values);
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Trace, String> {
+        if !bytes.starts_with(MAGIC) {
+            return Err("not a search trace".to_string());
+        }
+        let mut reader = Reader { bytes, pos: MAGIC.len() };
+        let arguments = (0..reader.u64()?)
+            .map(|_| {
+                let length = reader.u64()? as usize;
+                String::from_utf8(reader.take(length)?.to_vec()).map_err(|_| "argument is not UTF-8".to_string())
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        let inputs = (0..reader.u64()?).map(|_| reader.f64()).collect::<Result<Vec<f64>, String>>()?;
+        let (target, tolerance) = (reader.f64()?, reader.f64()?);
+        let count = reader.u64()?;
+        let mut events = Vec::new();
+        for _ in 0..count {
+            let event = match reader.take(1)?[0] {
+                0 => SearchEvent::Expanded { depth: reader.u64()? as usize, values: reader.values()? },
+                1 => SearchEvent::Generated {
+                    parent: reader.values()?,
+                    depth: reader.u64()? as usize,
+                    values: reader.values()?,
+                    priority: reader.u64()? as i64,
+                    heuristic: reader.u64()? as i64,
+                },
+                2 => SearchEvent::Pruned {
+                    depth: reader.u64()? as usize,
+                    values: reader.values()?,
+                    reason: match reader.take(1)?[0] {
+                        0 => PruneReason::DepthLimit,
+                        1 => PruneReason::Capacity,
+                        2 => PruneReason::Duplicate,
+                        3 => PruneReason::Custom,
+                        4 => PruneReason::Beam,
+                        5 => PruneReason::Forbidden,
+                        other => return Err(format!("unknown prune reason {}", other)),
+                    },
+                },
+                3 => SearchEvent::Goal { depth: reader.u64()? as usize, values: reader.values()? },
+                other => return Err(format!("unknown event kind {}", other)),
+            };
+            events.push(event);
+        }
+        if reader.pos != bytes.len() {
+            return Err("trailing bytes after the last event".to_string());
+        }
+        Ok(Trace { arguments, inputs, target, tolerance, events })
+    }
+}
+
+fn put_u64(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_values(bytes: &mut Vec<u8>, values: &[i64]) {
+    put_u64(bytes, values.len() as u64);
+    for &value in values {
+        put_u64(bytes, value as u64);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Result<&[u8], String> {
+        let end = self.pos.checked_add(length).filter(|&end| end <= self.bytes.len()).ok_or("trace ends early")?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        self.u64().map(f64::from_bits)
+    }
+
+    fn values(&mut self) -> Result<Vec<i64>, String> {
+        (0..self.u64()?).map(|_| self.u64().map(|value| value as i64)).collect()
+    }
+}
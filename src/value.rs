@@ -0,0 +1,281 @@
+use core::cmp::Ordering;
+use core::fmt::Debug;
+use core::hash::{Hash, Hasher};
+
+use crate::SCALE;
+
+// Number representation the search works in. Values must order and hash exactly,
+// since states are deduplicated by their values
+pub trait Value: Copy + Ord + Hash + Debug + Send + Sync {
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    // One of `parts` equal shares of the value
+    fn divide(self, parts: u32) -> Self;
+    fn add(self, other: Self) -> Self;
+    fn sum(values: &[Self]) -> Self {
+        values[1..].iter().fold(values[0], |total, &value| total.add(value))
+    }
+    fn abs_diff(self, other: Self) -> Self;
+    // The value in the integer units priorities are measured in (thousandths)
+    fn cost(self) -> i64;
+    // A small unsigned integer that identifies the value exactly, for packing states into a few words
+    fn packed(self) -> Option<u64> {
+        None
+    }
+}
+
+// Fixed-point thousandths, the search's original representation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fixed(pub i64);
+
+impl Value for Fixed {
+    fn from_f64(value: f64) -> Self {
+        Fixed((value * SCALE) as i64)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE
+    }
+
+    // Shares are computed in floating point and truncated, as the search always has
+    fn divide(self, parts: u32) -> Self {
+        Fixed::from_f64(self.to_f64() / parts as f64)
+    }
+
+    fn add(self, other: Self) -> Self {
+        Fixed::from_f64(self.to_f64() + other.to_f64())
+    }
+
+    // Summed in floating point before truncating, so merging three flows rounds once
+    fn sum(values: &[Self]) -> Self {
+        Fixed::from_f64(values.iter().map(|value| value.to_f64()).sum())
+    }
+
+    fn abs_diff(self, other: Self) -> Self {
+        Fixed((self.0 - other.0).abs())
+    }
+
+    fn cost(self) -> i64 {
+        self.0
+    }
+
+    fn packed(self) -> Option<u64> {
+        u64::try_from(self.0).ok()
+    }
+}
+
+// Plain floating point; states only merge when they are bit-for-bit equal
+#[derive(Clone, Copy, Debug)]
+pub struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl Value for Float {
+    fn from_f64(value: f64) -> Self {
+        Float(value)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0
+    }
+
+    fn divide(self, parts: u32) -> Self {
+        Float(self.0 / parts as f64)
+    }
+
+    fn add(self, other: Self) -> Self {
+        Float(self.0 + other.0)
+    }
+
+    fn abs_diff(self, other: Self) -> Self {
+        Float((self.0 - other.0).abs())
+    }
+
+    fn cost(self) -> i64 {
+        (self.0 * SCALE) as i64
+    }
+}
+
+// Exact fractions, always in lowest terms with a positive denominator. A result whose numerator or
+// denominator no longer fits in 64 bits panics rather than wrapping into some other fraction
+#[cfg(feature = "exact")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i64,
+    denominator: i64,
+}
+
+// Largest denominator `Rational::from_f64` settles on; past it, the nearest fraction below it stands in
+#[cfg(feature = "exact")]
+const MAX_DENOMINATOR: i128 = 1_000_000_000;
+
+#[cfg(feature = "exact")]
+fn narrow(value: i128) -> i64 {
+    i64::try_from(value).unwrap_or_else(|_| panic!("Rational overflow: {} does not fit in 64 bits", value))
+}
+
+#[cfg(feature = "exact")]
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+#[cfg(feature = "exact")]
+impl Rational {
+    pub fn new(numerator: i64, denominator: i64) -> Rational {
+        Rational::reduced(numerator as i128, denominator as i128)
+    }
+
+    pub fn numerator(self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(self) -> i64 {
+        self.denominator
+    }
+
+    fn reduced(numerator: i128, denominator: i128) -> Rational {
+        assert!(denominator != 0, "Rational with zero denominator");
+        let divisor = gcd(numerator, denominator).max(1) * denominator.signum();
+        Rational { numerator: narrow(numerator / divisor), denominator: narrow(denominator / divisor) }
+    }
+}
+
+#[cfg(feature = "exact")]
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "exact")]
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator as i128 * other.denominator as i128).cmp(&(other.numerator as i128 * self.denominator as i128))
+    }
+}
+
+#[cfg(feature = "exact")]
+impl Value for Rational {
+    // The simplest fraction converting back to the same f64, found from the continued fraction, so
+    // decimal rates come out exact and `100/3` comes out as a third
+    fn from_f64(value: f64) -> Self {
+        assert!(value.is_finite(), "Rational from non-finite {}", value);
+        // The last two convergents, starting from the conventional 0/1 and 1/0
+        let (mut previous, mut current) = ((0i128, 1i128), (1i128, 0i128));
+        let mut rest = value;
+        loop {
+            let whole = rest.floor();
+            // A term this large only follows a remainder within rounding of zero
+            if current.1 != 0 && whole > MAX_DENOMINATOR as f64 {
+                break;
+            }
+            let next = (whole as i128 * current.0 + previous.0, whole as i128 * current.1 + previous.1);
+            if next.1 > MAX_DENOMINATOR {
+                break;
+            }
+            (previous, current) = (current, next);
+            if rest == whole || next.0 as f64 / next.1 as f64 == value {
+                break;
+            }
+            rest = 1.0 / (rest - whole);
+        }
+        Rational::reduced(current.0, current.1)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    fn divide(self, parts: u32) -> Self {
+        Rational::reduced(self.numerator as i128, self.denominator as i128 * parts as i128)
+    }
+
+    fn add(self, other: Self) -> Self {
+        Rational::reduced(
+            self.numerator as i128 * other.denominator as i128 + other.numerator as i128 * self.denominator as i128,
+            self.denominator as i128 * other.denominator as i128,
+        )
+    }
+
+    fn abs_diff(self, other: Self) -> Self {
+        let difference = self.add(Rational::reduced(-(other.numerator as i128), other.denominator as i128));
+        Rational::reduced((difference.numerator as i128).abs(), difference.denominator as i128)
+    }
+
+    fn cost(self) -> i64 {
+        narrow(self.numerator as i128 * SCALE as i128 / self.denominator as i128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_round_trip() {
+        for value in [0.0, 12.5, 86.667, 100.0 / 3.0, 1e-9] {
+            assert_eq!(Float::from_f64(value).to_f64(), value);
+        }
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    fn rationals_stay_reduced() {
+        let half = Rational::new(-4, -8);
+        assert_eq!((half.numerator(), half.denominator()), (1, 2));
+        assert_eq!(Rational::new(6, -4), Rational::new(-3, 2));
+        let third = Rational::from_f64(1.0).divide(3);
+        assert_eq!(third.add(third).add(third), Rational::new(1, 1));
+        assert_eq!(Rational::new(1, 3).abs_diff(Rational::new(1, 2)), Rational::new(1, 6));
+        assert_eq!(Rational::new(2, 3).cost(), 666);
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    fn rationals_convert_from_rates_exactly() {
+        for (value, expected) in [(86.667, (86_667, 1000)), (0.1, (1, 10)), (-2.5, (-5, 2)), (100.0 / 3.0, (100, 3)), (7.0, (7, 1))] {
+            let rational = Rational::from_f64(value);
+            assert_eq!((rational.numerator(), rational.denominator()), expected);
+            assert_eq!(rational.to_f64(), value);
+        }
+        // No fraction with a small denominator is exact; the nearest within the bound stands in
+        let pi = Rational::from_f64(core::f64::consts::PI);
+        assert!(pi.denominator() <= MAX_DENOMINATOR as i64);
+        assert!((pi.to_f64() - core::f64::consts::PI).abs() < 1e-15);
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn rational_overflow_is_reported() {
+        let tiny = Rational::new(1, i64::MAX);
+        tiny.add(Rational::new(1, i64::MAX - 1));
+    }
+}
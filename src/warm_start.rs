@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::render;
+use crate::{search_solutions, Objective, SearchOptions, SearchStats, Solution, SolutionSet, SCALE};
+
+// What one search found, kept on disk so the next run on a nearly identical problem need not start cold
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchCache {
+    pub inputs: Vec<f64>,
+    pub target: f64,
+    pub tolerance: f64,
+    pub plans: Vec<Solution>,
+}
+
+impl SearchCache {
+    pub fn new(inputs: Vec<f64>, target: f64, tolerance: f64, plans: &SolutionSet) -> SearchCache {
+        SearchCache { inputs, target, tolerance, plans: plans.iter().cloned().collect() }
+    }
+
+    pub fn to_json(&self) -> Json {
+        let mut fields = BTreeMap::new();
+        fields.insert("inputs".to_string(), Json::Array(self.inputs.iter().map(|&v| Json::Number(v)).collect()));
+        fields.insert("target".to_string(), Json::Number(self.target));
+        fields.insert("tolerance".to_string(), Json::Number(self.tolerance));
+        fields.insert("plans".to_string(), Json::Array(self.plans.iter().map(render::to_json).collect()));
+        Json::Object(fields)
+    }
+
+    pub fn from_json(document: &Json) -> Result<SearchCache, String> {
+        let inputs = document
+            .get("inputs")
+            .and_then(Json::as_array)
+            .ok_or("missing array \"inputs\"")?
+            .iter()
+            .map(|value| value.as_f64().ok_or("non-numeric value in \"inputs\""))
+            .collect::<Result<Vec<f64>, &str>>()?;
+        let plans = document
+            .get("plans")
+            .and_then(Json::as_array)
+            .ok_or("missing array \"plans\"")?
+            .iter()
+            .map(render::from_json)
+            .collect::<Result<Vec<Solution>, String>>()?;
+        Ok(SearchCache {
+            inputs,
+            target: document.get("target").and_then(Json::as_f64).ok_or("missing number \"target\"")?,
+            tolerance: document.get("tolerance").and_then(Json::as_f64).ok_or("missing number \"tolerance\"")?,
+            plans,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<SearchCache, Error> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+        json::parse(&text)
+            .and_then(|document| SearchCache::from_json(&document))
+            .map_err(|message| Error::InvalidFile { path: path.to_string(), message })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.to_json().to_string()).map_err(|source| Error::Io { path: path.to_string(), source })
+    }
+
+    // Cached plans that still work for a problem differing in at most one input rate. The search
+    // only ever splits and merges, so a plan whose steps never drew on the changed input replays unchanged
+    pub fn reusable(&self, inputs: &[f64], target: f64, tolerance: f64) -> Vec<Solution> {
+        let changed = self.inputs.iter().zip(inputs).filter(|(old, new)| old != new).count();
+        if self.inputs.len() != inputs.len() || changed > 1 || self.target != target || self.tolerance != tolerance {
+            return Vec::new();
+        }
+        self.plans.iter().filter_map(|plan| plan.rebased(inputs.to_vec(), tolerance, 1.0 / SCALE)).collect()
+    }
+}
+
+// `search_solutions`, warm-started from a cache: plans that carry over bound the search depth, so it
+// only looks for something shorter, and they are kept if it finds nothing better (or runs out of time)
+pub fn resolve(cache: Option<&SearchCache>, inputs: Vec<f64>, target: f64, tolerance: f64, options: &SearchOptions, limit: usize) -> (SolutionSet, SearchStats, usize) {
+    let reused = cache.map_or_else(Vec::new, |cache| cache.reusable(&inputs, target, tolerance));
+    resolve_with(reused, inputs, target, tolerance, options, limit)
+}
+
+// `resolve` with the carried-over plans already chosen, wherever they came from
+pub fn resolve_with(reused: Vec<Solution>, inputs: Vec<f64>, target: f64, tolerance: f64, options: &SearchOptions, limit: usize) -> (SolutionSet, SearchStats, usize) {
+    let mut options = options.clone();
+    if let Some(shortest) = reused.iter().map(|plan| plan.steps.len()).min() {
+        options.max_depth = options.max_depth.min(shortest);
+    }
+    let (found, stats) = search_solutions(inputs, target, tolerance, &options, limit);
+    let count = reused.len();
+    let mut solutions: SolutionSet = found.into_iter().chain(reused).collect();
+    let objectives: Vec<Objective> = options.objectives.iter().copied().chain([Objective::Steps, Objective::Deviation]).collect();
+    solutions.rank(&objectives);
+    (solutions.into_iter().take(limit).collect(), stats, count)
+}
@@ -0,0 +1,215 @@
+use std::io::{self, Read, Write};
+
+// Appended to a client's key before hashing, as RFC 6455 prescribes
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+// Just enough of the WebSocket protocol for the server to push a live search to a browser: the opening
+// handshake, reading the client's (masked) text messages and writing unfragmented text frames
+
+// The Sec-WebSocket-Accept value answering a client's Sec-WebSocket-Key
+pub fn accept_key(key: &str) -> String {
+    base64(&sha1(format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes()))
+}
+
+// The response switching a connection to the WebSocket protocol
+pub fn handshake(stream: &mut impl Write, key: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )
+}
+
+// The next text message from the client, answering pings on the way. None once the client closes the
+// connection or sends a message longer than `max_length`
+pub fn read_text(reader: &mut impl Read, writer: &mut impl Write, max_length: usize) -> io::Result<Option<String>> {
+    loop {
+        let mut header = [0; 2];
+        reader.read_exact(&mut header)?;
+        let opcode = header[0] & 0x0f;
+        let length = match header[1] & 0x7f {
+            126 => {
+                let mut bytes = [0; 2];
+                reader.read_exact(&mut bytes)?;
+                u16::from_be_bytes(bytes) as u64
+            }
+            127 => {
+                let mut bytes = [0; 8];
+                reader.read_exact(&mut bytes)?;
+                u64::from_be_bytes(bytes)
+            }
+            length => length as u64,
+        };
+        if length > max_length as u64 {
+            return Ok(None);
+        }
+        // Clients always mask their frames
+        let mut mask = [0; 4];
+        if header[1] & 0x80 != 0 {
+            reader.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0; length as usize];
+        reader.read_exact(&mut payload)?;
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+        match opcode {
+            OPCODE_TEXT => return Ok(Some(String::from_utf8_lossy(&payload).into_owned())),
+            OPCODE_PING => write_frame(writer, OPCODE_PONG, &payload)?,
+            OPCODE_CLOSE => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+pub fn write_text(writer: &mut impl Write, text: &str) -> io::Result<()> {
+    write_frame(writer, OPCODE_TEXT, text.as_bytes())
+}
+
+// Close the connection normally (status 1000)
+pub fn write_close(writer: &mut impl Write) -> io::Result<()> {
+    write_frame(writer, OPCODE_CLOSE, &1000u16.to_be_bytes())
+}
+
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        length @ 0..=125 => frame.push(length as u8),
+        length @ 126..=0xffff => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+fn sha1(message: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+    let mut padded = message.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(message.len() as u64 * 8).to_be_bytes());
+    for block in padded.chunks(64) {
+        let mut words = [0u32; 80];
+        for (index, word) in block.chunks(4).enumerate() {
+            words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..80 {
+            words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, &word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            (e, d, c, b, a) = (d, c, b.rotate_left(30), a, temp);
+        }
+        for (total, part) in state.iter_mut().zip([a, b, c, d, e]) {
+            *total = total.wrapping_add(part);
+        }
+    }
+    let mut digest = [0; 20];
+    for (chunk, word) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for position in 0..4 {
+            if position <= chunk.len() {
+                text.push(ALPHABET[(group >> (18 - 6 * position) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // A frame as a client sends it: masked with `mask`
+    fn client_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        write_frame(&mut frame, opcode, payload).unwrap();
+        let start = frame.len() - payload.len();
+        frame[1] |= 0x80;
+        let masked: Vec<u8> = payload.iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]).collect();
+        frame.truncate(start);
+        frame.extend_from_slice(&mask);
+        frame.extend_from_slice(&masked);
+        frame
+    }
+
+    #[test]
+    fn answers_the_rfc_sample_key() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        assert_eq!(accept_key(" dGhlIHNhbXBsZSBub25jZQ==\r"), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn writes_each_length_encoding() {
+        for (length, header) in [
+            (0, vec![0x81, 0]),
+            (125, vec![0x81, 125]),
+            (126, vec![0x81, 126, 0, 126]),
+            (0xffff, vec![0x81, 126, 0xff, 0xff]),
+            (0x10000, vec![0x81, 127, 0, 0, 0, 0, 0, 1, 0, 0]),
+        ] {
+            let mut frame = Vec::new();
+            write_text(&mut frame, &"a".repeat(length)).unwrap();
+            assert_eq!(frame[..header.len()], header[..], "{}", length);
+            assert_eq!(frame.len(), header.len() + length);
+        }
+        let mut frame = Vec::new();
+        write_close(&mut frame).unwrap();
+        assert_eq!(frame, [0x88, 2, 0x03, 0xe8]);
+    }
+
+    #[test]
+    fn reads_masked_frames() {
+        // The masked "Hello" from RFC 6455, section 5.7
+        let sample = [0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58];
+        let mut replies = Vec::new();
+        assert_eq!(read_text(&mut Cursor::new(sample), &mut replies, 1024).unwrap().as_deref(), Some("Hello"));
+
+        let long = "x".repeat(70_000);
+        let mut stream = client_frame(OPCODE_PING, b"hi", [1, 2, 3, 4]);
+        stream.extend(client_frame(OPCODE_TEXT, long.as_bytes(), [9, 8, 7, 6]));
+        stream.extend(client_frame(OPCODE_CLOSE, &1000u16.to_be_bytes(), [5, 5, 5, 5]));
+        let mut stream = Cursor::new(stream);
+        assert_eq!(read_text(&mut stream, &mut replies, 100_000).unwrap(), Some(long));
+        // The ping was answered with an unmasked pong carrying its payload
+        assert_eq!(replies, [0x8a, 2, b'h', b'i']);
+        assert_eq!(read_text(&mut stream, &mut replies, 100_000).unwrap(), None);
+        assert!(read_text(&mut stream, &mut replies, 100_000).is_err());
+
+        let oversized = client_frame(OPCODE_TEXT, &[b'y'; 200], [1, 1, 1, 1]);
+        assert_eq!(read_text(&mut Cursor::new(oversized), &mut replies, 199).unwrap(), None);
+    }
+}